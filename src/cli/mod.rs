@@ -1,4 +1,6 @@
-use clap::{command, Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(
@@ -20,11 +22,11 @@ pub enum Commands {
 
     /// Perform graph operations on SQL schemas
     #[command(alias = "G")]
-    Graph(GraphArgs),
+    Graph(Box<GraphArgs>),
 
     /// Handle CSV files
     #[command()]
-    Csv(CsvArgs),
+    Csv(Box<CsvArgs>),
 
     /// Show path to config directory
     #[command()]
@@ -38,12 +40,52 @@ pub struct InitArgs {
     pub force: bool,
 }
 
+/// Layout engines Graphviz's `dot` command accepts with `-K`.
+pub const GRAPHVIZ_ENGINES: [&str; 8] = [
+    "dot",
+    "neato",
+    "fdp",
+    "sfdp",
+    "circo",
+    "twopi",
+    "osage",
+    "patchwork",
+];
+
 #[derive(Args)]
 pub struct GraphArgs {
     /// Force regeneration of the graph
     #[arg(short, long, alias = "regen")]
     pub regenerate: bool,
 
+    /// Graphviz layout engine, overriding the configured one
+    #[arg(long, global = true, value_parser = GRAPHVIZ_ENGINES)]
+    pub engine: Option<String>,
+
+    /// Direction of the diagram layout, overriding the configured one
+    #[arg(long, global = true, value_parser = ["TB", "LR", "BT", "RL"])]
+    pub rankdir: Option<String>,
+
+    /// Shape of tables in diagrams, overriding the configured one
+    #[arg(long, global = true, value_parser = ["record", "Mrecord"])]
+    pub node_shape: Option<String>,
+
+    /// Font of diagram labels, overriding the configured one
+    #[arg(long, global = true)]
+    pub font: Option<String>,
+
+    /// Space between tables of the same rank in inches, overriding the configured one
+    #[arg(long, global = true)]
+    pub nodesep: Option<f64>,
+
+    /// Space between ranks in inches, overriding the configured one
+    #[arg(long, global = true)]
+    pub ranksep: Option<f64>,
+
+    /// Print the path of rendered diagrams instead of opening them
+    #[arg(long, global = true)]
+    pub no_open: bool,
+
     #[command(subcommand)]
     pub subcommand: Option<GraphSubcommands>,
 }
@@ -54,12 +96,175 @@ pub enum JoinType {
     Left,
     Right,
     Full,
+    /// Match each left row to the nearest preceding right row on the last key column
+    Asof,
+}
+
+/// How `csv join` finds the rows that match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JoinStrategy {
+    /// Hold the right file in memory
+    #[default]
+    Hash,
+    /// Partition both files into temporary files by key, for right files too large for memory
+    Spill,
+    /// Stream both files, which must already be sorted on the key
+    Merge,
+}
+
+/// How `csv pivot` combines several values landing in the same cell.
+#[derive(Debug, Clone, ValueEnum)]
+pub enum PivotAggregate {
+    First,
+    Last,
+    Sum,
+    Count,
+}
+
+/// Which occurrence `csv dedupe` keeps when rows share a key.
+#[derive(Debug, Clone, ValueEnum)]
+pub enum DedupeKeep {
+    First,
+    Last,
+}
+
+/// What `csv cast` and `csv dateformat` do with a value that cannot be converted.
+#[derive(Debug, Clone, ValueEnum)]
+pub enum CastOnError {
+    /// Drop the row
+    Skip,
+    /// Empty the value
+    Null,
+    /// Stop with an error
+    Fail,
+}
+
+/// Line terminator written after each CSV record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Unix line endings
+    #[default]
+    Lf,
+    /// Windows line endings
+    Crlf,
+    /// Use the line ending of the input file's header
+    Preserve,
+}
+
+/// Format a command writes its rows in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Comma-separated values using the configured dialect
+    #[default]
+    Csv,
+    /// One JSON array of objects keyed by header name
+    Json,
+    /// One JSON object per line, keyed by header name
+    Jsonl,
+    /// Standalone HTML page with a styled table
+    Html,
+    /// LaTeX tabular for reports
+    Latex,
+    /// Parquet with inferred column types (needs the `parquet` feature)
+    Parquet,
+    /// Arrow IPC stream with inferred column types (needs the `arrow` feature)
+    Arrow,
+}
+
+/// What to do with a row whose field count differs from the header's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RaggedPolicy {
+    /// Stop with an error
+    #[default]
+    Error,
+    /// Fill missing fields with empty values and drop extra fields
+    Pad,
+    /// Drop extra fields from long rows and skip short rows
+    Truncate,
+    /// Skip the row
+    Skip,
+}
+
+/// What to do when a header names the same column more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateHeaders {
+    /// Suffix repeated names with their occurrence number (`name`, `name_2`)
+    #[default]
+    Rename,
+    /// Stop with an error
+    Error,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
 pub enum DisplayType {
     Png,
     Pdf,
+    /// Scalable image whose tables are linkable anchors, e.g. `graph.svg#users`
+    Svg,
+    /// Tables and their foreign keys printed in the terminal, without an image file
+    Ascii,
+}
+
+/// Format `graph export` writes the graph in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Tables, columns, primary keys and foreign keys as documented JSON
+    #[default]
+    Json,
+    /// Graphviz DOT source, as rendered by `graph display`
+    Dot,
+}
+
+/// Format `graph mst` writes the tree in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MstFormat {
+    /// Render and open a PNG image
+    #[default]
+    Png,
+    /// Render and open a PDF document
+    Pdf,
+    /// Render and open an SVG image
+    Svg,
+    /// Graphviz DOT source
+    Dot,
+    /// Tables and foreign keys in the `graph export` JSON layout
+    Json,
+    /// One foreign key per line
+    Text,
+}
+
+/// Format `graph report` writes the report in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Headings, tables and lists for reading or pasting into a review
+    #[default]
+    Markdown,
+    /// The same metrics as JSON
+    Json,
+}
+
+/// What `shortest-path` and `graph join` minimize when choosing a path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PathWeight {
+    /// The number of joins, preferring smaller tables between equally long paths
+    #[default]
+    Joins,
+    /// The rows of the tables joined, as counted by `graph count-rows`
+    Rows,
+}
+
+/// The order in which `graph join` joins the tables it connects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum JoinOrder {
+    /// Along the path, from the first table given
+    #[default]
+    Path,
+    /// From the smallest table, each time adding the smallest one it connects to, by
+    /// counted rows or else by file size; inner joins only
+    Size,
 }
 
 #[derive(Subcommand)]
@@ -68,9 +273,15 @@ pub enum GraphSubcommands {
     #[command()]
     Create {
         /// Path to SQL schema file
-        #[arg()]
-        schema: String,
-        /// Output format (dot, json, text)
+        #[arg(required_unless_present = "pg")]
+        schema: Option<String>,
+        /// Read the schema from a live PostgreSQL database instead, e.g. postgres://user@host/db
+        #[arg(long, value_name = "URL", conflicts_with = "schema")]
+        pg: Option<String>,
+        /// PostgreSQL schema to read tables from
+        #[arg(long, value_name = "NAME", default_value = "public", requires = "pg")]
+        pg_schema: String,
+        /// Output format (png, pdf, svg, ascii)
         #[arg(short, long, default_value = "png")]
         format: DisplayType,
     },
@@ -84,41 +295,339 @@ pub enum GraphSubcommands {
         /// Destination table
         #[arg()]
         to: String,
+        /// Comma-separated tables the path must pass through, in order
+        #[arg(long, value_delimiter = ',')]
+        via: Vec<String>,
+        /// What the path minimizes
+        #[arg(long, default_value = "joins")]
+        weight: PathWeight,
+        /// Also render the graph with the path highlighted (png, pdf, svg, ascii)
+        #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "png")]
+        render: Option<DisplayType>,
+    },
+
+    /// Find the fewest foreign keys connecting a set of tables, the basis for joining them
+    #[command(alias = "jt")]
+    JoinTree {
+        /// Tables to connect
+        #[arg(required = true, num_args = 2..)]
+        tables: Vec<String>,
+    },
+
+    /// List alternative paths between two tables, shortest first
+    #[command()]
+    Paths {
+        /// Source table
+        #[arg()]
+        from: String,
+        /// Destination table
+        #[arg()]
+        to: String,
+        /// Number of paths to list
+        #[arg(long, default_value_t = 5)]
+        max: usize,
+        /// Longest path to consider, in joins
+        #[arg(long, value_name = "N")]
+        max_len: Option<usize>,
+    },
+
+    /// Find chains of tables matching a pattern such as `users -> * -> invoices`
+    #[command()]
+    Query {
+        /// Tables separated by `->`, where `*` matches any characters of a name and `**`
+        /// any number of tables
+        #[arg()]
+        pattern: String,
+        /// Number of chains to list
+        #[arg(long, default_value_t = 20)]
+        max: usize,
     },
 
     /// Create a minimum spanning tree from the schema
     #[command()]
-    Mst,
+    Mst {
+        /// Output format: a rendered image, or dot, json or text written to stdout
+        #[arg(short, long, default_value = "png")]
+        format: MstFormat,
+    },
+
+    /// List groups of tables connected by foreign keys
+    #[command()]
+    Components,
+
+    /// Report circular foreign key chains
+    #[command()]
+    Cycles,
+
+    /// Report the tables, columns and foreign keys that differ between two schemas
+    #[command()]
+    Diff {
+        /// Old SQL schema file
+        #[arg()]
+        old: PathBuf,
+        /// New SQL schema file
+        #[arg()]
+        new: PathBuf,
+        /// Also render the new schema with additions in green and removals in red (png, pdf, svg)
+        #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "png")]
+        render: Option<DisplayType>,
+    },
+
+    /// List the tables in the graph with their column count, primary key and foreign keys
+    #[command()]
+    Tables,
+
+    /// Describe a table: its columns, primary key, foreign keys and incoming references
+    #[command()]
+    Info {
+        /// Table to describe
+        #[arg()]
+        table: String,
+    },
+
+    /// List every table and column that references a table
+    #[command()]
+    ReferencedBy {
+        /// Referenced table
+        #[arg()]
+        table: String,
+    },
+
+    /// List tables with no foreign keys to or from other tables
+    #[command()]
+    Orphans,
+
+    /// List the tables directly connected to a table, with their join columns
+    #[command()]
+    Neighbors {
+        /// Table to list the neighbors of
+        #[arg()]
+        table: String,
+    },
+
+    /// Rank tables by how many tables they join and how many shortest paths cross them
+    #[command()]
+    Hubs {
+        /// Number of tables to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+
+    /// Summarize the most referenced tables, longest dependency chains, average path
+    /// length and widest tables
+    #[command()]
+    Report {
+        /// Output format
+        #[arg(short, long, default_value = "markdown")]
+        format: ReportFormat,
+        /// Number of tables and chains to list in each section
+        #[arg(long, default_value_t = 5)]
+        top: usize,
+    },
+
+    /// Count the rows of each table's file in the source path and cache them in the graph
+    #[command()]
+    CountRows,
+
+    /// Write the graph to stdout for other tools
+    #[command()]
+    Export {
+        /// Output format (json, dot)
+        #[arg(short, long, default_value = "json")]
+        format: ExportFormat,
+    },
 
     /// Display the graph structure
     #[command()]
     Display {
-        /// Output format (png, pdf)
+        /// Output format (png, pdf, svg, ascii)
         #[arg(short, long, default_value = "png")]
         format: DisplayType,
     },
 
     /// Join two CSV files
     #[command()]
-    Join {
-        /// First CSV file
-        #[arg()]
-        left_table: String,
-        /// Second CSV file
-        #[arg()]
-        right_table: String,
-        /// Join type (inner, left, right, full)
-        #[arg(short, long, default_value = "inner")]
-        r#type: JoinType,
-    },
+    Join(GraphJoinArgs),
+
+    /// Show how `graph join` would join tables: the path, keys, header and sizes,
+    /// without reading any rows
+    #[command()]
+    Explain(GraphJoinArgs),
+}
+
+/// Arguments of `graph join`.
+#[derive(Args)]
+pub struct GraphJoinArgs {
+    /// Tables to join: two along the path between them, more along the join tree
+    /// connecting them all
+    #[arg(num_args = 2.., required_unless_present = "path")]
+    pub tables: Vec<String>,
+    /// Join type (inner, left, right, full)
+    #[arg(short, long, default_value = "inner")]
+    pub r#type: JoinType,
+    /// Compare key values without regard to case
+    #[arg(long)]
+    pub ignore_case: bool,
+    /// Comma-separated output columns to write, in order
+    #[arg(long, value_delimiter = ',')]
+    pub select: Vec<String>,
+    /// Keep only rows matching a condition on one table, e.g. "orders.status = 'paid'",
+    /// applied before that table is joined
+    #[arg(long, value_name = "CONDITION")]
+    pub r#where: Vec<String>,
+    /// Comma-separated tables the join path must pass through, in order
+    #[arg(long, value_delimiter = ',')]
+    pub via: Vec<String>,
+    /// Comma-separated tables to join exactly in this order, instead of a found path
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = ["tables", "via", "weight"]
+    )]
+    pub path: Vec<String>,
+    /// What the join path minimizes
+    #[arg(long, default_value = "joins")]
+    pub weight: PathWeight,
+    /// The order in which the tables are joined
+    #[arg(long, default_value = "path", conflicts_with = "path")]
+    pub order: JoinOrder,
+    /// Also render the graph with the join path highlighted (png, pdf, svg, ascii)
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "png")]
+    pub render: Option<DisplayType>,
+    /// Format of the rows written
+    #[arg(long, default_value = "csv")]
+    pub format: OutputFormat,
+    /// Copy the joined rows into this PostgreSQL table, using `postgres_url` from the config
+    #[arg(long, value_name = "TABLE", conflicts_with = "format")]
+    pub copy_to: Option<String>,
+    /// Write the joined rows to standard output instead of the configured output file
+    #[arg(long, conflicts_with = "copy_to")]
+    pub stdout: bool,
+    /// Name every output column after its table, as in `users.name`, which `--select`
+    /// then uses too
+    #[arg(long)]
+    pub prefix_columns: bool,
+    /// Check that every table's file exists, matches the schema and has the join columns,
+    /// then exit without joining
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Join every table again instead of resuming from the results of earlier runs cached
+    /// in .csvgraph/join-cache
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 #[derive(Args)]
 pub struct CsvArgs {
+    /// Quote character, overriding the configured one
+    #[arg(long, global = true)]
+    pub quote: Option<char>,
+
+    /// Escape quotes inside quoted fields with this character instead of doubling them
+    #[arg(long, global = true)]
+    pub escape: Option<char>,
+
+    /// Quote every output field
+    #[arg(long, global = true)]
+    pub always_quote: bool,
+
+    /// Line ending of output records, overriding the configured one
+    #[arg(long, global = true)]
+    pub line_ending: Option<LineEnding>,
+
+    /// How to handle rows with more or fewer fields than the header
+    #[arg(long, global = true)]
+    pub on_ragged: Option<RaggedPolicy>,
+
+    /// How to handle header names that appear more than once
+    #[arg(long, global = true)]
+    pub on_duplicate_headers: Option<DuplicateHeaders>,
+
+    /// Write results to this file instead of stdout, relative to the configured CSV
+    /// output folder
+    #[arg(long, global = true)]
+    pub output: Option<PathBuf>,
+
+    /// Format of the rows written
+    #[arg(long, global = true, default_value = "csv")]
+    pub format: OutputFormat,
+
+    /// Show at most this many rows in HTML output
+    #[arg(long, global = true, value_name = "N")]
+    pub max_rows: Option<usize>,
+
     #[command(subcommand)]
     pub subcommand: CsvSubcommands,
 }
 
+/// Arguments of `csv join`, boxed to keep `CsvSubcommands` small.
+#[derive(Args)]
+pub struct JoinArgs {
+    /// First CSV file
+    #[arg()]
+    pub file1: String,
+    /// Second CSV file
+    #[arg()]
+    pub file2: String,
+    /// Left table column
+    #[arg(requires = "right_column")]
+    pub left_column: Option<String>,
+    /// Right table column
+    #[arg()]
+    pub right_column: Option<String>,
+    /// Key column pair as LEFT=RIGHT, or a column named the same on both sides;
+    /// repeat to join on several columns
+    #[arg(long = "on", value_name = "LEFT=RIGHT")]
+    pub on: Vec<String>,
+    /// Compare key values as numbers when they parse as one, so 001 matches 1
+    #[arg(long)]
+    pub numeric_keys: bool,
+    /// Compare key values without regard to case
+    #[arg(long)]
+    pub ignore_case: bool,
+    /// Suffix for left columns whose name also appears in the right file
+    #[arg(long)]
+    pub lsuffix: Option<String>,
+    /// Suffix for right columns whose name also appears in the left file
+    /// [default: _<FILE2>]
+    #[arg(long)]
+    pub rsuffix: Option<String>,
+    /// How matching rows are found
+    #[arg(long, default_value = "hash")]
+    pub strategy: JoinStrategy,
+    /// Number of temporary partitions per file for the spill strategy
+    #[arg(long, default_value = "64")]
+    pub partitions: usize,
+    /// Comma-separated output columns to write, in order
+    #[arg(long, value_delimiter = ',')]
+    pub select: Vec<String>,
+    /// Largest gap an asof join accepts, in seconds for dates and times
+    #[arg(long)]
+    pub tolerance: Option<f64>,
+    /// Fuzzy join: match keys at most this many character edits apart
+    #[arg(long, conflicts_with = "min_similarity")]
+    pub max_distance: Option<usize>,
+    /// Fuzzy join: match keys with at least this similarity, from 0 to 1
+    #[arg(long)]
+    pub min_similarity: Option<f64>,
+    /// Let empty key values match each other instead of matching nothing
+    #[arg(long)]
+    pub null_keys_match: bool,
+    /// Prefix every left column as ALIAS.column [default for a self-join: left]
+    #[arg(long)]
+    pub left_alias: Option<String>,
+    /// Prefix every right column as ALIAS.column [default for a self-join: right]
+    #[arg(long)]
+    pub right_alias: Option<String>,
+    /// Range condition LEFT=START,END matching rows whose left column lies between
+    /// the right start and end columns, inclusive
+    #[arg(long, value_name = "LEFT=START,END")]
+    pub between: Option<String>,
+    /// Join type (inner, left, right, full, asof)
+    #[arg(short, long, default_value = "inner")]
+    pub r#type: JoinType,
+}
+
 #[derive(Subcommand)]
 pub enum CsvSubcommands {
     /// Display the first n rows of a CSV file
@@ -145,23 +654,7 @@ pub enum CsvSubcommands {
 
     /// Join two CSV files
     #[command()]
-    Join {
-        /// First CSV file
-        #[arg()]
-        file1: String,
-        /// Second CSV file
-        #[arg()]
-        file2: String,
-        /// Left table column
-        #[arg()]
-        left_column: String,
-        /// Right table column
-        #[arg()]
-        right_column: String,
-        /// Join type (inner, left, right, full)
-        #[arg(short, long, default_value = "inner")]
-        r#type: JoinType,
-    },
+    Join(Box<JoinArgs>),
 
     /// Concatenate CSV files vertically
     #[command()]
@@ -171,6 +664,28 @@ pub enum CsvSubcommands {
         files: Vec<String>,
     },
 
+    /// Merge CSV files already sorted on a key into one sorted file
+    #[command()]
+    Merge {
+        /// Column the files are sorted on
+        #[arg(short, long)]
+        key: String,
+        /// CSV files to merge
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
+    /// Paste the rows of two CSV files side by side
+    #[command()]
+    Hstack {
+        /// Left CSV file
+        #[arg()]
+        file1: String,
+        /// Right CSV file
+        #[arg()]
+        file2: String,
+    },
+
     /// Select specific columns from a CSV file
     #[command()]
     Select {
@@ -182,6 +697,17 @@ pub enum CsvSubcommands {
         columns: Vec<String>,
     },
 
+    /// Move columns to the front of a CSV file in the given order
+    #[command()]
+    Reorder {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Columns in their new order; unlisted columns follow
+        #[arg(required = true)]
+        columns: Vec<String>,
+    },
+
     /// Drop (Remove) specific columns from a CSV file
     #[command()]
     Drop {
@@ -193,6 +719,313 @@ pub enum CsvSubcommands {
         #[arg()]
         columns: Vec<String>,
     },
+
+    /// Append a column computed from an expression
+    #[command()]
+    Derive {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Column definition, e.g. "total = price * qty"
+        #[arg()]
+        expression: String,
+    },
+
+    /// Prepend a row number column
+    #[command()]
+    Index {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Name of the row number column
+        #[arg(short, long, default_value = "row_id")]
+        name: String,
+        /// First row number
+        #[arg(short, long, default_value_t = 1, allow_negative_numbers = true)]
+        start: i64,
+    },
+
+    /// Keep only the rows matching an expression
+    #[command()]
+    Filter {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Condition, e.g. "status = 'paid' and total > 100"
+        #[arg()]
+        expression: String,
+    },
+
+    /// Convert column values to a type, e.g. "price:float"
+    #[command()]
+    Cast {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Casts as <COLUMN>:<TYPE>, where type is int, float, date, bool or string
+        #[arg(required = true)]
+        casts: Vec<String>,
+        /// How to handle values that fail to convert
+        #[arg(short, long, default_value = "fail")]
+        on_error: CastOnError,
+    },
+
+    /// Reformat the dates in a column, e.g. from "%d/%m/%Y" to "%Y-%m-%d"
+    #[command()]
+    Dateformat {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Column holding the dates
+        #[arg(short, long)]
+        column: String,
+        /// strftime format the dates are currently in
+        #[arg(short, long)]
+        from: String,
+        /// strftime format to write the dates in
+        #[arg(short, long, default_value = "%Y-%m-%d")]
+        to: String,
+        /// How to handle values that do not match the format
+        #[arg(short, long, default_value = "fail")]
+        on_error: CastOnError,
+    },
+
+    /// Infer the type and nullability of each column
+    #[command()]
+    Schema {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Number of rows to scan (defaults to the whole file)
+        #[arg(short, long)]
+        sample: Option<usize>,
+    },
+
+    /// Show summary statistics for each column
+    #[command()]
+    Describe {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+    },
+
+    /// Count the data rows of a CSV file
+    #[command()]
+    Count {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Count rows per distinct value of this column
+        #[arg(short, long)]
+        per_value: Option<String>,
+    },
+
+    /// Show the most common values of a column
+    #[command()]
+    Freq {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Column to profile
+        #[arg()]
+        column: String,
+        /// Number of values to display
+        #[arg(short, long, default_value = "10")]
+        top: usize,
+    },
+
+    /// Split a CSV file into several files
+    #[command(group(ArgGroup::new("mode").required(true).args(["rows", "by"])))]
+    Split {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Number of rows per output file
+        #[arg(short, long)]
+        rows: Option<usize>,
+        /// Write one file per distinct value of this column
+        #[arg(short, long)]
+        by: Option<String>,
+        /// Output directory (defaults to the configured csv_output_path)
+        #[arg(short, long)]
+        dir: Option<String>,
+    },
+
+    /// Reshape a long table into a wide one
+    #[command()]
+    Pivot {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Columns identifying each output row
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        index: Vec<String>,
+        /// Column whose values become the new column names
+        #[arg(short, long)]
+        key: String,
+        /// Column holding the cell values
+        #[arg(short = 'v', long)]
+        value: String,
+        /// How to combine several values for the same cell (first, last, sum, count)
+        #[arg(short, long, default_value = "first")]
+        agg: PivotAggregate,
+    },
+
+    /// Reshape a wide table into key/value rows
+    #[command(alias = "unpivot")]
+    Melt {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Columns repeated on every output row
+        #[arg(short, long, value_delimiter = ',')]
+        id_cols: Vec<String>,
+        /// Columns to unpivot (defaults to every non-id column)
+        #[arg(short = 'c', long, value_delimiter = ',')]
+        value_cols: Vec<String>,
+        /// Name of the output column holding the original column names
+        #[arg(long, default_value = "variable")]
+        var_name: String,
+        /// Name of the output column holding the values
+        #[arg(long, default_value = "value")]
+        value_name: String,
+    },
+
+    /// Convert the letter case of column values
+    #[command(group(ArgGroup::new("case").required(true).args(["upper", "lower"])))]
+    Transform {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Columns to transform
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        column: Vec<String>,
+        /// Convert values to upper case
+        #[arg(long)]
+        upper: bool,
+        /// Convert values to lower case
+        #[arg(long)]
+        lower: bool,
+    },
+
+    /// Split a multi-valued column into one row per value
+    #[command()]
+    Explode {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Column holding the delimited values
+        #[arg(short, long)]
+        column: String,
+        /// Separator between values
+        #[arg(short, long, default_value = ";")]
+        sep: String,
+    },
+
+    /// Trim and collapse whitespace in headers and values
+    #[command()]
+    Clean {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Also convert header names to snake_case
+        #[arg(short, long)]
+        snake_case: bool,
+    },
+
+    /// Output a range of rows
+    #[command()]
+    Slice {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Number of rows to skip
+        #[arg(short, long, default_value_t = 0)]
+        offset: usize,
+        /// Maximum number of rows to output (defaults to all remaining rows)
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+
+    /// Remove duplicate rows, or rows sharing a key
+    #[command()]
+    Dedupe {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Columns identifying a row (defaults to the whole row)
+        #[arg(short, long, value_delimiter = ',')]
+        key: Vec<String>,
+        /// Which occurrence of a duplicate to keep (first, last)
+        #[arg(long, default_value = "first")]
+        keep: DedupeKeep,
+    },
+
+    /// Output the rows of a CSV file in reverse order
+    #[command()]
+    Reverse {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+    },
+
+    /// Output the rows of a CSV file in random order
+    #[command()]
+    Shuffle {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Seed for a reproducible order
+        #[arg(short, long)]
+        seed: Option<u64>,
+    },
+
+    /// Print the rows matching a regular expression
+    #[command()]
+    Grep {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Regular expression to search for
+        #[arg()]
+        pattern: String,
+        /// Only search this column
+        #[arg(short, long)]
+        column: Option<String>,
+        /// Print the number of matching rows instead of the rows
+        #[arg(long)]
+        count: bool,
+    },
+
+    /// Substitute regex matches within a column
+    #[command()]
+    Replace {
+        /// Input CSV file
+        #[arg()]
+        file: String,
+        /// Column to apply the substitution to
+        #[arg(short, long)]
+        column: String,
+        /// Regular expression to replace
+        #[arg(short, long)]
+        pattern: String,
+        /// Replacement text, may reference capture groups such as $1
+        #[arg(short, long = "with")]
+        with: String,
+    },
+    /// Import CSV files into tables of a SQLite database, one table per file
+    #[command()]
+    ToSqlite {
+        /// SQLite database file, created if missing
+        #[arg()]
+        db: String,
+        /// CSV files to import, each into a table named after the file
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Replace tables that already exist
+        #[arg(long)]
+        replace: bool,
+    },
 }
 
 pub fn parse_args() -> Cli {
@@ -1,38 +1,232 @@
-use crate::cli::{CsvArgs, CsvSubcommands, JoinType};
-use crate::config::{create_config_folder, read_config, Config};
-use crate::csv::{self, DataFrame};
+use crate::cli::{
+    CastOnError, CsvArgs, CsvSubcommands, DedupeKeep, JoinArgs, JoinType, OutputFormat,
+    PivotAggregate,
+};
+use crate::config::{self, create_config_folder, read_config, Config};
+use crate::csv::{
+    self, Compression, DataFrame, FuzzyThreshold, JoinOptions, RangeCondition, TextCase,
+};
+use crate::expr;
 use crate::utils::print_info;
+use prettytable::{format, row, Table};
+use regex::Regex;
+use std::cell::RefCell;
 use std::error::Error;
-use std::fs::File;
-use std::io::{self, BufReader, BufWriter, Read};
-use std::path::Path;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use tempfile::NamedTempFile;
 
 /// Execute CSV operations based on command line arguments.
 pub fn execute(args: &CsvArgs) -> Result<(), Box<dyn Error>> {
     let config_dir = create_config_folder()?;
-    let config: Config = read_config(&config_dir)?;
+    let mut config: Config = read_config(&config_dir)?;
 
-    match &args.subcommand {
-        CsvSubcommands::Head { file, lines } => handle_head(&config, file, *lines),
-        CsvSubcommands::Tail { file, lines } => handle_tail(&config, file, *lines),
-        CsvSubcommands::Concat { files } => handle_concat(&config, files),
-        CsvSubcommands::Drop { file, columns } => handle_drop(&config, file, columns),
-        CsvSubcommands::Select { file, columns } => handle_select(&config, file, columns),
-        CsvSubcommands::Join {
-            file1,
-            file2,
-            left_column,
-            right_column,
-            r#type,
-        } => handle_join(&config, file1, file2, left_column, right_column, r#type),
+    // Command line flags take precedence over the configured dialect.
+    if let Some(quote) = args.quote {
+        config.csv_dialect.quote = quote;
+    }
+    if let Some(escape) = args.escape {
+        config.csv_dialect.escape = Some(escape);
+    }
+    config.csv_dialect.always_quote |= args.always_quote;
+    if let Some(line_ending) = args.line_ending {
+        config.csv_dialect.line_ending = line_ending;
     }
+    if let Some(on_ragged) = args.on_ragged {
+        config.csv_dialect.on_ragged = on_ragged;
+    }
+    if let Some(on_duplicate_headers) = args.on_duplicate_headers {
+        config.csv_dialect.on_duplicate_headers = on_duplicate_headers;
+    }
+    config.csv_dialect.validate()?;
+
+    if args.output.is_some() && matches!(args.subcommand, CsvSubcommands::Split { .. }) {
+        return Err("split writes one file per part; use --dir instead of --output".into());
+    }
+    let output = Output::new(&config, args.output.as_deref(), args.format, args.max_rows);
+    match &args.subcommand {
+        CsvSubcommands::Head { file, lines } => handle_head(&config, &output, file, *lines),
+        CsvSubcommands::Tail { file, lines } => handle_tail(&config, &output, file, *lines),
+        CsvSubcommands::Concat { files } => handle_concat(&config, &output, files),
+        CsvSubcommands::Merge { key, files } => handle_merge(&config, &output, key, files),
+        CsvSubcommands::Hstack { file1, file2 } => handle_hstack(&config, &output, file1, file2),
+        CsvSubcommands::Drop { file, columns } => handle_drop(&config, &output, file, columns),
+        CsvSubcommands::Select { file, columns } => handle_select(&config, &output, file, columns),
+        CsvSubcommands::Reorder { file, columns } => {
+            handle_reorder(&config, &output, file, columns)
+        }
+        CsvSubcommands::Derive { file, expression } => {
+            handle_derive(&config, &output, file, expression)
+        }
+        CsvSubcommands::Index { file, name, start } => {
+            handle_index(&config, &output, file, name, *start)
+        }
+        CsvSubcommands::Filter { file, expression } => {
+            handle_filter(&config, &output, file, expression)
+        }
+        CsvSubcommands::Cast {
+            file,
+            casts,
+            on_error,
+        } => handle_cast(&config, &output, file, casts, on_error),
+        CsvSubcommands::Dateformat {
+            file,
+            column,
+            from,
+            to,
+            on_error,
+        } => handle_dateformat(&config, &output, file, column, from, to, on_error),
+        CsvSubcommands::Schema { file, sample } => handle_schema(&config, &output, file, *sample),
+        CsvSubcommands::Describe { file } => handle_describe(&config, &output, file),
+        CsvSubcommands::Count { file, per_value } => {
+            handle_count(&config, &output, file, per_value.as_deref())
+        }
+        CsvSubcommands::Freq { file, column, top } => {
+            handle_freq(&config, &output, file, column, *top)
+        }
+        CsvSubcommands::Split {
+            file,
+            rows,
+            by,
+            dir,
+        } => handle_split(&config, file, *rows, by.as_deref(), dir.as_deref()),
+        CsvSubcommands::Pivot {
+            file,
+            index,
+            key,
+            value,
+            agg,
+        } => handle_pivot(&config, &output, file, index, key, value, agg),
+        CsvSubcommands::Melt {
+            file,
+            id_cols,
+            value_cols,
+            var_name,
+            value_name,
+        } => handle_melt(
+            &config, &output, file, id_cols, value_cols, var_name, value_name,
+        ),
+        CsvSubcommands::Transform {
+            file,
+            column,
+            upper,
+            ..
+        } => {
+            let case = if *upper {
+                TextCase::Upper
+            } else {
+                TextCase::Lower
+            };
+            handle_transform(&config, &output, file, column, case)
+        }
+        CsvSubcommands::Explode { file, column, sep } => {
+            handle_explode(&config, &output, file, column, sep)
+        }
+        CsvSubcommands::Clean { file, snake_case } => {
+            handle_clean(&config, &output, file, *snake_case)
+        }
+        CsvSubcommands::Slice {
+            file,
+            offset,
+            limit,
+        } => handle_slice(&config, &output, file, *offset, *limit),
+        CsvSubcommands::Dedupe { file, key, keep } => {
+            handle_dedupe(&config, &output, file, key, keep)
+        }
+        CsvSubcommands::Reverse { file } => handle_reverse(&config, &output, file),
+        CsvSubcommands::Shuffle { file, seed } => handle_shuffle(&config, &output, file, *seed),
+        CsvSubcommands::Grep {
+            file,
+            pattern,
+            column,
+            count,
+        } => handle_grep(&config, &output, file, pattern, column.as_deref(), *count),
+        CsvSubcommands::Replace {
+            file,
+            column,
+            pattern,
+            with,
+        } => handle_replace(&config, &output, file, column, pattern, with),
+        CsvSubcommands::ToSqlite { db, files, replace } => {
+            handle_to_sqlite(&config, db, files, *replace)
+        }
+        CsvSubcommands::Join(join) => {
+            let JoinArgs {
+                file1,
+                file2,
+                left_column,
+                right_column,
+                on,
+                numeric_keys,
+                ignore_case,
+                lsuffix,
+                rsuffix,
+                strategy,
+                partitions,
+                select,
+                tolerance,
+                max_distance,
+                min_similarity,
+                null_keys_match,
+                left_alias,
+                right_alias,
+                between,
+                r#type,
+            } = join.as_ref();
+            let mut keys: Vec<(String, String)> = left_column
+                .iter()
+                .zip(right_column)
+                .map(|(left, right)| (left.clone(), right.clone()))
+                .collect();
+            keys.extend(on.iter().map(|pair| parse_join_key(pair)));
+            if min_similarity.is_some_and(|s| !(0.0..=1.0).contains(&s)) {
+                return Err("Similarity must be between 0 and 1".into());
+            }
+            let mut options = JoinOptions {
+                numeric_keys: *numeric_keys,
+                ignore_case: *ignore_case,
+                left_suffix: lsuffix.clone().unwrap_or_default(),
+                right_suffix: rsuffix
+                    .clone()
+                    .unwrap_or_else(|| format!("_{}", input_name(file2))),
+                strategy: *strategy,
+                partitions: *partitions,
+                select: select.clone(),
+                tolerance: *tolerance,
+                fuzzy: max_distance
+                    .map(FuzzyThreshold::Distance)
+                    .or(min_similarity.map(FuzzyThreshold::Similarity)),
+                null_keys_match: *null_keys_match,
+                left_alias: left_alias.clone(),
+                right_alias: right_alias.clone(),
+                range: between.as_deref().map(parse_range_condition).transpose()?,
+                ..JoinOptions::default()
+            };
+            // Both sides of a self-join share every column name, so tell them apart.
+            if file1 == file2 && options.left_alias.is_none() && options.right_alias.is_none() {
+                options.left_alias = Some("left".to_string());
+                options.right_alias = Some("right".to_string());
+            }
+            handle_join(&config, &output, file1, file2, &keys, r#type, &options)
+        }
+    }?;
+    output.commit()
 }
 
 /// Display the first n lines of a CSV file.
-fn handle_head(config: &Config, file: &str, lines: usize) -> Result<(), Box<dyn Error>> {
-    let file_path = config.source_path.join(format!("{}.csv", file));
-    println!("{:?}", file_path);
-    csv::read_csv_stream(&file_path, Some(lines), false)?;
+fn handle_head(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    lines: usize,
+) -> Result<(), Box<dyn Error>> {
+    let file_path = Input::open(config, file)?;
+    let table = csv::read_csv_table(&file_path, Some(lines), false, &config.csv_dialect)?;
+    output.write_table(&table)?;
     print_info(&format!(
         "Successfully displayed first {} lines from '{}'",
         lines, file
@@ -41,9 +235,15 @@ fn handle_head(config: &Config, file: &str, lines: usize) -> Result<(), Box<dyn
 }
 
 /// Display the last n lines of a CSV file.
-fn handle_tail(config: &Config, file: &str, lines: usize) -> Result<(), Box<dyn Error>> {
-    let file_path = config.source_path.join(format!("{}.csv", file));
-    csv::read_csv_stream(&file_path, Some(lines), true)?;
+fn handle_tail(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    lines: usize,
+) -> Result<(), Box<dyn Error>> {
+    let file_path = Input::open(config, file)?;
+    let table = csv::read_csv_table(&file_path, Some(lines), true, &config.csv_dialect)?;
+    output.write_table(&table)?;
     print_info(&format!(
         "Successfully displayed last {} lines from '{}'",
         lines, file
@@ -52,39 +252,104 @@ fn handle_tail(config: &Config, file: &str, lines: usize) -> Result<(), Box<dyn
 }
 
 /// Concatenate multiple CSV files.
-fn handle_concat(config: &Config, files: &[String]) -> Result<(), Box<dyn Error>> {
+fn handle_concat(config: &Config, output: &Output, files: &[String]) -> Result<(), Box<dyn Error>> {
     if files.len() < 2 {
         eprintln!("Error: At least two files are needed to use the concat command");
         return Ok(());
     }
 
-    let mut df = DataFrame::new("concatenated".to_string());
-    df.read_headers(Path::new(&files[0]))?;
-    let stdout = io::stdout();
+    let mut df = new_frame(config, "concatenated");
+    let inputs = files
+        .iter()
+        .map(|file| Input::open(config, file))
+        .collect::<Result<Vec<_>, _>>()?;
+    df.read_headers(&inputs[0])?;
 
-    let mut writer = BufWriter::new(stdout.lock());
+    let mut writer = output.writer()?;
     df.write_headers(&mut writer)?;
 
-    for file in files {
-        let file = config.source_path.join(format!("{}.csv", file));
-        let mut input = BufReader::new(File::open(file)?);
-        df.concat_stream(&mut input.by_ref(), &mut writer)?;
+    for file in &inputs {
+        let mut input = df.open_rows(file)?;
+        df.concat_stream(&mut input, &mut writer)?;
     }
+    writer.flush()?;
     print_info(&format!("Successfully concatenated {} files", files.len()));
     Ok(())
 }
 
+/// Merge CSV files sorted on the same key into a single sorted output.
+fn handle_merge(
+    config: &Config,
+    output: &Output,
+    key: &str,
+    files: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, "merged");
+    let mut inputs = Vec::with_capacity(files.len());
+    for (i, name) in files.iter().enumerate() {
+        let file = Input::open(config, name)?;
+        let mut current = new_frame(config, name);
+        current.read_headers(&file)?;
+        if i == 0 {
+            df.headers = current.headers.clone();
+            df.dialect = current.dialect.clone();
+        } else if current.headers != df.headers {
+            return Err(format!("Headers of '{}' differ from '{}'", name, files[0]).into());
+        }
+        inputs.push(current.open_rows(&file)?);
+    }
+
+    let mut writer = output.writer()?;
+
+    df.merge_sorted_stream(&mut inputs, &mut writer, key)?;
+    writer.flush()?;
+    print_info(&format!("Successfully merged {} files", files.len()));
+    Ok(())
+}
+
+/// Paste two CSV files with the same number of rows side by side.
+fn handle_hstack(
+    config: &Config,
+    output: &Output,
+    file1: &str,
+    file2: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut left = new_frame(config, file1);
+    let mut right = new_frame(config, file2);
+    let file1 = Input::open(config, file1)?;
+    let file2 = Input::open(config, file2)?;
+    left.read_headers(&file1)?;
+    right.read_headers(&file2)?;
+
+    let mut left_input = left.open_rows(&file1)?;
+    let mut right_input = right.open_rows(&file2)?;
+    let mut writer = output.writer()?;
+
+    left.hstack_stream(&right, &mut left_input, &mut right_input, &mut writer)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully stacked '{:?}' and '{:?}'",
+        file1, file2
+    ));
+    Ok(())
+}
+
 /// Drop specified columns from a CSV file.
-fn handle_drop(config: &Config, file: &str, columns: &[String]) -> Result<(), Box<dyn Error>> {
-    let mut df = DataFrame::new(file.to_string());
-    let file = config.source_path.join(format!("{}.csv", file));
+fn handle_drop(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    columns: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
     df.read_headers(&file)?;
 
-    let mut input = BufReader::new(File::open(file.clone())?);
-    let stdout = io::stdout();
-    let mut writer = BufWriter::new(stdout.lock());
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
 
-    df.drop_stream(&mut input.by_ref(), &mut writer, columns)?;
+    df.drop_stream(&mut input, &mut writer, columns)?;
+    writer.flush()?;
     print_info(&format!(
         "Successfully dropped columns {:?} from '{:?}'",
         columns, file
@@ -93,16 +358,21 @@ fn handle_drop(config: &Config, file: &str, columns: &[String]) -> Result<(), Bo
 }
 
 /// Select specified columns from a CSV file.
-fn handle_select(config: &Config, file: &str, columns: &[String]) -> Result<(), Box<dyn Error>> {
-    let mut df = DataFrame::new(file.to_string());
-    let file = config.source_path.join(format!("{}.csv", file));
-    df.read_headers(Path::new(&file.clone()))?;
+fn handle_select(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    columns: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
 
-    let mut input = BufReader::new(File::open(file.clone())?);
-    let stdout = io::stdout();
-    let mut writer = BufWriter::new(stdout.lock());
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
 
-    df.select_stream(&mut input.by_ref(), &mut writer, columns)?;
+    df.select_stream(&mut input, &mut writer, columns)?;
+    writer.flush()?;
     print_info(&format!(
         "Successfully selected columns {:?} from '{:?}'",
         columns, file
@@ -110,36 +380,1069 @@ fn handle_select(config: &Config, file: &str, columns: &[String]) -> Result<(),
     Ok(())
 }
 
+/// Reorder the columns of a CSV file.
+fn handle_reorder(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    columns: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.reorder_stream(&mut input, &mut writer, columns)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully reordered columns {:?} in '{:?}'",
+        columns, file
+    ));
+    Ok(())
+}
+
+/// Append a computed column to a CSV file.
+fn handle_derive(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    expression: &str,
+) -> Result<(), Box<dyn Error>> {
+    let (column, expression) = expr::parse_assignment(expression)?;
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.derive_stream(&mut input, &mut writer, &column, &expression)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully derived column '{}' in '{:?}'",
+        column, file
+    ));
+    Ok(())
+}
+
+/// Prepend a row number column to a CSV file.
+fn handle_index(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    name: &str,
+    start: i64,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.index_stream(&mut input, &mut writer, name, start)?;
+    writer.flush()?;
+    print_info(&format!("Successfully indexed '{:?}'", file));
+    Ok(())
+}
+
+/// Keep the rows of a CSV file that match an expression.
+fn handle_filter(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    expression: &str,
+) -> Result<(), Box<dyn Error>> {
+    let predicate = expr::parse(expression)?;
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.filter_stream(&mut input, &mut writer, &predicate)?;
+    writer.flush()?;
+    print_info(&format!("Successfully filtered '{:?}'", file));
+    Ok(())
+}
+
+/// Convert columns of a CSV file to the given types, reporting values that fail.
+fn handle_cast(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    casts: &[String],
+    on_error: &CastOnError,
+) -> Result<(), Box<dyn Error>> {
+    let casts = casts
+        .iter()
+        .map(|spec| csv::parse_cast_spec(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    let failures = df.cast_stream(&mut input, &mut writer, &casts, on_error)?;
+    writer.flush()?;
+    for failure in &failures {
+        eprintln!(
+            "Line {}: cannot cast '{}' in column '{}' to {}",
+            failure.line, failure.value, failure.column, failure.column_type
+        );
+    }
+    print_info(&format!(
+        "Successfully cast '{:?}' with {} failed values",
+        file,
+        failures.len()
+    ));
+    Ok(())
+}
+
+/// Reformat the dates in one column of a CSV file.
+fn handle_dateformat(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    column: &str,
+    from: &str,
+    to: &str,
+    on_error: &CastOnError,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    let failures = df.dateformat_stream(&mut input, &mut writer, column, from, to, on_error)?;
+    writer.flush()?;
+    for failure in &failures {
+        eprintln!(
+            "Line {}: '{}' in column '{}' does not match format '{}'",
+            failure.line, failure.value, failure.column, from
+        );
+    }
+    print_info(&format!(
+        "Successfully reformatted dates in '{:?}' with {} failed values",
+        file,
+        failures.len()
+    ));
+    Ok(())
+}
+
+/// Report the inferred type and nullability of each column.
+fn handle_schema(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    sample: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let schema = df.infer_types(&mut input, sample)?;
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row!["column", "type", "nullable"]);
+    for column in &schema {
+        table.add_row(row![column.name, column.column_type, column.nullable]);
+    }
+    output.write_table(&table)?;
+
+    print_info(&format!("Successfully inferred schema of '{:?}'", file));
+    Ok(())
+}
+
+/// Print summary statistics for each column of a CSV file.
+fn handle_describe(config: &Config, output: &Output, file: &str) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let stats = df.describe(&mut input)?;
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row![
+        "column", "count", "nulls", "min", "max", "mean", "distinct"
+    ]);
+    for column in &stats {
+        table.add_row(row![
+            column.name,
+            column.count,
+            column.null_count,
+            column.min.as_deref().unwrap_or(""),
+            column.max.as_deref().unwrap_or(""),
+            column.mean.map(|m| format!("{:.4}", m)).unwrap_or_default(),
+            column.distinct
+        ]);
+    }
+    output.write_table(&table)?;
+
+    print_info(&format!("Successfully described '{:?}'", file));
+    Ok(())
+}
+
+/// Count the rows of a CSV file, optionally per value of a column.
+fn handle_count(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    per_value: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    let mut input = df.open_rows(&file)?;
+
+    match per_value {
+        Some(column) => {
+            df.read_headers(&file)?;
+            let counts = df.count_by_value(&mut input, column)?;
+            let mut writer = output.writer()?;
+            df.write_row(&mut writer, &[column, "count"])?;
+            for (value, count) in &counts {
+                df.write_row(&mut writer, &[value.clone(), count.to_string()])?;
+            }
+            writer.flush()?;
+        }
+        None => writeln!(output.text_writer()?, "{}", df.count_rows(&mut input)?)?,
+    }
+    Ok(())
+}
+
+/// Print the most common values of a column with counts and percentages.
+fn handle_freq(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    column: &str,
+    top: usize,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let frequencies = df.value_frequencies(&mut input, column, Some(top))?;
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row![column, "count", "percent"]);
+    for frequency in &frequencies {
+        table.add_row(row![
+            frequency.value,
+            frequency.count,
+            format!("{:.2}%", frequency.percent)
+        ]);
+    }
+    output.write_table(&table)?;
+
+    print_info(&format!(
+        "Successfully profiled column '{}' of '{:?}'",
+        column, file
+    ));
+    Ok(())
+}
+
+/// Split a CSV file by row count or by the values of a column.
+fn handle_split(
+    config: &Config,
+    file: &str,
+    rows: Option<usize>,
+    by: Option<&str>,
+    dir: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let path = Input::open(config, file)?;
+    df.read_headers(&path)?;
+    let dir = dir.map_or_else(|| config.csv_output_path.clone(), PathBuf::from);
+
+    let mut input = df.open_rows(&path)?;
+    let paths = match (rows, by) {
+        (_, Some(column)) => df.split_by_column(&mut input, &dir, input_name(file), column)?,
+        (Some(rows), None) => df.split_by_rows(&mut input, &dir, input_name(file), rows)?,
+        (None, None) => return Err("Either --rows or --by is required".into()),
+    };
+
+    for path in &paths {
+        print_info(&format!("  {}", config::display_relative_path(path)));
+    }
+    print_info(&format!(
+        "Successfully split '{:?}' into {} files",
+        path,
+        paths.len()
+    ));
+    Ok(())
+}
+
+/// Reshape a long CSV file into a wide one.
+fn handle_pivot(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    index: &[String],
+    key: &str,
+    value: &str,
+    agg: &PivotAggregate,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.pivot_stream(&mut input, &mut writer, index, key, value, agg)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully pivoted '{:?}' on '{}' with values from '{}'",
+        file, key, value
+    ));
+    Ok(())
+}
+
+/// Reshape a wide CSV file into key/value rows.
+fn handle_melt(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    id_cols: &[String],
+    value_cols: &[String],
+    var_name: &str,
+    value_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.melt_stream(
+        &mut input,
+        &mut writer,
+        id_cols,
+        value_cols,
+        var_name,
+        value_name,
+    )?;
+    writer.flush()?;
+    print_info(&format!("Successfully melted '{:?}'", file));
+    Ok(())
+}
+
+/// Convert the letter case of columns in a CSV file.
+fn handle_transform(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    columns: &[String],
+    case: TextCase,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.transform_stream(&mut input, &mut writer, columns, case)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully transformed columns {:?} in '{:?}'",
+        columns, file
+    ));
+    Ok(())
+}
+
+/// Split a delimited column of a CSV file into one row per value.
+fn handle_explode(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    column: &str,
+    separator: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.explode_stream(&mut input, &mut writer, column, separator)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully exploded '{}' in '{:?}'",
+        column, file
+    ));
+    Ok(())
+}
+
+/// Normalize the whitespace, and optionally the header names, of a CSV file.
+fn handle_clean(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    snake_case: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.clean_stream(&mut input, &mut writer, snake_case)?;
+    writer.flush()?;
+    print_info(&format!("Successfully cleaned '{:?}'", file));
+    Ok(())
+}
+
+/// Output the rows of a CSV file between an offset and a limit.
+fn handle_slice(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    offset: usize,
+    limit: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.slice_stream(&mut input, &mut writer, offset, limit)?;
+    writer.flush()?;
+    print_info(&format!("Successfully sliced '{:?}'", file));
+    Ok(())
+}
+
+/// Remove duplicate rows from a CSV file.
+fn handle_dedupe(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    key: &[String],
+    keep: &DedupeKeep,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    let removed = df.dedupe_stream(&mut input, &mut writer, key, keep)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully removed {} duplicate rows from '{:?}'",
+        removed, file
+    ));
+    Ok(())
+}
+
+/// Output the rows of a CSV file last to first.
+fn handle_reverse(config: &Config, output: &Output, file: &str) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.reverse_stream(&mut input, &mut writer)?;
+    writer.flush()?;
+    print_info(&format!("Successfully reversed '{:?}'", file));
+    Ok(())
+}
+
+/// Output the rows of a CSV file in random order.
+fn handle_shuffle(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    seed: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    df.shuffle_stream(&mut input, &mut writer, seed)?;
+    writer.flush()?;
+    print_info(&format!("Successfully shuffled '{:?}'", file));
+    Ok(())
+}
+
+/// Print, or count, the rows of a CSV file matching a regular expression.
+fn handle_grep(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    pattern: &str,
+    column: Option<&str>,
+    count: bool,
+) -> Result<(), Box<dyn Error>> {
+    let pattern = Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    if count {
+        let matches = df.count_matches(&mut input, &pattern, column)?;
+        writeln!(output.text_writer()?, "{}", matches)?;
+        return Ok(());
+    }
+
+    let mut writer = output.writer()?;
+    let matches = df.grep_stream(&mut input, &mut writer, &pattern, column)?;
+    writer.flush()?;
+    print_info(&format!("Found {} matching rows in '{:?}'", matches, file));
+    Ok(())
+}
+
+/// Apply a regex substitution to one column of a CSV file.
+fn handle_replace(
+    config: &Config,
+    output: &Output,
+    file: &str,
+    column: &str,
+    pattern: &str,
+    replacement: &str,
+) -> Result<(), Box<dyn Error>> {
+    let pattern = Regex::new(pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+    let mut df = new_frame(config, file);
+    let file = Input::open(config, file)?;
+    df.read_headers(&file)?;
+
+    let mut input = df.open_rows(&file)?;
+    let mut writer = output.writer()?;
+
+    let replaced = df.replace_stream(&mut input, &mut writer, column, &pattern, replacement)?;
+    writer.flush()?;
+    print_info(&format!(
+        "Successfully replaced values in {} rows of '{:?}'",
+        replaced, file
+    ));
+    Ok(())
+}
+
+/// Import CSV files into a SQLite database, one table per file.
+#[cfg(feature = "sqlite")]
+fn handle_to_sqlite(
+    config: &Config,
+    db: &str,
+    files: &[String],
+    replace: bool,
+) -> Result<(), Box<dyn Error>> {
+    for file in files {
+        let table = csv::sanitize_file_name(input_name(file));
+        let input = Input::open(config, file)?;
+        let rows = csv::write_sqlite(&input, Path::new(db), &table, &config.csv_dialect, replace)?;
+        print_info(&format!("Imported {} rows into table '{}'", rows, table));
+    }
+    print_info(&format!(
+        "Successfully imported {} files into '{}'",
+        files.len(),
+        db
+    ));
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn handle_to_sqlite(
+    _config: &Config,
+    _db: &str,
+    _files: &[String],
+    _replace: bool,
+) -> Result<(), Box<dyn Error>> {
+    Err(csv::SQLITE_DISABLED.into())
+}
+
+/// Create a DataFrame that reads and writes using the configured CSV dialect.
+fn new_frame(config: &Config, name: &str) -> DataFrame {
+    DataFrame::new(input_name(name).to_string()).with_dialect(config.csv_dialect.clone())
+}
+
+/// Where a command writes its results: stdout, or a file that is only replaced once
+/// the command has succeeded.
+struct Output {
+    path: Option<PathBuf>,
+    format: OutputFormat,
+    /// Dialect of the CSV spooled for conversion to another format.
+    dialect: csv::Dialect,
+    /// Rows shown in HTML output.
+    max_rows: Option<usize>,
+    /// Temporary file holding the results until they are committed: next to `path`,
+    /// or anywhere when stdout gets them in another format.
+    spool: RefCell<Option<NamedTempFile>>,
+}
+
+impl Output {
+    /// Resolves an `--output` path against the configured CSV output folder. A path
+    /// ending in `.html`, `.parquet` or `.arrow` selects that format unless another was asked for.
+    fn new(
+        config: &Config,
+        path: Option<&Path>,
+        format: OutputFormat,
+        max_rows: Option<usize>,
+    ) -> Self {
+        let format = match path.and_then(Path::extension) {
+            Some(extension) if format == OutputFormat::Csv => match extension.to_str() {
+                Some("parquet") => OutputFormat::Parquet,
+                Some("arrow" | "arrows") => OutputFormat::Arrow,
+                Some("html" | "htm") => OutputFormat::Html,
+                _ => format,
+            },
+            _ => format,
+        };
+        Output {
+            path: path.map(|path| config.csv_output_path.join(path)),
+            format,
+            dialect: config.csv_dialect.clone(),
+            max_rows,
+            spool: RefCell::new(None),
+        }
+    }
+
+    /// Opens a buffered writer for the CSV rows the command produces.
+    fn writer(&self) -> Result<BufWriter<Box<dyn Write>>, Box<dyn Error>> {
+        let spool = match &self.path {
+            Some(path) => {
+                let dir = Self::dir_of(path);
+                fs::create_dir_all(dir)?;
+                NamedTempFile::new_in(dir)?
+            }
+            None if self.format != OutputFormat::Csv => NamedTempFile::new()?,
+            None => return Ok(BufWriter::new(Box::new(io::stdout().lock()))),
+        };
+        let file = spool.as_file().try_clone()?;
+        *self.spool.borrow_mut() = Some(spool);
+        Ok(BufWriter::new(Box::new(file)))
+    }
+
+    /// Opens a buffered writer for output that is not CSV rows, such as a table.
+    fn text_writer(&self) -> Result<BufWriter<Box<dyn Write>>, Box<dyn Error>> {
+        if self.format != OutputFormat::Csv {
+            return Err("--format only applies to commands that write CSV rows".into());
+        }
+        self.writer()
+    }
+
+    /// Writes a table the command built: drawn with box characters as plain text, or
+    /// as rows in any other format, with the titles as the header.
+    fn write_table(&self, table: &Table) -> Result<(), Box<dyn Error>> {
+        if self.format == OutputFormat::Csv {
+            table.print(&mut self.writer()?)?;
+            return Ok(());
+        }
+        let writer = self.dialect.writer_builder().from_writer(self.writer()?);
+        table.to_csv_writer(writer)?;
+        Ok(())
+    }
+
+    /// Folder the output file is written to.
+    fn dir_of(path: &Path) -> &Path {
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        }
+    }
+
+    /// Converts the results to the requested format, then moves the written file over
+    /// the target in a single rename, compressing it first when the target ends in
+    /// `.gz` or `.zst`.
+    fn commit(self) -> Result<(), Box<dyn Error>> {
+        let Some(mut spool) = self.spool.into_inner() else {
+            return Ok(());
+        };
+        if self.format != OutputFormat::Csv {
+            let Some(path) = &self.path else {
+                let mut stdout = BufWriter::new(io::stdout());
+                csv::convert_csv(
+                    spool.path(),
+                    &mut stdout,
+                    self.format,
+                    &self.dialect,
+                    self.max_rows,
+                )?;
+                stdout.flush()?;
+                return Ok(());
+            };
+            let mut converted = BufWriter::new(NamedTempFile::new_in(Self::dir_of(path))?);
+            csv::convert_csv(
+                spool.path(),
+                &mut converted,
+                self.format,
+                &self.dialect,
+                self.max_rows,
+            )?;
+            spool = converted.into_inner().map_err(|e| e.into_error())?;
+        }
+        let Some(path) = self.path else {
+            return Ok(());
+        };
+        if let Some(compression) = Compression::from_path(&path) {
+            spool.as_file_mut().seek(SeekFrom::Start(0))?;
+            let compressed = NamedTempFile::new_in(Self::dir_of(&path))?;
+            spool = compression.compress(&mut BufReader::new(spool.as_file()), compressed)?;
+        }
+        // Temporary files are private; give the result the target's permissions instead.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).map_or(0o644, |metadata| metadata.permissions().mode());
+            spool
+                .as_file()
+                .set_permissions(fs::Permissions::from_mode(mode))?;
+        }
+        spool
+            .persist(&path)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e.error))?;
+        print_info(&format!(
+            "Wrote results to '{}'",
+            config::display_relative_path(&path)
+        ));
+        Ok(())
+    }
+}
+
+/// The file name that stands for standard input.
+const STDIN: &str = "-";
+
+/// The prefix of input names and source paths that refer to S3 objects.
+const S3_SCHEME: &str = "s3://";
+
+/// Set once standard input has been spooled, since it can only be read once.
+static STDIN_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// A CSV input named on the command line.
+enum Input {
+    /// `<name>.csv` under the configured source path.
+    File(PathBuf),
+    /// A compressed, JSON Lines or Avro file under the source path, converted to plain
+    /// CSV in a temporary file.
+    Converted(PathBuf, NamedTempFile),
+    /// Standard input, copied to a temporary file so it can be read more than once.
+    Stdin(NamedTempFile),
+}
+
+impl Input {
+    /// Resolves `name` under the source path, or spools standard input for `-`.
+    /// Names ending in `.jsonl`, `.avro`, `.gz` or `.zst` are taken as they are and
+    /// converted to plain CSV. `s3://` names, and any name when the source path is an
+    /// `s3://` prefix, are downloaded first.
+    fn open(config: &Config, name: &str) -> Result<Self, Box<dyn Error>> {
+        if name == STDIN {
+            if STDIN_TAKEN.swap(true, AtomicOrdering::SeqCst) {
+                return Err("Standard input can only be used as one input".into());
+            }
+            return Ok(Input::Stdin(Self::spool(&mut io::stdin().lock())?));
+        }
+        if name.ends_with(".parquet") {
+            return Self::convert_parquet(config, name);
+        }
+        if name.ends_with(".avro") {
+            return Self::convert_avro(config, name);
+        }
+        if let Some((db, table)) = Self::sqlite_table(name) {
+            return Self::convert_sqlite(config, db, table);
+        }
+        let compression = Compression::from_path(Path::new(name));
+        let jsonl = Self::is_jsonl(name);
+        if compression.is_none() && !jsonl {
+            return Ok(match Self::locate(config, &format!("{}.csv", name))? {
+                (path, Some(download)) => Input::Converted(path, download),
+                (path, None) => Input::File(path),
+            });
+        }
+
+        let (path, file) = Self::open_file(config, name)?;
+        let spool = match compression {
+            Some(compression) => {
+                let plain = Self::spool(&mut compression.decoder(BufReader::new(file))?)?;
+                match jsonl {
+                    true => Self::convert_jsonl(config, name, plain.reopen()?)?,
+                    false => plain,
+                }
+            }
+            None => Self::convert_jsonl(config, name, file)?,
+        };
+        Ok(Input::Converted(path, spool))
+    }
+
+    /// Finds `file_name` under the source path. S3 objects are downloaded to a temporary
+    /// file, which is returned along with the URL they came from.
+    fn locate(
+        config: &Config,
+        file_name: &str,
+    ) -> Result<(PathBuf, Option<NamedTempFile>), Box<dyn Error>> {
+        let source = config.source_path.to_string_lossy();
+        let url = match (is_s3_url(file_name), is_s3_url(&source)) {
+            (true, _) => file_name.to_string(),
+            (false, true) => format!("{}/{}", source.trim_end_matches('/'), file_name),
+            (false, false) => return Ok((config.source_path.join(file_name), None)),
+        };
+        Ok((PathBuf::from(&url), Some(Self::download(&url)?)))
+    }
+
+    /// Opens `file_name` under the source path for reading, as [`Input::locate`] finds it.
+    fn open_file(config: &Config, file_name: &str) -> Result<(PathBuf, File), Box<dyn Error>> {
+        let (path, download) = Self::locate(config, file_name)?;
+        let file = match download {
+            Some(download) => download.reopen()?,
+            None => {
+                File::open(&path).map_err(|e| format!("Failed to open file '{:?}': {}", path, e))?
+            }
+        };
+        Ok((path, file))
+    }
+
+    /// Copies the S3 object at `url` to a temporary file.
+    #[cfg(feature = "s3")]
+    fn download(url: &str) -> Result<NamedTempFile, Box<dyn Error>> {
+        let mut download = NamedTempFile::new()?;
+        let mut writer = BufWriter::new(download.as_file_mut());
+        csv::read_s3_object(url, &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        Ok(download)
+    }
+
+    #[cfg(not(feature = "s3"))]
+    fn download(_url: &str) -> Result<NamedTempFile, Box<dyn Error>> {
+        Err(csv::S3_DISABLED.into())
+    }
+
+    /// Converts a JSON Lines file to CSV in a temporary file.
+    fn convert_jsonl(
+        config: &Config,
+        name: &str,
+        file: File,
+    ) -> Result<NamedTempFile, Box<dyn Error>> {
+        let mut converted = NamedTempFile::new()?;
+        let mut writer = BufWriter::new(converted.as_file_mut());
+        new_frame(config, name).jsonl_to_csv(&mut BufReader::new(file), &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        Ok(converted)
+    }
+
+    /// Converts a Parquet file under the source path to CSV in a temporary file.
+    #[cfg(feature = "parquet")]
+    fn convert_parquet(config: &Config, name: &str) -> Result<Self, Box<dyn Error>> {
+        let (path, file) = Self::open_file(config, name)?;
+        let mut converted = NamedTempFile::new()?;
+        let mut writer = BufWriter::new(converted.as_file_mut());
+        new_frame(config, name).parquet_to_csv(file, &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        Ok(Input::Converted(path, converted))
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn convert_parquet(_config: &Config, _name: &str) -> Result<Self, Box<dyn Error>> {
+        Err(csv::PARQUET_DISABLED.into())
+    }
+
+    /// Converts an Avro container file under the source path to CSV in a temporary file.
+    #[cfg(feature = "avro")]
+    fn convert_avro(config: &Config, name: &str) -> Result<Self, Box<dyn Error>> {
+        let (path, file) = Self::open_file(config, name)?;
+        let mut converted = NamedTempFile::new()?;
+        let mut writer = BufWriter::new(converted.as_file_mut());
+        new_frame(config, name).avro_to_csv(BufReader::new(file), &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        Ok(Input::Converted(path, converted))
+    }
+
+    #[cfg(not(feature = "avro"))]
+    fn convert_avro(_config: &Config, _name: &str) -> Result<Self, Box<dyn Error>> {
+        Err(csv::AVRO_DISABLED.into())
+    }
+
+    /// Splits a `<db>:<table>` name whose database ends in `.db`, `.sqlite` or
+    /// `.sqlite3` into the database path and table.
+    fn sqlite_table(name: &str) -> Option<(&str, &str)> {
+        let (db, table) = name.rsplit_once(':')?;
+        [".db", ".sqlite", ".sqlite3"]
+            .iter()
+            .any(|extension| db.ends_with(extension))
+            .then_some((db, table))
+    }
+
+    /// Reads a table of a SQLite database as CSV in a temporary file.
+    #[cfg(feature = "sqlite")]
+    fn convert_sqlite(config: &Config, db: &str, table: &str) -> Result<Self, Box<dyn Error>> {
+        let mut converted = NamedTempFile::new()?;
+        let mut writer = BufWriter::new(converted.as_file_mut());
+        new_frame(config, table).sqlite_to_csv(Path::new(db), table, &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        Ok(Input::Converted(PathBuf::from(db).join(table), converted))
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn convert_sqlite(_config: &Config, _db: &str, _table: &str) -> Result<Self, Box<dyn Error>> {
+        Err(csv::SQLITE_DISABLED.into())
+    }
+
+    /// Whether `name` is a JSON Lines file, possibly compressed.
+    fn is_jsonl(name: &str) -> bool {
+        let name = match Compression::from_path(Path::new(name)) {
+            Some(_) => name.rsplit_once('.').map_or(name, |(stem, _)| stem),
+            None => name,
+        };
+        name.ends_with(".jsonl")
+    }
+
+    /// Copies `input` to a temporary file.
+    fn spool(input: &mut dyn io::Read) -> Result<NamedTempFile, Box<dyn Error>> {
+        let mut spool = NamedTempFile::new()?;
+        io::copy(input, spool.as_file_mut())?;
+        spool.as_file_mut().flush()?;
+        Ok(spool)
+    }
+}
+
+impl Deref for Input {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        match self {
+            Input::File(path) => path,
+            Input::Converted(_, spool) | Input::Stdin(spool) => spool.path(),
+        }
+    }
+}
+
+impl AsRef<Path> for Input {
+    fn as_ref(&self) -> &Path {
+        self
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::File(path) | Input::Converted(path, _) => path.fmt(f),
+            Input::Stdin(_) => "<stdin>".fmt(f),
+        }
+    }
+}
+
+/// Whether `location` is an `s3://` URL.
+fn is_s3_url(location: &str) -> bool {
+    location.starts_with(S3_SCHEME)
+}
+
+/// Name used for an input in messages, frame names and derived file names.
+fn input_name(name: &str) -> &str {
+    if name == STDIN {
+        return "stdin";
+    }
+    if let Some((_, table)) = Input::sqlite_table(name) {
+        return table;
+    }
+    let name = match name.strip_prefix(S3_SCHEME) {
+        Some(key) => key.rsplit('/').next().unwrap_or(key),
+        None => name,
+    };
+    let name = match Compression::from_path(Path::new(name)) {
+        Some(_) => name.rsplit_once('.').map_or(name, |(stem, _)| stem),
+        None => name,
+    };
+    name.strip_suffix(".csv")
+        .or_else(|| name.strip_suffix(".jsonl"))
+        .or_else(|| name.strip_suffix(".parquet"))
+        .or_else(|| name.strip_suffix(".avro"))
+        .unwrap_or(name)
+}
+/// Parse a `--on` join key pair written as `left=right`, or a single column name
+/// shared by both files.
+fn parse_join_key(pair: &str) -> (String, String) {
+    match pair.split_once('=') {
+        Some((left, right)) => (left.trim().to_string(), right.trim().to_string()),
+        None => (pair.trim().to_string(), pair.trim().to_string()),
+    }
+}
+
+/// Parse a `--between` range condition written as `left=start,end`.
+fn parse_range_condition(condition: &str) -> Result<RangeCondition, Box<dyn Error>> {
+    let (column, bounds) = condition
+        .split_once('=')
+        .ok_or_else(|| format!("Range '{}' must look like LEFT=START,END", condition))?;
+    let (start, end) = bounds
+        .split_once(',')
+        .ok_or_else(|| format!("Range '{}' must look like LEFT=START,END", condition))?;
+    Ok(RangeCondition {
+        column: column.trim().to_string(),
+        start: start.trim().to_string(),
+        end: end.trim().to_string(),
+    })
+}
+
 /// Join two CSV files based on specified columns.
 fn handle_join(
     config: &Config,
+    output: &Output,
     file1: &str,
     file2: &str,
-    left_column: &str,
-    right_column: &str,
+    keys: &[(String, String)],
     r#type: &JoinType,
+    options: &JoinOptions,
 ) -> Result<(), Box<dyn Error>> {
-    let mut left_df = DataFrame::new(file1.to_string());
-    let file1 = config.source_path.join(format!("{}.csv", file1));
+    if keys.is_empty() && options.range.is_none() {
+        return Err(
+            "Specify the join columns, at least one --on LEFT=RIGHT pair or --between".into(),
+        );
+    }
+    let mut left_df = new_frame(config, file1);
+    let file1 = Input::open(config, file1)?;
     left_df.read_headers(&file1)?;
 
-    let file2 = config.source_path.join(format!("{}.csv", file2));
-    let mut left_input = BufReader::new(File::open(file1.clone())?);
-    let mut right_input = BufReader::new(File::open(file2.clone())?);
-    let stdout = io::stdout();
-    let mut writer = BufWriter::new(stdout.lock());
+    let file2 = Input::open(config, file2)?;
+    let mut left_input = BufReader::new(File::open(&file1)?);
+    let mut right_input = BufReader::new(File::open(&file2)?);
+    let mut writer = output.writer()?;
 
     left_df.join_stream(
         &mut left_input,
         &mut right_input,
         &mut writer,
-        left_column,
-        right_column,
+        keys,
         r#type,
+        options,
     )?;
+    let (left_columns, right_columns): (Vec<&str>, Vec<&str>) = keys
+        .iter()
+        .map(|(left, right)| (left.as_str(), right.as_str()))
+        .unzip();
+    writer.flush()?;
     print_info(&format!(
         "Successfully joined '{:?}' and '{:?}' on columns '{}' and '{}'",
-        file1, file2, left_column, right_column
+        file1,
+        file2,
+        left_columns.join(", "),
+        right_columns.join(", ")
     ));
     Ok(())
 }
@@ -1,42 +1,132 @@
-use crate::cli::{DisplayType, GraphArgs, GraphSubcommands, JoinType};
+use crate::cli::{
+    DisplayType, ExportFormat, GraphArgs, GraphJoinArgs, GraphSubcommands, JoinOrder, JoinType,
+    MstFormat, PathWeight, ReportFormat,
+};
 use crate::config::{self, Config};
-use crate::csv::{human_readable_bytes, DataFrame};
-use crate::graph;
+use crate::csv::{self, human_readable_bytes, DataFrame, JoinOptions};
+use crate::expr::{self, BinaryOp, Expr, Value};
 use crate::utils::print_info;
-use petgraph::algo::dijkstra;
-use petgraph::data::FromElements;
-use petgraph::graph::{NodeIndex, UnGraph};
+use crate::{graph, sql};
+use ::csv::ByteRecord;
+use petgraph::algo::{all_simple_paths, dijkstra, tarjan_scc};
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex, UnGraph};
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use prettytable::{format, row, Table};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
-use std::path::Path;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use tempfile::NamedTempFile;
+use std::sync::Mutex;
+use tempfile::{NamedTempFile, TempPath};
 
 /// Execute graph operations based on command line arguments.
 pub fn execute(args: &GraphArgs) -> Result<(), Box<dyn Error>> {
     let config_dir = config::create_config_folder()?;
-    let config: Config = config::read_config(&config_dir)?;
+    let mut config: Config = config::read_config(&config_dir)?;
+    if let Some(engine) = &args.engine {
+        config.graphviz_settings.engine = engine.clone();
+    }
+    if args.no_open {
+        config.graphviz_settings.open_rendered = false;
+    }
+    let style = &mut config.graphviz_settings.style;
+    if let Some(rankdir) = &args.rankdir {
+        style.rankdir = rankdir.clone();
+    }
+    if let Some(node_shape) = &args.node_shape {
+        style.node_shape = node_shape.clone();
+    }
+    if let Some(font) = &args.font {
+        style.font_name = font.clone();
+    }
+    if let Some(nodesep) = args.nodesep {
+        style.nodesep = nodesep;
+    }
+    if let Some(ranksep) = args.ranksep {
+        style.ranksep = ranksep;
+    }
+
+    if let Some(GraphSubcommands::Create {
+        pg: Some(url),
+        pg_schema,
+        format,
+        ..
+    }) = &args.subcommand
+    {
+        let g = introspect_graph_cache(url, pg_schema, &config_dir)?;
+        let g = graph::exclude_tables(g, &config.exclude_tables)?;
+        return render_graph(&config, &g, get_type(format));
+    }
+
+    // Comparing two schema files needs no cached graph.
+    if let Some(GraphSubcommands::Diff { old, new, render }) = &args.subcommand {
+        return handle_graph_diff(old, new, render.as_ref(), &config);
+    }
 
     if args.regenerate || !config::graph_cache_exists(&config_dir) {
-        regenerate_graph_cache(&config_dir)?;
+        regenerate_graph_cache(&config, &config_dir)?;
         return Ok(());
     }
+    let schemas = config::read_sql_schemas(&config).unwrap_or_default();
+    if !schemas.is_empty()
+        && config::graph_cache_is_stale(&config::schemas_hash(&schemas), &config_dir)
+    {
+        print_info("The SQL schema changed since the graph was cached.");
+        regenerate_graph_cache(&config, &config_dir)?;
+    }
 
-    let g = config::read_graph_cache(&config_dir)?;
+    let g = graph::exclude_tables(
+        config::read_graph_cache(&config_dir)?,
+        &config.exclude_tables,
+    )?;
 
     match &args.subcommand {
         Some(subcommand) => match subcommand {
-            GraphSubcommands::Create { schema, format } => {
-                handle_graph_create(schema, &config, &g, get_type(format))
-            }
-            GraphSubcommands::ShortestPath { from, to } => handle_graph_shortest_path(from, to, &g),
-            GraphSubcommands::Join {
-                left_table,
-                right_table,
-                ..
-            } => handle_graph_join(&config, left_table, right_table, &g),
-            GraphSubcommands::Mst => handle_graph_mst(&g, &config),
+            GraphSubcommands::Create { schema, format, .. } => handle_graph_create(
+                schema.as_deref().unwrap_or_default(),
+                &config,
+                &g,
+                get_type(format),
+            ),
+            GraphSubcommands::ShortestPath {
+                from,
+                to,
+                via,
+                weight,
+                render,
+            } => handle_graph_shortest_path(from, to, via, *weight, render.as_ref(), &config, &g),
+            GraphSubcommands::Join(join) => handle_graph_join(&config, &config_dir, join, &g),
+            GraphSubcommands::Explain(join) => handle_graph_explain(&config, join, &g),
+            GraphSubcommands::Paths {
+                from,
+                to,
+                max,
+                max_len,
+            } => handle_graph_paths(from, to, *max, *max_len, &g),
+            GraphSubcommands::Query { pattern, max } => handle_graph_query(pattern, *max, &g),
+            GraphSubcommands::Mst { format } => handle_graph_mst(&g, &config, *format),
+            GraphSubcommands::Components => handle_graph_components(&g),
+            GraphSubcommands::Cycles => handle_graph_cycles(&g),
+            GraphSubcommands::Diff { .. } => unreachable!("handled before reading the cache"),
+            GraphSubcommands::Tables => handle_graph_tables(&g),
+            GraphSubcommands::Info { table } => handle_graph_info(table, &g),
+            GraphSubcommands::ReferencedBy { table } => handle_graph_referenced_by(table, &g),
+            GraphSubcommands::Orphans => handle_graph_orphans(&g),
+            GraphSubcommands::Neighbors { table } => handle_graph_neighbors(table, &g),
+            GraphSubcommands::Hubs { top } => handle_graph_hubs(*top, &g),
+            // Counts are cached for every table, including excluded ones.
+            GraphSubcommands::CountRows => handle_graph_count_rows(
+                &config,
+                &config_dir,
+                config::read_graph_cache(&config_dir)?,
+            ),
+            GraphSubcommands::JoinTree { tables } => handle_graph_join_tree(tables, &g),
+            GraphSubcommands::Report { format, top } => handle_graph_report(*format, *top, &g),
+            GraphSubcommands::Export { format } => handle_graph_export(*format, &config, &g),
             GraphSubcommands::Display { format } => {
                 handle_graph_display(&g, &config, "graph", get_type(format))
             }
@@ -49,18 +139,45 @@ fn get_type(format: &DisplayType) -> &str {
     match format {
         DisplayType::Pdf => "pdf",
         DisplayType::Png => "png",
+        DisplayType::Svg => "svg",
+        DisplayType::Ascii => "ascii",
     }
 }
 
 /// Regenerate and cache the graph data.
-pub fn regenerate_graph_cache(config_dir: &Path) -> Result<(), Box<dyn Error>> {
+pub fn regenerate_graph_cache(config: &Config, config_dir: &Path) -> Result<(), Box<dyn Error>> {
     print_info("Generating new graph data.");
-    let g = graph::generate_graph(&config_dir.to_path_buf())?;
+    let g = graph::generate_graph(config, config_dir)?;
     config::write_graph_cache(&g, config_dir)?;
     print_info("Graph data regenerated and cached.");
     Ok(())
 }
 
+/// Builds the graph from the tables and foreign keys of a live PostgreSQL database and
+/// caches it in place of one parsed from a schema file.
+#[cfg(feature = "postgres")]
+fn introspect_graph_cache(
+    url: &str,
+    schema: &str,
+    config_dir: &Path,
+) -> Result<UnGraph<DataFrame, (String, String)>, Box<dyn Error>> {
+    let tables = sql::introspect_postgres(url, schema)?;
+    let g = graph::create_graph(tables);
+    config::write_graph_cache(&g, config_dir)?;
+    config::remove_schema_hash(config_dir)?;
+    print_info("Graph data read from PostgreSQL and cached.");
+    Ok(g)
+}
+
+#[cfg(not(feature = "postgres"))]
+fn introspect_graph_cache(
+    _url: &str,
+    _schema: &str,
+    _config_dir: &Path,
+) -> Result<UnGraph<DataFrame, (String, String)>, Box<dyn Error>> {
+    Err(sql::POSTGRES_DISABLED.into())
+}
+
 /// Handle the creation of a graph based on a schema.
 fn handle_graph_create(
     schema: &str,
@@ -68,69 +185,418 @@ fn handle_graph_create(
     g: &UnGraph<DataFrame, (String, String)>,
     format: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let _schema_path = if !schema.is_empty() {
-        Path::new(schema).to_path_buf()
-    } else {
-        config::find_sql_schema().ok_or("No SQL schema found in the current directory")?
-    };
+    if schema.is_empty() && config::find_sql_schemas(config).is_empty() {
+        return Err("No SQL schema found in the current directory".into());
+    }
 
-    let dot_content = graph::write_dot_file(g);
-    let output_dir = Path::new(&config.output_path);
-    std::fs::create_dir_all(output_dir)?;
+    render_graph(config, g, format)
+}
 
-    let dot_file = output_dir.join("graph.dot");
-    let png_file = output_dir.join(format!("graph.{}", format));
+/// Renders the graph with `dot` in the output folder and opens the result.
+fn render_graph(
+    config: &Config,
+    g: &UnGraph<DataFrame, (String, String)>,
+    format: &str,
+) -> Result<(), Box<dyn Error>> {
+    handle_graph_display(g, config, "graph", format)
+}
 
-    save_dot_file(&dot_file, &dot_content)?;
-    run_dot_command(&dot_file, &png_file, format)?;
-    graph::open_dot_file(&png_file)?;
+/// Folder of the configuration directory caching the rows of every hop of a join.
+const JOIN_CACHE_DIR: &str = "join-cache";
 
-    Ok(())
-}
+/// File in the join cache that joins lock, shared while joining and exclusively to prune.
+const JOIN_CACHE_LOCK: &str = ".lock";
 
 /// Handle the join operation between two tables in the graph.
 fn handle_graph_join(
     config: &Config,
-    left_table: &str,
-    right_table: &str,
+    config_dir: &Path,
+    args: &GraphJoinArgs,
     g: &UnGraph<DataFrame, (String, String)>,
 ) -> Result<(), Box<dyn Error>> {
-    let left_node = find_node(g, left_table)?;
-    let right_node = find_node(g, right_table)?;
-
-    let path = find_shortest_path(g, left_node, right_node)?;
-    join_tables_along_path(g, &path, config)?;
+    let (path, options) = resolve_join(g, config, args)?;
+    if args.dry_run {
+        return check_join_inputs(g, &path, config, args, &options);
+    }
+    if let Some(format) = &args.render {
+        render_path(g, &path, config, get_type(format))?;
+    }
+    let cache_dir = match args.no_cache {
+        true => None,
+        false => Some(config_dir.join(JOIN_CACHE_DIR)),
+    };
+    join_tables_along_path(g, &path, config, cache_dir.as_deref(), args, &options)?;
 
     print_info("Join operation completed successfully.");
     Ok(())
 }
 
+/// Handle explaining how `graph join` would join tables, from the schema and file sizes
+/// alone.
+fn handle_graph_explain(
+    config: &Config,
+    args: &GraphJoinArgs,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let (path, options) = resolve_join(g, config, args)?;
+    let steps = plan_join(g, &path, &options, args.prefix_columns)?;
+    let names: Vec<&str> = path.iter().map(|&n| g[n].name.as_str()).collect();
+    println!(
+        "Join plan: {} ({}, {} {})",
+        names.join(" -> "),
+        format!("{:?}", args.r#type).to_lowercase(),
+        path.len() - 1,
+        if path.len() == 2 { "join" } else { "joins" }
+    );
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row![
+        "step",
+        "table",
+        "joins on",
+        "file",
+        "columns",
+        "est. rows"
+    ]);
+    for (i, step) in steps.iter().enumerate() {
+        let keys: Vec<String> = step
+            .keys
+            .iter()
+            .map(|(left, right)| format!("{} = {}.{}", left, step.table, right))
+            .collect();
+        let size = table_file_size(config, &g[path[i]])
+            .map_or("missing".to_string(), human_readable_bytes);
+        table.add_row(row![
+            i + 1,
+            step.table,
+            keys.join(", "),
+            size,
+            step.headers.len(),
+            step.estimated_rows
+                .map(|rows| rows.to_string())
+                .unwrap_or_default()
+        ]);
+    }
+    table.printstd();
+    if let Some(last) = steps.last() {
+        println!("Output columns: {}", last.headers.join(", "));
+    }
+    if steps.iter().any(|step| step.estimated_rows.is_none()) {
+        print_info("Run `csvg graph count-rows` to estimate the rows of every step");
+    }
+    Ok(())
+}
+
+/// Checks everything a join along `path` reads before it starts: that each table's file
+/// exists with the schema's columns, that every hop's join columns resolve in the real
+/// headers, and that the `--where` conditions name existing columns.
+fn check_join_inputs(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    config: &Config,
+    args: &GraphJoinArgs,
+    options: &JoinOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut problems = 0;
+    let mut checked = g.clone();
+    for &node in path {
+        let table = &g[node];
+        let headers = match read_table_headers(config, table) {
+            Ok(headers) => headers,
+            Err(e) => {
+                println!("{}: {}", table.name, e);
+                problems += 1;
+                continue;
+            }
+        };
+        let (missing, extra) = compare_headers(&table.headers, &headers);
+        if !extra.is_empty() {
+            println!(
+                "{}: columns not in the schema: {}",
+                table.name,
+                extra.join(", ")
+            );
+        }
+        match missing.is_empty() {
+            true => println!("{}: ok ({} columns)", table.name, headers.len()),
+            false => {
+                println!("{}: missing columns: {}", table.name, missing.join(", "));
+                problems += 1;
+            }
+        }
+        checked[node].headers = remap_headers(&table.headers, &headers);
+    }
+
+    if problems == 0 {
+        match plan_join(&checked, path, options, args.prefix_columns) {
+            Ok(steps) => {
+                for step in steps.iter().skip(1) {
+                    let keys: Vec<String> = step
+                        .keys
+                        .iter()
+                        .map(|(left, right)| format!("{} = {}.{}", left, step.table, right))
+                        .collect();
+                    println!("join {}: on {}", step.table, keys.join(", "));
+                }
+            }
+            Err(e) => {
+                println!("join columns: {}", e);
+                problems += 1;
+            }
+        }
+        for (conditions, &node) in assign_conditions(g, path, &args.r#where)?.iter().zip(path) {
+            for condition in conditions {
+                if let Err(e) = condition.clone().bind(&checked[node].headers) {
+                    println!("{}: --where {}", g[node].name, e);
+                    problems += 1;
+                }
+            }
+        }
+    }
+
+    match problems {
+        0 => {
+            println!("Dry run passed");
+            Ok(())
+        }
+        1 => Err("Dry run found 1 problem".into()),
+        n => Err(format!("Dry run found {} problems", n).into()),
+    }
+}
+
+/// Reads the header of a table's file.
+fn read_table_headers(config: &Config, table: &DataFrame) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut reader = config
+        .csv_dialect
+        .reader_builder()
+        .from_reader(open_table(config, table)?);
+    Ok(reader.headers()?.iter().map(str::to_string).collect())
+}
+
+/// The header of a table's file and its columns as the join names them.
+struct TableHeaders {
+    file: Vec<String>,
+    columns: Vec<String>,
+}
+
+/// Reads the header of every table of `path` before joining any, failing with the
+/// schema columns each file lacks and warning about the columns it adds. Returns each
+/// file's header and its columns as [`remap_headers`] names them.
+fn check_join_headers(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    config: &Config,
+) -> Result<Vec<TableHeaders>, Box<dyn Error>> {
+    let mut headers = Vec::new();
+    let mut problems = Vec::new();
+    for &node in path {
+        let table = &g[node];
+        let file = match read_table_headers(config, table) {
+            Ok(file) => file,
+            Err(e) => {
+                problems.push(format!("{}: {}", table.name, e));
+                continue;
+            }
+        };
+        let (missing, extra) = compare_headers(&table.headers, &file);
+        if !missing.is_empty() {
+            problems.push(format!(
+                "{}: missing columns {}",
+                table.name,
+                missing.join(", ")
+            ));
+        }
+        if !extra.is_empty() {
+            print_info(&format!(
+                "Warning: {} has columns not in the schema: {}",
+                table.name,
+                extra.join(", ")
+            ));
+        }
+        let columns = remap_headers(&table.headers, &file);
+        headers.push(TableHeaders { file, columns });
+    }
+    match problems.is_empty() {
+        true => Ok(headers),
+        false => Err(format!(
+            "Files do not match the schema:\n  {}",
+            problems.join("\n  ")
+        )
+        .into()),
+    }
+}
+
+/// Names a file's columns as the schema spells them where they differ only in case, so
+/// that `ID` in a file matches `id` in the schema.
+pub fn remap_headers(schema: &[String], file: &[String]) -> Vec<String> {
+    file.iter()
+        .map(|column| {
+            schema
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(column))
+                .unwrap_or(column)
+                .clone()
+        })
+        .collect()
+}
+
+/// Compares a table's columns in the schema with the header of its file, in any order
+/// and case, returning the schema's columns the file lacks and the file's columns the
+/// schema lacks.
+pub fn compare_headers(schema: &[String], file: &[String]) -> (Vec<String>, Vec<String>) {
+    let lacks = |headers: &[String], column: &String| {
+        !headers.iter().any(|h| h.eq_ignore_ascii_case(column))
+    };
+    let missing = schema.iter().filter(|c| lacks(file, c)).cloned().collect();
+    let extra = file.iter().filter(|c| lacks(schema, c)).cloned().collect();
+    (missing, extra)
+}
+
+/// Resolves the tables `graph join` joins, in order, and the options of its joins.
+fn resolve_join(
+    g: &UnGraph<DataFrame, (String, String)>,
+    config: &Config,
+    args: &GraphJoinArgs,
+) -> Result<(Vec<NodeIndex>, JoinOptions), Box<dyn Error>> {
+    let options = JoinOptions {
+        ignore_case: args.ignore_case,
+        select: args.select.clone(),
+        ..JoinOptions::default()
+    };
+    if matches!(args.r#type, JoinType::Asof) {
+        return Err("An as-of join needs sorted files; use `csv join --type asof` instead".into());
+    }
+    let path = match args.tables.as_slice() {
+        [] => find_explicit_path(g, &args.path)?,
+        [left_table, right_table] => {
+            let left_node = find_node(g, left_table)?;
+            let right_node = find_node(g, right_table)?;
+            let via = find_nodes(g, &args.via)?;
+            find_path_via(g, left_node, &via, right_node, args.weight)?
+        }
+        tables => {
+            if !args.via.is_empty() {
+                return Err("--via applies to a join of two tables".into());
+            }
+            find_join_order(g, tables)?
+        }
+    };
+    if args.order == JoinOrder::Size {
+        if !matches!(args.r#type, JoinType::Inner) {
+            return Err(
+                "--order size needs an inner join, whose rows do not depend on the order".into(),
+            );
+        }
+        // Row counts and file sizes are not comparable, so counts are only used when
+        // every table has one.
+        let counted = path.iter().all(|&n| g[n].row_count.is_some());
+        let size = |node: NodeIndex| match counted {
+            true => g[node].row_count.unwrap_or(0),
+            false => table_file_size(config, &g[node]).unwrap_or(u64::MAX),
+        };
+        return Ok((order_joins_by_size(g, &path, size), options));
+    }
+    Ok((path, options))
+}
+
+/// Orders `tables`, which foreign keys connect, to join the smallest first: starting
+/// from the smallest table, it adds the smallest table with a foreign key to or from one
+/// already added, keeping the given order between tables of the same size.
+pub fn order_joins_by_size(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[NodeIndex],
+    size: impl Fn(NodeIndex) -> u64,
+) -> Vec<NodeIndex> {
+    let mut order: Vec<NodeIndex> = Vec::with_capacity(tables.len());
+    while order.len() < tables.len() {
+        let remaining: Vec<NodeIndex> = tables
+            .iter()
+            .copied()
+            .filter(|node| !order.contains(node))
+            .collect();
+        // A table connected to none added so far waits its turn in the given order.
+        let next = remaining
+            .iter()
+            .copied()
+            .filter(|&node| order.is_empty() || order.iter().any(|&o| g.contains_edge(o, node)))
+            .min_by_key(|&node| size(node))
+            .unwrap_or(remaining[0]);
+        order.push(next);
+    }
+    order
+}
+
+/// Size in bytes of a table's file.
+fn table_file_size(config: &Config, table: &DataFrame) -> Option<u64> {
+    table_file(config, table).map(|(_, file)| file.len())
+}
+
+/// The file holding the rows of `table`, as [`open_table`] finds it, with its metadata.
+fn table_file(config: &Config, table: &DataFrame) -> Option<(PathBuf, std::fs::Metadata)> {
+    ["csv", "parquet"].iter().find_map(|ext| {
+        let path = config.source_path.join(format!("{}.{}", table.name, ext));
+        std::fs::metadata(&path).ok().map(|file| (path, file))
+    })
+}
+
 /// Find a node in the graph by table name.
 pub fn find_node(
     g: &UnGraph<DataFrame, (String, String)>,
     table: &str,
 ) -> Result<NodeIndex, Box<dyn Error>> {
-    g.node_indices()
-        .find(|&node| g[node].name == table)
+    graph::find_table(g.node_weights(), table)
+        .map(NodeIndex::new)
         .ok_or_else(|| format!("Table '{}' not found in graph", table).into())
 }
 
-/// Find the shortest path between two nodes in the graph.
+/// Cost of one join when paths are weighed by joins, above any total of row counts so
+/// that rows only decide between paths of as many joins.
+const JOIN_COST: u128 = 1 << 64;
+
+/// Find the shortest path between two nodes in the graph. When several paths have as
+/// few joins, the one through the fewest rows is taken, as counted by `graph count-rows`.
 pub fn find_shortest_path(
     g: &UnGraph<DataFrame, (String, String)>,
     start: NodeIndex,
     end: NodeIndex,
 ) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
-    let res = dijkstra(g, start, Some(end), |_| 1);
+    find_weighted_shortest_path(g, start, end, PathWeight::Joins)
+}
+
+/// Find the cheapest path between two nodes, where joining a table costs one join or,
+/// with [`PathWeight::Rows`], its row count. Tables without a count cost one row.
+pub fn find_weighted_shortest_path(
+    g: &UnGraph<DataFrame, (String, String)>,
+    start: NodeIndex,
+    end: NodeIndex,
+    weight: PathWeight,
+) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
+    let cost = |node: NodeIndex| {
+        let rows = u128::from(g[node].row_count.unwrap_or(0));
+        match weight {
+            PathWeight::Joins => JOIN_COST + rows,
+            PathWeight::Rows => rows.max(1),
+        }
+    };
+    let res = dijkstra(g, start, Some(end), |edge| cost(edge.target()));
+    if !res.contains_key(&end) {
+        return Err(format!(
+            "No path between '{}' and '{}': they are in different connected components \
+             (see `csvg graph components`)",
+            g[start].name, g[end].name
+        )
+        .into());
+    }
     let mut path = Vec::new();
     let mut current = end;
 
     while current != start {
         path.push(current);
+        let before = res[&current] - cost(current);
         current = g
             .neighbors(current)
-            .filter(|n| res.contains_key(n))
-            .min_by_key(|n| res[n])
+            .find(|n| res.get(n) == Some(&before))
             .ok_or("Path reconstruction failed")?;
     }
     path.push(start);
@@ -138,170 +604,2301 @@ pub fn find_shortest_path(
     Ok(path)
 }
 
-/// Join tables along the shortest path between two nodes.
-fn join_tables_along_path(
+/// Join tables along the shortest path between two nodes with the join type of `args`
+/// at every hop, so a left join keeps every row of the first table, a right join every
+/// row of the last, and a full join the rows of all of them. Inner joins of a tree with
+/// independent branches of several tables join each branch on its own thread before
+/// joining it at its junction table, naming and ordering the columns as joining the
+/// tables in path order would. The result goes to the configured output file
+/// unless `args` asks for standard output or PostgreSQL.
+///
+/// With a `cache_dir`, the rows of every hop are kept there under a hash of its inputs
+/// and options, so running the join again reuses the hops whose tables, conditions and
+/// options did not change, as after a failure. Once the join succeeds, only its own
+/// hops stay cached, unless another join is running with the same cache.
+pub fn join_tables_along_path(
     g: &UnGraph<DataFrame, (String, String)>,
     path: &[NodeIndex],
     config: &Config,
+    cache_dir: Option<&Path>,
+    args: &GraphJoinArgs,
+    options: &JoinOptions,
 ) -> Result<(), Box<dyn Error>> {
     if path.is_empty() {
         return Err("Path is empty".into());
     }
+    let cache_lock = match cache_dir {
+        Some(cache_dir) => {
+            std::fs::create_dir_all(cache_dir)?;
+            let lock = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(cache_dir.join(JOIN_CACHE_LOCK))?;
+            lock.lock_shared()?;
+            Some(lock)
+        }
+        None => None,
+    };
+    let headers = check_join_headers(g, path, config)?;
+    let parents = join_parents(g, path);
+    // Branches joined on their own would name and order columns by branch, so they are
+    // joined with every column named after its table and renamed at the end.
+    let columns = match matches!(args.r#type, JoinType::Inner) && has_independent_branches(&parents)
+    {
+        true => Some(path_join_columns(
+            g,
+            path,
+            &headers,
+            options,
+            args.prefix_columns,
+        )?),
+        false => None,
+    };
+    let options = match &columns {
+        Some(columns) => JoinOptions {
+            select: columns
+                .iter()
+                .map(|(_, prefixed)| prefixed.clone())
+                .collect(),
+            ..options.clone()
+        },
+        None => options.clone(),
+    };
+    let run = JoinRun {
+        g,
+        path,
+        config,
+        cache_dir,
+        cached: Mutex::new(Vec::new()),
+        cache_lock,
+        args,
+        options: &options,
+        prefix_columns: args.prefix_columns || columns.is_some(),
+        conditions: assign_conditions(g, path, &args.r#where)?,
+        headers,
+    };
+    let joined = match &columns {
+        Some(columns) => {
+            let joined = run.join_subtree(0, &parents)?;
+            run.rename_columns(joined, columns)?
+        }
+        None => {
+            (1..path.len()).try_fold(run.start(0)?, |joined, step| run.add_table(joined, step))?
+        }
+    };
+    write_join_output(config, args, joined.file.path())?;
+    run.prune_cache()
+}
+
+/// Writes the joined rows in `path` where `args` asks: PostgreSQL, standard output or
+/// the configured output file.
+fn write_join_output(
+    config: &Config,
+    args: &GraphJoinArgs,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(table) = &args.copy_to {
+        return export_to_postgres(config, path, table);
+    }
 
-    let mut current_df = g[path[0]].clone();
-    let mut temp_file = NamedTempFile::new()?;
+    if args.stdout {
+        let mut final_writer = BufWriter::new(std::io::stdout());
+        csv::convert_csv(
+            path,
+            &mut final_writer,
+            args.format,
+            &config.csv_dialect,
+            None,
+        )?;
+        final_writer.flush()?;
+        return Ok(());
+    }
 
-    // Copy first table to temp file
-    {
-        let mut writer = BufWriter::new(&temp_file);
-        let mut reader = BufReader::new(File::open(
-            &config.source_path.join(format!("{}.csv", current_df.name)),
-        )?);
-        let bytes_copied = std::io::copy(&mut reader, &mut writer)?;
-        writer.flush()?;
-        print_info(&format!(
-            "Initial file size: {}",
-            human_readable_bytes(bytes_copied)
-        ));
+    // Written next to the output file and renamed, so a failed join leaves it intact.
+    let output_file = Path::new(&config.output_file);
+    let output_dir = match output_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(output_dir)?;
+    let mut final_writer = BufWriter::new(NamedTempFile::new_in(output_dir)?);
+    csv::convert_csv(
+        path,
+        &mut final_writer,
+        args.format,
+        &config.csv_dialect,
+        None,
+    )?;
+    let output = final_writer.into_inner().map_err(|e| e.into_error())?;
+    let written = output.as_file().metadata()?.len();
+    output
+        .persist(output_file)
+        .map_err(|e| format!("Failed to write '{}': {}", output_file.display(), e.error))?;
+
+    print_info(&format!(
+        "written {} to {}",
+        human_readable_bytes(written),
+        config::display_relative_path(output_file)
+    ));
+
+    Ok(())
+}
+
+/// What every hop of a join along `path` shares.
+struct JoinRun<'a> {
+    g: &'a UnGraph<DataFrame, (String, String)>,
+    path: &'a [NodeIndex],
+    config: &'a Config,
+    /// Where the rows of every hop are kept, unless joining without a cache.
+    cache_dir: Option<&'a Path>,
+    /// Cached files the join used so far.
+    cached: Mutex<Vec<PathBuf>>,
+    /// Shared lock on the cache, held while joining so no other join prunes it.
+    cache_lock: Option<File>,
+    args: &'a GraphJoinArgs,
+    options: &'a JoinOptions,
+    /// Whether columns are named after their table, as `--prefix-columns` asks and
+    /// joining branches on their own needs.
+    prefix_columns: bool,
+    /// Conditions on the rows of each table of `path`.
+    conditions: Vec<Vec<Expr>>,
+    /// Headers of the file of each table of `path`.
+    headers: Vec<TableHeaders>,
+}
+
+/// Rows joined so far: the steps of the path they come from, the hash of what they
+/// were joined from and the file holding them.
+struct Joined {
+    df: DataFrame,
+    steps: Vec<usize>,
+    key: String,
+    file: JoinedFile,
+}
+
+/// A file of joined rows, removed once used unless it is kept in the cache.
+enum JoinedFile {
+    Temporary(TempPath),
+    Cached(PathBuf),
+}
+
+impl JoinedFile {
+    fn path(&self) -> &Path {
+        match self {
+            JoinedFile::Temporary(path) => path,
+            JoinedFile::Cached(path) => path,
+        }
+    }
+}
+
+/// One hop of a join: the rows of `next`, hashed as `key`, joined on `keys` with
+/// `options`, adding the tables at `steps` of the path.
+struct Hop<'a> {
+    next: &'a DataFrame,
+    key: String,
+    keys: Vec<(String, String)>,
+    options: JoinOptions,
+    steps: Vec<usize>,
+}
+
+impl JoinRun<'_> {
+    /// Starts joining from the table at `step` with a copy of its rows.
+    fn start(&self, step: usize) -> Result<Joined, Box<dyn Error>> {
+        // The first table's rows are read by position, so its columns come from its file.
+        let mut first = self.g[self.path[step]].clone();
+        let columns = &self.headers[step].columns;
+        first.header_indices = columns
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.clone(), i))
+            .collect();
+        first.headers = columns.clone();
+        let df = match self.prefix_columns {
+            true => prefixed_table(&first),
+            false => first,
+        }
+        .with_dialect(self.config.csv_dialect.clone());
+
+        let key = self.table_key(step);
+        let file = self.joined_file(&key, &df.name, |writer| {
+            let mut reader = BufReader::new(open_filtered_table(
+                self.config,
+                &self.g[self.path[step]],
+                None,
+                &self.conditions[step],
+            )?);
+            let bytes_copied = std::io::copy(&mut reader, writer)?;
+            print_info(&format!(
+                "Initial {} file size: {}",
+                df.name,
+                human_readable_bytes(bytes_copied)
+            ));
+            Ok(())
+        })?;
+        Ok(Joined {
+            df,
+            steps: vec![step],
+            key,
+            file,
+        })
     }
 
-    for (i, (_, &next_node)) in path.iter().zip(path.iter().skip(1)).enumerate() {
-        let next_df = &g[next_node];
-        print_info(&format!("Joining {} and {}", current_df.name, next_df.name));
+    /// Hash of the rows read from the table at `step`: its file as last modified, the
+    /// conditions on them and how their columns are named.
+    fn table_key(&self, step: usize) -> String {
+        let table = &self.g[self.path[step]];
+        let file = table_file(self.config, table)
+            .map(|(path, file)| (path, file.len(), file.modified().ok()));
+        config::schema_hash(&format!(
+            "{} {} {:?} {:?} {:?} {:?} {:?} {}",
+            env!("CARGO_PKG_VERSION"),
+            table.name,
+            file,
+            self.conditions[step],
+            self.headers[step].file,
+            self.headers[step].columns,
+            self.config.csv_dialect,
+            self.prefix_columns
+        ))
+    }
 
-        let mut left_reader = BufReader::new(temp_file.reopen()?);
-        let mut right_reader = BufReader::new(File::open(
-            &config.source_path.join(format!("{}.csv", next_df.name)),
-        )?);
+    /// The file of the rows hashed as `key`, those of `rows`: the one cached by an
+    /// earlier run when there is one, or else one `write` fills.
+    fn joined_file(
+        &self,
+        key: &str,
+        rows: &str,
+        write: impl FnOnce(&mut BufWriter<&File>) -> Result<(), Box<dyn Error>>,
+    ) -> Result<JoinedFile, Box<dyn Error>> {
+        let cached = self.cache_dir.map(|dir| dir.join(format!("{}.csv", key)));
+        if let Some(path) = &cached {
+            self.cached
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(path.clone());
+            if path.exists() {
+                print_info(&format!("Reusing the cached rows of {}", rows));
+                return Ok(JoinedFile::Cached(path.clone()));
+            }
+        }
 
-        let new_temp_file = NamedTempFile::new()?;
-        let (left_col, right_col) = find_join_columns(&current_df, next_df)?;
+        let file = match self.cache_dir {
+            Some(dir) => NamedTempFile::new_in(dir)?,
+            None => NamedTempFile::new()?,
+        };
         {
-            let mut writer = BufWriter::new(&new_temp_file);
-
-            current_df.join_stream(
-                &mut left_reader,
-                &mut right_reader,
-                &mut writer,
-                &left_col,
-                &right_col,
-                &JoinType::Inner,
-            )?;
+            let mut writer = BufWriter::new(file.as_file());
+            write(&mut writer)?;
             writer.flush()?;
         }
+        match cached {
+            // Written aside and renamed, so a failed hop leaves nothing to resume from.
+            Some(path) => {
+                file.persist(&path).map_err(|e| e.error)?;
+                Ok(JoinedFile::Cached(path))
+            }
+            None => Ok(JoinedFile::Temporary(file.into_temp_path())),
+        }
+    }
+
+    /// Removes the files of the cache the join did not use, unless another join is using
+    /// the cache too.
+    fn prune_cache(&self) -> Result<(), Box<dyn Error>> {
+        let (Some(cache_dir), Some(lock)) = (self.cache_dir, &self.cache_lock) else {
+            return Ok(());
+        };
+        lock.unlock()?;
+        match lock.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(()),
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        let used = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        for entry in std::fs::read_dir(cache_dir)? {
+            let path = entry?.path();
+            if !used.contains(&path) && path != cache_dir.join(JOIN_CACHE_LOCK) {
+                std::fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tables of the path neither in `joined` nor among `adding`.
+    fn later(&self, joined: &Joined, adding: &[usize]) -> Vec<NodeIndex> {
+        (0..self.path.len())
+            .filter(|step| !joined.steps.contains(step) && !adding.contains(step))
+            .map(|step| self.path[step])
+            .collect()
+    }
+
+    /// Joins the rows of the table at `step` to `joined`.
+    fn add_table(&self, joined: Joined, step: usize) -> Result<Joined, Box<dyn Error>> {
+        let next = &self.g[self.path[step]];
+        let headers = &self.headers[step];
+        let keys = find_hop_keys(self.g, self.path, step, &joined.df)?;
+        let hop = Hop {
+            next,
+            key: self.table_key(step),
+            options: table_hop_options(
+                self.g,
+                &joined.df,
+                next,
+                &self.later(&joined, &[step]),
+                self.options,
+                self.prefix_columns,
+            ),
+            keys,
+            steps: vec![step],
+        };
+        let df = add_joined_table(&joined.df, next, &hop.keys, self.prefix_columns);
+        self.join(joined, hop, df, || {
+            open_filtered_table(
+                self.config,
+                next,
+                (headers.file != headers.columns).then_some(&headers.columns),
+                &self.conditions[step],
+            )
+        })
+    }
 
-        temp_file = new_temp_file;
-        let file_size = temp_file.as_file().metadata()?.len();
+    /// Joins `branch`, the rows joined from the table at `step` on, to `joined`.
+    fn add_branch(
+        &self,
+        joined: Joined,
+        step: usize,
+        branch: Joined,
+    ) -> Result<Joined, Box<dyn Error>> {
+        let mut keys = find_hop_keys(self.g, self.path, step, &joined.df)?;
+        if self.prefix_columns {
+            for (_, right) in &mut keys {
+                *right = format!("{}.{}", branch.df.name, right);
+            }
+        }
+        let hop = Hop {
+            next: &branch.df,
+            key: branch.key.clone(),
+            options: hop_options(
+                self.g,
+                &joined.df,
+                &branch.df,
+                &self.later(&joined, &branch.steps),
+                self.options,
+                self.prefix_columns,
+            ),
+            keys,
+            steps: branch.steps.clone(),
+        };
+        let df = update_dataframe_after_join(&joined.df, &branch.df, &hop.keys);
+        self.join(joined, hop, df, || Ok(File::open(branch.file.path())?))
+    }
+
+    /// Joins the rows `open` opens to `joined` as `hop` says, for `df` to describe with
+    /// the header they were written with. Rows an earlier run cached are reused instead.
+    fn join(
+        &self,
+        joined: Joined,
+        hop: Hop,
+        mut df: DataFrame,
+        open: impl FnOnce() -> Result<File, Box<dyn Error>>,
+    ) -> Result<Joined, Box<dyn Error>> {
+        let key = config::schema_hash(&format!(
+            "{} {} {:?} {:?} {:?}",
+            joined.key, hop.key, hop.keys, self.args.r#type, hop.options
+        ));
+        let rows = format!("{} and {}", joined.df.name, hop.next.name);
+        let file = self.joined_file(&key, &rows, |writer| {
+            print_info(&format!("Joining {}", rows));
+            joined.df.join_stream(
+                &mut BufReader::new(File::open(joined.file.path())?),
+                &mut BufReader::new(open()?),
+                writer,
+                &hop.keys,
+                &self.args.r#type,
+                &hop.options,
+            )
+        })?;
+
+        let file_size = std::fs::metadata(file.path())?.len();
         print_info(&format!(
-            "Size after join {}: {}",
-            i + 1,
+            "Size after joining {}: {}",
+            hop.next.name,
             human_readable_bytes(file_size)
         ));
         if file_size == 0 {
             print_info("Warning: Join produced no results");
             return Err("Join produced no results".into());
         }
-
-        current_df = update_dataframe_after_join(&current_df, next_df, &left_col, &right_col);
+        // Pick up the suffixes given to colliding columns in the joined header.
+        df.read_headers(file.path())?;
+        let mut steps = joined.steps;
+        steps.extend(hop.steps);
+        Ok(Joined {
+            df,
+            steps,
+            key,
+            file,
+        })
     }
 
-    if let Some(parent) = Path::new(&config.output_file).parent() {
-        std::fs::create_dir_all(parent)?;
+    /// Gives the columns of `joined`, named after their table, the names of `columns`
+    /// pairing each name with the prefixed one.
+    fn rename_columns(
+        &self,
+        mut joined: Joined,
+        columns: &[(String, String)],
+    ) -> Result<Joined, Box<dyn Error>> {
+        let names: Vec<String> = columns.iter().map(|(name, _)| name.clone()).collect();
+        if names == joined.df.headers {
+            return Ok(joined);
+        }
+        let key = config::schema_hash(&format!("{} {:?}", joined.key, names));
+        let file = self.joined_file(&key, &joined.df.name, |writer| {
+            joined.df.write_row(writer, &names)?;
+            std::io::copy(&mut joined.df.open_rows(joined.file.path())?, writer)?;
+            Ok(())
+        })?;
+        joined.df.header_indices = names
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.clone(), i))
+            .collect();
+        joined.df.headers = names;
+        Ok(Joined {
+            key,
+            file,
+            ..joined
+        })
     }
 
-    let mut final_reader = BufReader::new(temp_file.reopen()?);
-    let stdout = std::io::stdout();
-    let mut final_writer = BufWriter::new(stdout.lock());
-    let bytes_copied = std::io::copy(&mut final_reader, &mut final_writer)?;
-    final_writer.flush()?;
-
-    print_info(&format!(
-        "written {} to {}",
-        human_readable_bytes(bytes_copied),
-        config.output_file
-    ));
-
-    Ok(())
-}
+    /// Joins the table at `step` with the tables below it in the join tree of `parents`,
+    /// joining the branches below it that have several tables on threads of their own.
+    fn join_subtree(&self, step: usize, parents: &[usize]) -> Result<Joined, Box<dyn Error>> {
+        let children: Vec<usize> = (step + 1..self.path.len())
+            .filter(|&s| parents[s] == step)
+            .collect();
+        let branches = std::thread::scope(|scope| {
+            let handles: Vec<_> = children
+                .iter()
+                .map(|&child| {
+                    (subtree_size(parents, child) > 1).then(|| {
+                        scope.spawn(move || {
+                            self.join_subtree(child, parents).map_err(|e| e.to_string())
+                        })
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .map(|h| {
+                            h.join()
+                                .unwrap_or_else(|_| Err("A branch of the join failed".into()))
+                        })
+                        .transpose()
+                })
+                .collect::<Result<Vec<Option<Joined>>, String>>()
+        })?;
 
-/// Find suitable join columns between two DataFrames.
-pub fn find_join_columns(
-    left: &DataFrame,
-    right: &DataFrame,
-) -> Result<(String, String), Box<dyn Error>> {
-    for (left_col, _, right_col) in &left.foreign_keys {
-        if right.headers.contains(right_col) {
-            return Ok((left_col.clone(), right_col.clone()));
-        }
-    }
-    for (right_col, _, left_col) in &right.foreign_keys {
-        if left.headers.contains(left_col) {
-            return Ok((left_col.clone(), right_col.clone()));
+        let mut joined = self.start(step)?;
+        for (child, branch) in children.into_iter().zip(branches) {
+            joined = match branch {
+                Some(branch) => self.add_branch(joined, child, branch)?,
+                None => self.add_table(joined, child)?,
+            };
         }
+        Ok(joined)
     }
-    Err("No suitable join columns found".into())
 }
 
-/// Update the DataFrame after a join operation.
-pub fn update_dataframe_after_join(
-    left_df: &DataFrame,
-    right_df: &DataFrame,
-    left_col: &str,
-    right_col: &str,
-) -> DataFrame {
-    let mut new_df = left_df.clone();
-
-    new_df
-        .headers
-        .extend(right_df.headers.iter().filter(|&h| h != right_col).cloned());
-
-    new_df.header_indices = new_df
-        .headers
-        .iter()
-        .enumerate()
-        .map(|(i, h)| (h.clone(), i))
+/// The columns a join along `path` writes, in order and named as joining one table
+/// after the other names them, each paired with its name when every column is named
+/// after its table. Tables have the columns of their files, as `headers` lists them.
+fn path_join_columns(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    headers: &[TableHeaders],
+    options: &JoinOptions,
+    prefix_columns: bool,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut files = g.clone();
+    for (&node, headers) in path.iter().zip(headers) {
+        let table = &mut files[node];
+        table.header_indices = headers
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.clone(), i))
+            .collect();
+        table.headers = headers.columns.clone();
+    }
+    let every_column = JoinOptions {
+        select: Vec::new(),
+        ..options.clone()
+    };
+    let output_headers = |prefix_columns| -> Result<Vec<String>, Box<dyn Error>> {
+        let steps = plan_join(&files, path, &every_column, prefix_columns)?;
+        Ok(steps
+            .last()
+            .map(|step| step.headers.clone())
+            .unwrap_or_default())
+    };
+    let columns: Vec<(String, String)> = output_headers(prefix_columns)?
+        .into_iter()
+        .zip(output_headers(true)?)
         .collect();
-
-    new_df.foreign_keys.extend(
-        right_df
-            .foreign_keys
+    match options.select.is_empty() {
+        true => Ok(columns),
+        false => options
+            .select
             .iter()
-            .filter(|&(col, _, _)| col != right_col)
-            .cloned(),
-    );
-
-    if right_df.primary_key.as_ref() == Some(&right_col.to_string()) {
-        new_df.primary_key = Some(left_col.to_string());
+            .map(|column| {
+                columns
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .cloned()
+                    .ok_or_else(|| format!("Column '{}' not found in join output", column).into())
+            })
+            .collect(),
     }
+}
 
-    new_df
+/// The step of `path` each table joins to, the first table it is joined with by a
+/// foreign key, making a tree rooted at the first table, which is its own parent.
+pub fn join_parents(g: &UnGraph<DataFrame, (String, String)>, path: &[NodeIndex]) -> Vec<usize> {
+    (0..path.len())
+        .map(|step| {
+            (0..step)
+                .find(|&earlier| g.contains_edge(path[earlier], path[step]))
+                .unwrap_or(step.saturating_sub(1))
+        })
+        .collect()
 }
 
-/// Handle the shortest path operation between two nodes in the graph.
-fn handle_graph_shortest_path(
-    from: &str,
-    to: &str,
-    g: &UnGraph<DataFrame, (String, String)>,
-) -> Result<(), Box<dyn Error>> {
-    let from_index = find_node(g, from)?;
-    let to_index = find_node(g, to)?;
+/// Number of tables in the subtree of the join tree of `parents` rooted at `step`.
+fn subtree_size(parents: &[usize], step: usize) -> usize {
+    (step..parents.len())
+        .filter(|&s| {
+            let mut node = s;
+            while node > step {
+                node = parents[node];
+            }
+            node == step
+        })
+        .count()
+}
 
-    let path = find_shortest_path(g, from_index, to_index)?;
-    let path_str: Vec<String> = path.iter().map(|&n| g[n].name.clone()).collect();
-    println!("Shortest path: {}", path_str.join(" -> "));
+/// Whether some table of the join tree of `parents` has at least two branches below it
+/// of several tables each, which can be joined independently.
+pub fn has_independent_branches(parents: &[usize]) -> bool {
+    (0..parents.len()).any(|step| {
+        (step + 1..parents.len())
+            .filter(|&s| parents[s] == step && subtree_size(parents, s) > 1)
+            .count()
+            > 1
+    })
+}
 
+/// Copies the joined rows in `path` into `table` of the database named by the
+/// configured `postgres_url`.
+#[cfg(feature = "postgres")]
+fn export_to_postgres(config: &Config, path: &Path, table: &str) -> Result<(), Box<dyn Error>> {
+    let url = config
+        .postgres_url
+        .as_deref()
+        .ok_or("Set postgres_url in the config to copy results to PostgreSQL")?;
+    let rows = sql::copy_to_postgres(path, url, table, &config.csv_dialect)?;
+    print_info(&format!(
+        "Copied {} rows to PostgreSQL table {}",
+        rows, table
+    ));
     Ok(())
 }
 
-/// Handle the Minimum Spanning Tree operation .
+#[cfg(not(feature = "postgres"))]
+fn export_to_postgres(_config: &Config, _path: &Path, _table: &str) -> Result<(), Box<dyn Error>> {
+    Err(sql::POSTGRES_DISABLED.into())
+}
+
+/// Opens the rows of `table`: `<table>.csv` in the source path, or with the `parquet`
+/// feature, `<table>.parquet` converted to CSV when there is no CSV file.
+fn open_table(config: &Config, table: &DataFrame) -> Result<File, Box<dyn Error>> {
+    let path = config.source_path.join(format!("{}.csv", table.name));
+    #[cfg(feature = "parquet")]
+    {
+        let parquet_path = config.source_path.join(format!("{}.parquet", table.name));
+        if !path.exists() && parquet_path.exists() {
+            let mut converted = tempfile::tempfile()?;
+            let mut writer = BufWriter::new(&converted);
+            table.parquet_to_csv(File::open(parquet_path)?, &mut writer)?;
+            writer.flush()?;
+            drop(writer);
+            converted.seek(SeekFrom::Start(0))?;
+            return Ok(converted);
+        }
+    }
+    File::open(&path).map_err(|e| format!("Failed to open file '{:?}': {}", path, e).into())
+}
+
+/// One table of a join plan and what the rows joined so far look like after it.
+#[derive(Debug)]
+pub struct JoinStep {
+    pub table: String,
+    /// `(joined column, table column)` pairs the table joins on; none for the first.
+    pub keys: Vec<(String, String)>,
+    pub headers: Vec<String>,
+    /// Rows expected after the step, once `graph count-rows` has counted the tables.
+    pub estimated_rows: Option<u64>,
+}
+
+/// Plans a join along `path` from the schema alone: the keys each hop joins on and the
+/// header it writes, as [`join_tables_along_path`] would, and the rows to expect.
+pub fn plan_join(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    options: &JoinOptions,
+    prefix_columns: bool,
+) -> Result<Vec<JoinStep>, Box<dyn Error>> {
+    let Some(&first) = path.first() else {
+        return Err("Path is empty".into());
+    };
+    let mut current = match prefix_columns {
+        true => prefixed_table(&g[first]),
+        false => g[first].clone(),
+    };
+    let mut estimated_rows = current.row_count;
+    let mut steps = vec![JoinStep {
+        table: current.name.clone(),
+        keys: Vec::new(),
+        headers: current.headers.clone(),
+        estimated_rows,
+    }];
+    for step in 1..path.len() {
+        let next = &g[path[step]];
+        let keys = find_hop_keys(g, path, step, &current)?;
+        let hop_options = table_hop_options(
+            g,
+            &current,
+            next,
+            &path[step + 1..],
+            options,
+            prefix_columns,
+        );
+        let headers = current.join_output_headers(&next.headers, &keys, &hop_options)?;
+        estimated_rows = estimate_hop_rows(g, path, step, estimated_rows);
+
+        current = add_joined_table(&current, next, &keys, prefix_columns);
+        current.header_indices = headers
+            .iter()
+            .enumerate()
+            .map(|(i, h)| (h.clone(), i))
+            .collect();
+        current.headers = headers.clone();
+        steps.push(JoinStep {
+            table: next.name.clone(),
+            keys,
+            headers,
+            estimated_rows,
+        });
+    }
+    Ok(steps)
+}
+
+/// Rows expected once the table at `step` of `path` joins `rows` joined rows: as many
+/// when the table it joins references it, as each row matches at most one of its rows,
+/// or as many per row of that table as it has when it holds the foreign key.
+fn estimate_hop_rows(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    step: usize,
+    rows: Option<u64>,
+) -> Option<u64> {
+    let next = path[step];
+    let (earlier, edge) = path[..step]
+        .iter()
+        .find_map(|&n| g.find_edge(n, next).map(|edge| (n, edge)))?;
+    let (src, _) = g.edge_endpoints(edge)?;
+    match src == next {
+        false => rows,
+        true => {
+            let per_row = g[next].row_count? as f64 / g[earlier].row_count?.max(1) as f64;
+            Some((rows? as f64 * per_row).round() as u64)
+        }
+    }
+}
+
+/// Options of the join adding the table `next` to `current`, before the tables of
+/// `later`. Columns the table shares with the joined ones are named after it, or all of
+/// its columns are with `prefix_columns`.
+fn table_hop_options(
+    g: &UnGraph<DataFrame, (String, String)>,
+    current: &DataFrame,
+    next: &DataFrame,
+    later: &[NodeIndex],
+    options: &JoinOptions,
+    prefix_columns: bool,
+) -> JoinOptions {
+    let named = match prefix_columns {
+        true => prefixed_table(next),
+        false => next.clone(),
+    };
+    JoinOptions {
+        right_alias: prefix_columns.then(|| next.name.clone()),
+        ..hop_options(g, current, &named, later, options, prefix_columns)
+    }
+}
+
+/// Options of the join adding `next`, with its columns named as in its rows, to
+/// `current`, before the tables of `later`. Columns `next` shares with the joined ones
+/// are named after it. Every hop but the last keeps the selected columns it has and the
+/// keys later hops join on, and rows only on the right keep their key values.
+fn hop_options(
+    g: &UnGraph<DataFrame, (String, String)>,
+    current: &DataFrame,
+    next: &DataFrame,
+    later: &[NodeIndex],
+    options: &JoinOptions,
+    prefix_columns: bool,
+) -> JoinOptions {
+    let is_last_hop = later.is_empty();
+    let later: Vec<DataFrame> = later
+        .iter()
+        .map(|&n| match prefix_columns {
+            true => prefixed_table(&g[n]),
+            false => g[n].clone(),
+        })
+        .collect();
+    JoinOptions {
+        right_suffix: format!("_{}", next.name),
+        select: match is_last_hop || options.select.is_empty() {
+            true => options.select.clone(),
+            false => find_columns_to_keep(
+                current,
+                next,
+                &later.iter().collect::<Vec<_>>(),
+                &options.select,
+            ),
+        },
+        skip_missing_select: !is_last_hop,
+        keep_right_keys: true,
+        ..options.clone()
+    }
+}
+
+/// A copy of `table` whose columns, keys included, are named after it, as in
+/// `users.name`, with foreign keys naming the columns they reference likewise.
+pub fn prefixed_table(table: &DataFrame) -> DataFrame {
+    let prefixed = |table: &str, column: &str| format!("{}.{}", table, column);
+    let mut prefixed_df = table.clone();
+    prefixed_df.headers = table
+        .headers
+        .iter()
+        .map(|h| prefixed(&table.name, h))
+        .collect();
+    prefixed_df.header_indices = prefixed_df
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.clone(), i))
+        .collect();
+    prefixed_df.primary_key = table
+        .primary_key
+        .as_ref()
+        .map(|key| prefixed(&table.name, key));
+    prefixed_df.foreign_keys = table
+        .foreign_keys
+        .iter()
+        .map(|(column, dst, dst_column)| {
+            (
+                prefixed(&table.name, column),
+                dst.clone(),
+                prefixed(dst, dst_column),
+            )
+        })
+        .collect();
+    prefixed_df.composite_foreign_keys = table
+        .composite_foreign_keys
+        .iter()
+        .map(|columns| columns.iter().map(|c| prefixed(&table.name, c)).collect())
+        .collect();
+    prefixed_df
+}
+
+/// The frame of the rows joined so far once `next` joins them on `keys`, with the
+/// columns of `next` named after it under `prefix_columns`.
+fn add_joined_table(
+    current: &DataFrame,
+    next: &DataFrame,
+    keys: &[(String, String)],
+    prefix_columns: bool,
+) -> DataFrame {
+    match prefix_columns {
+        false => update_dataframe_after_join(current, next, keys),
+        true => {
+            let keys: Vec<(String, String)> = keys
+                .iter()
+                .map(|(left, right)| (left.clone(), format!("{}.{}", next.name, right)))
+                .collect();
+            update_dataframe_after_join(current, &prefixed_table(next), &keys)
+        }
+    }
+}
+
+/// Opens a table's rows like [`open_table`], keeping only those matching every condition
+/// and, when given, heading them with `headers` instead of the file's header.
+fn open_filtered_table(
+    config: &Config,
+    table: &DataFrame,
+    headers: Option<&[String]>,
+    conditions: &[Expr],
+) -> Result<File, Box<dyn Error>> {
+    let mut file = open_table(config, table)?;
+    let predicate = conditions
+        .iter()
+        .cloned()
+        .reduce(|left, right| Expr::Binary(BinaryOp::And, Box::new(left), Box::new(right)));
+    if predicate.is_none() && headers.is_none() {
+        return Ok(file);
+    }
+    if predicate.is_some() {
+        print_info(&format!("Filtering {}", table.name));
+    }
+
+    let mut rows = NamedTempFile::new()?;
+    std::io::copy(&mut file, &mut rows)?;
+    let mut df = table.clone().with_dialect(config.csv_dialect.clone());
+    df.read_headers(rows.path())?;
+    if let Some(headers) = headers {
+        df.headers = headers.to_vec();
+    }
+    let predicate = predicate
+        .unwrap_or(Expr::Literal(Value::Bool(true)))
+        .bind(&df.headers)
+        .map_err(|e| format!("Cannot filter {}: {}", table.name, e))?;
+
+    let mut filtered = tempfile::tempfile()?;
+    {
+        let mut writer = BufWriter::new(&filtered);
+        df.filter_stream(&mut df.open_rows(rows.path())?, &mut writer, &predicate)?;
+        writer.flush()?;
+    }
+    filtered.seek(SeekFrom::Start(0))?;
+    Ok(filtered)
+}
+
+/// Parses `--where` conditions and assigns each to the step of `path` whose table
+/// qualifies its columns, as in `orders.status = 'paid'`. The conditions of a step
+/// refer to the table's columns by their own names.
+pub fn assign_conditions(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    conditions: &[String],
+) -> Result<Vec<Vec<Expr>>, Box<dyn Error>> {
+    let mut assigned = vec![Vec::new(); path.len()];
+    for condition in conditions {
+        let expression = expr::parse(condition)?;
+        let mut step = None;
+        for column in expression.columns() {
+            let (table, _) = column.rsplit_once('.').ok_or_else(|| {
+                format!(
+                    "Qualify column '{}' in '{}' with its table, e.g. 'orders.{}'",
+                    column, condition, column
+                )
+            })?;
+            let position =
+                graph::find_table(path.iter().map(|&n| &g[n]), table).ok_or_else(|| {
+                    format!(
+                        "Table '{}' in '{}' is not on the join path",
+                        table, condition
+                    )
+                })?;
+            if step.is_some_and(|step| step != position) {
+                return Err(format!(
+                    "'{}' refers to several tables; give each its own --where",
+                    condition
+                )
+                .into());
+            }
+            step = Some(position);
+        }
+        let step = step.ok_or_else(|| format!("'{}' refers to no table's columns", condition))?;
+        let unqualified = |column: &str| {
+            column
+                .rsplit_once('.')
+                .map_or(column, |(_, name)| name)
+                .to_string()
+        };
+        assigned[step].push(expression.rename_columns(&unqualified));
+    }
+    Ok(assigned)
+}
+
+/// Find suitable join columns between two DataFrames, as `(left, right)` pairs
+/// that must all match.
+pub fn find_join_columns(
+    left: &DataFrame,
+    right: &DataFrame,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    for (left_col, _, right_col) in &left.foreign_keys {
+        if right.headers.contains(right_col) {
+            return Ok(vec![(left_col.clone(), right_col.clone())]);
+        }
+    }
+    for (right_col, _, left_col) in &right.foreign_keys {
+        if left.headers.contains(left_col) {
+            return Ok(vec![(left_col.clone(), right_col.clone())]);
+        }
+    }
+    Err("No suitable join columns found".into())
+}
+
+/// Finds the key columns joining the table at `step` of `path` to `current`, the rows
+/// joined so far, from one foreign key with the first earlier table it has one with:
+/// every column of a composite foreign key, or the single column of another. Columns of
+/// tables after the first are named after their table where they collided, and all
+/// columns are with `--prefix-columns`.
+pub fn find_hop_keys(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    step: usize,
+    current: &DataFrame,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let next = path[step];
+    for (position, &earlier) in path[..step].iter().enumerate() {
+        let mut edges: Vec<EdgeIndex> = g.edges_connecting(earlier, next).map(|e| e.id()).collect();
+        edges.sort();
+        let Some(&first) = edges.first() else {
+            continue;
+        };
+        let (holder, referenced_table) = g.edge_endpoints(first).unwrap();
+        let held_columns: Vec<&String> = edges
+            .iter()
+            .filter(|&&edge| g.edge_endpoints(edge).unwrap().0 == holder)
+            .map(|&edge| &g[edge].0)
+            .collect();
+        let first_column = &g[first].0;
+        // The constraint the first edge belongs to, if it spans several columns that all
+        // reference the same table.
+        let constraint = g[holder].composite_foreign_keys.iter().find(|columns| {
+            columns.contains(first_column)
+                && columns.iter().all(|column| held_columns.contains(&column))
+        });
+        let mut keys = Vec::new();
+        for edge in edges {
+            let (src, dst) = g.edge_endpoints(edge).unwrap();
+            let (column, referenced) = &g[edge];
+            let in_constraint = match constraint {
+                Some(columns) => columns.contains(column),
+                None => edge == first,
+            };
+            let is_key = (src, dst) == (holder, referenced_table) && in_constraint;
+            if !is_key || keys.iter().any(|(_, _, other)| *other == column) {
+                continue;
+            }
+            let (left, right) = match src == earlier {
+                true => (column, referenced),
+                false => (referenced, column),
+            };
+            let prefixed = format!("{}.{}", g[earlier].name, left);
+            let suffixed = format!("{}_{}", left, g[earlier].name);
+            let left = match (
+                current.headers.contains(&prefixed),
+                position > 0 && current.headers.contains(&suffixed),
+            ) {
+                (true, _) => prefixed,
+                (_, true) => suffixed,
+                _ => left.clone(),
+            };
+            keys.push((left, right.clone(), column));
+        }
+        return Ok(keys
+            .into_iter()
+            .map(|(left, right, _)| (left, right))
+            .collect());
+    }
+    find_join_columns(current, &g[next])
+}
+
+/// Lists the columns a join of `left` and `right` must keep for `select` to be written
+/// once `later` tables are joined: the selected columns, the foreign keys of either side
+/// referencing a later table, the columns later tables reference, and those a selected
+/// column of a later table is suffixed for colliding with, named as in the join's
+/// output, where right columns sharing a name with a left one are suffixed.
+pub fn find_columns_to_keep(
+    left: &DataFrame,
+    right: &DataFrame,
+    later: &[&DataFrame],
+    select: &[String],
+) -> Vec<String> {
+    let is_table =
+        |table: &DataFrame, name: &str| graph::find_table(std::iter::once(table), name).is_some();
+    let right_name = |column: &String| match left.headers.contains(column) {
+        true => format!("{}_{}", column, right.name),
+        false => column.clone(),
+    };
+    let is_later = |name: &str| later.iter().any(|&table| is_table(table, name));
+
+    let mut columns: Vec<String> = select.to_vec();
+    for (column, dst, _) in &left.foreign_keys {
+        if is_later(dst) {
+            columns.push(column.clone());
+        }
+    }
+    for (column, dst, _) in &right.foreign_keys {
+        if is_later(dst) {
+            columns.push(right_name(column));
+        }
+    }
+    for table in later {
+        for (_, dst, referenced) in &table.foreign_keys {
+            let suffixed = format!("{}_{}", referenced, dst);
+            match is_table(right, dst) {
+                true => columns.push(right_name(referenced)),
+                false if left.headers.contains(&suffixed) => columns.push(suffixed),
+                false if !is_later(dst) => columns.push(referenced.clone()),
+                false => {}
+            }
+        }
+        // `note_visits` is only written if a `note` is still there when visits joins.
+        for column in &table.headers {
+            let selected = select.contains(&format!("{}_{}", column, table.name));
+            if selected && (left.headers.contains(column) || right.headers.contains(column)) {
+                columns.push(column.clone());
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    columns.retain(|column| seen.insert(column.clone()));
+    columns
+}
+
+/// Find the shortest path between two nodes that passes through each of `via` in
+/// order, made of the cheapest paths by `weight` between consecutive stops. A path that would
+/// visit a table twice is an error, since the table would be joined twice.
+pub fn find_path_via(
+    g: &UnGraph<DataFrame, (String, String)>,
+    start: NodeIndex,
+    via: &[NodeIndex],
+    end: NodeIndex,
+    weight: PathWeight,
+) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
+    let stops: Vec<NodeIndex> = std::iter::once(start)
+        .chain(via.iter().copied())
+        .chain(std::iter::once(end))
+        .collect();
+    let mut path = vec![start];
+    for leg in stops.windows(2) {
+        path.extend(
+            find_weighted_shortest_path(g, leg[0], leg[1], weight)?
+                .into_iter()
+                .skip(1),
+        );
+    }
+    let mut seen = HashSet::new();
+    if let Some(&repeated) = path.iter().find(|&&node| !seen.insert(node)) {
+        let names: Vec<&str> = path.iter().map(|&n| g[n].name.as_str()).collect();
+        return Err(format!(
+            "Path {} visits '{}' more than once",
+            names.join(" -> "),
+            g[repeated].name
+        )
+        .into());
+    }
+    Ok(path)
+}
+
+/// Find the nodes of tables given in join order, checking that each table has a
+/// foreign key to or from the one before it and that none repeats.
+pub fn find_explicit_path(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[String],
+) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
+    if tables.len() < 2 {
+        return Err("A join path needs at least two tables".into());
+    }
+    let path = find_nodes(g, tables)?;
+    for hop in path.windows(2) {
+        if g.find_edge(hop[0], hop[1]).is_none() {
+            return Err(format!(
+                "No foreign key joins '{}' and '{}'",
+                g[hop[0]].name, g[hop[1]].name
+            )
+            .into());
+        }
+    }
+    let mut seen = HashSet::new();
+    if let Some(&repeated) = path.iter().find(|&&node| !seen.insert(node)) {
+        return Err(format!("Path visits '{}' more than once", g[repeated].name).into());
+    }
+    Ok(path)
+}
+
+/// Find the nodes of several tables by name.
+fn find_nodes(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[String],
+) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
+    tables.iter().map(|table| find_node(g, table)).collect()
+}
+
+/// Update the DataFrame after a join operation.
+pub fn update_dataframe_after_join(
+    left_df: &DataFrame,
+    right_df: &DataFrame,
+    keys: &[(String, String)],
+) -> DataFrame {
+    let mut new_df = left_df.clone();
+    let is_right_key = |column: &String| keys.iter().any(|(_, right)| right == column);
+
+    new_df.headers.extend(
+        right_df
+            .headers
+            .iter()
+            .filter(|&h| !is_right_key(h))
+            .cloned(),
+    );
+
+    new_df.header_indices = new_df
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| (h.clone(), i))
+        .collect();
+
+    // Types stay aligned with the headers only while both sides carry one per column.
+    if new_df.column_types.len() == left_df.headers.len()
+        && right_df.column_types.len() == right_df.headers.len()
+    {
+        new_df.column_types.extend(
+            right_df
+                .headers
+                .iter()
+                .zip(&right_df.column_types)
+                .filter(|&(h, _)| !is_right_key(h))
+                .map(|(_, t)| *t),
+        );
+    } else {
+        new_df.column_types.clear();
+    }
+
+    new_df.foreign_keys.extend(
+        right_df
+            .foreign_keys
+            .iter()
+            .filter(|&(col, _, _)| !is_right_key(col))
+            .cloned(),
+    );
+
+    if let Some((left_col, _)) = keys
+        .iter()
+        .find(|(_, right)| right_df.primary_key.as_ref() == Some(right))
+    {
+        new_df.primary_key = Some(left_col.clone());
+    }
+
+    new_df
+}
+
+/// Handle the shortest path operation between two nodes in the graph.
+fn handle_graph_shortest_path(
+    from: &str,
+    to: &str,
+    via: &[String],
+    weight: PathWeight,
+    render: Option<&DisplayType>,
+    config: &Config,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let from_index = find_node(g, from)?;
+    let to_index = find_node(g, to)?;
+    let via = find_nodes(g, via)?;
+
+    let path = find_path_via(g, from_index, &via, to_index, weight)?;
+    let path_str: Vec<String> = path.iter().map(|&n| g[n].name.clone()).collect();
+    println!("Shortest path: {}", path_str.join(" -> "));
+
+    if let Some(format) = render {
+        render_path(g, &path, config, get_type(format))?;
+    }
+    Ok(())
+}
+
+/// Renders the whole graph with `path` highlighted as `path.<format>` in the output
+/// folder and opens it.
+fn render_path(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    config: &Config,
+    format: &str,
+) -> Result<(), Box<dyn Error>> {
+    if format == "ascii" {
+        print!("{}", graph::write_ascii(g, path));
+        return Ok(());
+    }
+    if !graphviz_installed() {
+        return render_layered_svg(g, path, config, "path");
+    }
+    let dot_content = graph::write_highlighted_dot_file(g, &config.graphviz_settings, path);
+    render_dot_content(&dot_content, config, "path", format)
+}
+
+/// Groups the tables into connected components: sets of tables that can all be
+/// joined to each other through foreign keys. Table names are sorted within each
+/// group, and the largest groups come first.
+pub fn find_components(g: &UnGraph<DataFrame, (String, String)>) -> Vec<Vec<String>> {
+    let mut sets = UnionFind::new(g.node_count());
+    for edge in g.edge_indices() {
+        let (a, b) = g.edge_endpoints(edge).unwrap();
+        sets.union(a.index(), b.index());
+    }
+
+    let mut groups: HashMap<usize, Vec<String>> = HashMap::new();
+    for node in g.node_indices() {
+        groups
+            .entry(sets.find(node.index()))
+            .or_default()
+            .push(g[node].name.clone());
+    }
+    let mut components: Vec<Vec<String>> = groups.into_values().collect();
+    for component in &mut components {
+        component.sort();
+    }
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    components
+}
+
+/// Handle listing the connected components of the graph.
+fn handle_graph_components(g: &UnGraph<DataFrame, (String, String)>) -> Result<(), Box<dyn Error>> {
+    let components = find_components(g);
+    for (i, component) in components.iter().enumerate() {
+        println!(
+            "Component {} ({} {}): {}",
+            i + 1,
+            component.len(),
+            if component.len() == 1 {
+                "table"
+            } else {
+                "tables"
+            },
+            component.join(", ")
+        );
+    }
+    if components.len() > 1 {
+        print_info("Tables in different components cannot be joined to each other.");
+    }
+    Ok(())
+}
+
+/// Finds circular foreign key chains, following each key from the table that holds it
+/// to the table it references. Returns one shortest cycle for every group of tables
+/// that reference each other in a circle, including a table that references itself,
+/// starting from the group's first table by name.
+pub fn find_fk_cycles(g: &UnGraph<DataFrame, (String, String)>) -> Vec<Vec<String>> {
+    let mut references = DiGraph::<(), ()>::with_capacity(g.node_count(), 0);
+    for _ in g.node_indices() {
+        references.add_node(());
+    }
+    for src in g.node_indices() {
+        for (_, dst_table, _) in &g[src].foreign_keys {
+            if let Ok(dst) = find_node(g, dst_table) {
+                references.update_edge(src, dst, ());
+            }
+        }
+    }
+
+    let mut cycles: Vec<Vec<String>> = tarjan_scc(&references)
+        .into_iter()
+        .filter(|group| group.len() > 1 || references.contains_edge(group[0], group[0]))
+        .filter_map(|group| {
+            let start = *group.iter().min_by_key(|&&n| &g[n].name)?;
+            shortest_cycle(&references, start, &group)
+                .map(|cycle| cycle.iter().map(|&n| g[n].name.clone()).collect())
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// Finds the shortest cycle from `start` back to itself that stays within `group`.
+fn shortest_cycle(
+    references: &DiGraph<(), ()>,
+    start: NodeIndex,
+    group: &[NodeIndex],
+) -> Option<Vec<NodeIndex>> {
+    let mut previous: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for next in references.neighbors(node) {
+            if next == start {
+                let mut cycle = vec![node];
+                while let Some(&before) = previous.get(cycle.last()?) {
+                    cycle.push(before);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if group.contains(&next) && !previous.contains_key(&next) {
+                previous.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Handle reporting circular foreign key chains.
+fn handle_graph_cycles(g: &UnGraph<DataFrame, (String, String)>) -> Result<(), Box<dyn Error>> {
+    let cycles = find_fk_cycles(g);
+    if cycles.is_empty() {
+        println!("No circular foreign key chains found");
+        return Ok(());
+    }
+    for cycle in &cycles {
+        println!("{} -> {}", cycle.join(" -> "), cycle[0]);
+    }
+    Ok(())
+}
+
+/// Lists up to `max_paths` paths between two nodes that visit no table twice, shortest
+/// first, considering only paths of at most `max_len` joins when given.
+pub fn find_paths(
+    g: &UnGraph<DataFrame, (String, String)>,
+    start: NodeIndex,
+    end: NodeIndex,
+    max_paths: usize,
+    max_len: Option<usize>,
+) -> Vec<Vec<NodeIndex>> {
+    let longest = max_len
+        .unwrap_or(usize::MAX)
+        .min(g.node_count().saturating_sub(1));
+    let mut paths: Vec<Vec<NodeIndex>> = Vec::new();
+    // Search one length at a time so that short paths are found without first
+    // walking every long one.
+    for joins in 1..=longest {
+        let mut found: Vec<Vec<NodeIndex>> =
+            all_simple_paths(g, start, end, joins - 1, Some(joins - 1)).collect();
+        found.sort_by_key(|path| path.iter().map(|&n| g[n].name.clone()).collect::<Vec<_>>());
+        found.dedup();
+        for path in found {
+            if paths.len() == max_paths {
+                return paths;
+            }
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Handle listing alternative paths between two tables.
+fn handle_graph_paths(
+    from: &str,
+    to: &str,
+    max_paths: usize,
+    max_len: Option<usize>,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let from_index = find_node(g, from)?;
+    let to_index = find_node(g, to)?;
+
+    let paths = find_paths(g, from_index, to_index, max_paths, max_len);
+    if paths.is_empty() {
+        let within = max_len.map_or(String::new(), |n| format!(" within {} joins", n));
+        return Err(format!("No path found between '{}' and '{}'{}", from, to, within).into());
+    }
+    for (i, path) in paths.iter().enumerate() {
+        let names: Vec<&str> = path.iter().map(|&n| g[n].name.as_str()).collect();
+        println!(
+            "{}. {} ({} {})",
+            i + 1,
+            names.join(" -> "),
+            path.len() - 1,
+            if path.len() == 2 { "join" } else { "joins" }
+        );
+    }
+    Ok(())
+}
+
+/// Finds the join tree connecting tables given by name, as their nodes and its foreign keys.
+fn find_named_join_tree(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[String],
+) -> Result<(Vec<NodeIndex>, Vec<EdgeIndex>), Box<dyn Error>> {
+    let nodes = find_nodes(g, tables)?;
+    if nodes.len() > MAX_JOIN_TREE_TABLES {
+        return Err(format!(
+            "A join tree connects at most {} tables, got {}",
+            MAX_JOIN_TREE_TABLES,
+            nodes.len()
+        )
+        .into());
+    }
+    let edges = find_join_tree(g, &nodes)
+        .ok_or_else(|| format!("No join tree connects {}", tables.join(", ")))?;
+    Ok((nodes, edges))
+}
+
+/// Orders every table of the join tree connecting `tables` for joining one at a time:
+/// breadth first from the first table, so that each has a foreign key to or from a
+/// table joined before it.
+pub fn find_join_order(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[String],
+) -> Result<Vec<NodeIndex>, Box<dyn Error>> {
+    let (nodes, edges) = find_named_join_tree(g, tables)?;
+    let mut order = vec![nodes[0]];
+    let mut queue = VecDeque::from([nodes[0]]);
+    while let Some(node) = queue.pop_front() {
+        for &edge in &edges {
+            let (src, dst) = g.edge_endpoints(edge).unwrap();
+            let next = match (src == node, dst == node) {
+                (true, _) => dst,
+                (_, true) => src,
+                _ => continue,
+            };
+            if !order.contains(&next) {
+                order.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Most tables `graph join-tree` connects, bounding its exponential search.
+const MAX_JOIN_TREE_TABLES: usize = 12;
+
+/// How the cheapest tree for a set of tables and an end table was built.
+#[derive(Clone, Copy)]
+enum TreeStep {
+    /// The shortest path from the only table of the set to the end table.
+    Path(usize),
+    /// Two trees for complementary subsets, both ending at the end table.
+    Merge(usize),
+    /// The tree ending at another table, extended by the shortest path from it.
+    Extend(NodeIndex),
+}
+
+/// Finds the smallest set of foreign keys connecting all of `tables`: a minimum Steiner
+/// tree, computed exactly with the Dreyfus-Wagner algorithm, whose cost grows with
+/// 3^k for k tables. Returns the foreign keys sorted by edge index, or `None` if the
+/// tables are not all connected.
+pub fn find_join_tree(
+    g: &UnGraph<DataFrame, (String, String)>,
+    tables: &[NodeIndex],
+) -> Option<Vec<EdgeIndex>> {
+    let mut terminals = tables.to_vec();
+    terminals.sort();
+    terminals.dedup();
+    let n = g.node_count();
+    let k = terminals.len();
+    if k <= 1 {
+        return Some(Vec::new());
+    }
+
+    // Hops and the previous table on a shortest path from every table to every other.
+    let mut dist = vec![vec![usize::MAX; n]; n];
+    let mut prev = vec![vec![None; n]; n];
+    for source in g.node_indices() {
+        let (dist, prev) = (&mut dist[source.index()], &mut prev[source.index()]);
+        dist[source.index()] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for next in g.neighbors(node) {
+                if dist[next.index()] == usize::MAX {
+                    dist[next.index()] = dist[node.index()] + 1;
+                    prev[next.index()] = Some(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    // cost[mask][v]: joins of the cheapest tree connecting the tables in `mask` and v.
+    let full = (1 << k) - 1;
+    let mut cost = vec![vec![usize::MAX; n]; full + 1];
+    let mut steps = vec![vec![TreeStep::Path(0); n]; full + 1];
+    for (i, terminal) in terminals.iter().enumerate() {
+        for v in 0..n {
+            cost[1 << i][v] = dist[terminal.index()][v];
+            steps[1 << i][v] = TreeStep::Path(i);
+        }
+    }
+    for mask in 1..=full {
+        if mask & (mask - 1) == 0 {
+            continue;
+        }
+        for v in 0..n {
+            // Subsets holding the lowest table, so each split is tried once.
+            let low = mask & mask.wrapping_neg();
+            let mut sub = (mask - 1) & mask;
+            while sub > 0 {
+                if sub & low != 0 {
+                    let total = cost[sub][v].saturating_add(cost[mask ^ sub][v]);
+                    if total < cost[mask][v] {
+                        cost[mask][v] = total;
+                        steps[mask][v] = TreeStep::Merge(sub);
+                    }
+                }
+                sub = (sub - 1) & mask;
+            }
+        }
+        for v in 0..n {
+            for u in 0..n {
+                let total = cost[mask][u].saturating_add(dist[u][v]);
+                if total < cost[mask][v] {
+                    cost[mask][v] = total;
+                    steps[mask][v] = TreeStep::Extend(NodeIndex::new(u));
+                }
+            }
+        }
+    }
+    let root = terminals[0];
+    if cost[full][root.index()] == usize::MAX {
+        return None;
+    }
+
+    // Walks the recorded steps back, collecting the hops of every shortest path used.
+    let mut hops: HashSet<(NodeIndex, NodeIndex)> = HashSet::new();
+    let mut add_path = |from: NodeIndex, to: NodeIndex| {
+        let mut node = to;
+        while let Some(previous) = prev[from.index()][node.index()] {
+            hops.insert((previous.min(node), previous.max(node)));
+            node = previous;
+        }
+    };
+    let mut pending = vec![(full, root)];
+    while let Some((mask, v)) = pending.pop() {
+        match steps[mask][v.index()] {
+            TreeStep::Path(i) => add_path(terminals[i], v),
+            TreeStep::Merge(sub) => pending.extend([(sub, v), (mask ^ sub, v)]),
+            TreeStep::Extend(u) => {
+                add_path(u, v);
+                pending.push((mask, u));
+            }
+        }
+    }
+    let mut edges: Vec<EdgeIndex> = hops
+        .into_iter()
+        .map(|(a, b)| g.find_edge(a, b).unwrap())
+        .collect();
+    edges.sort();
+    Some(edges)
+}
+
+/// Handle finding the smallest tree of foreign keys joining a set of tables.
+fn handle_graph_join_tree(
+    tables: &[String],
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let (nodes, edges) = find_named_join_tree(g, tables)?;
+    let mut names: Vec<&str> = nodes.iter().map(|&n| g[n].name.as_str()).collect();
+    for &edge in &edges {
+        let (src, dst) = g.edge_endpoints(edge).unwrap();
+        names.extend([g[src].name.as_str(), g[dst].name.as_str()]);
+    }
+    names.sort();
+    names.dedup();
+    println!(
+        "Join tree: {} ({} {})",
+        names.join(", "),
+        edges.len(),
+        if edges.len() == 1 { "join" } else { "joins" }
+    );
+    for edge in edges {
+        let (src, dst) = g.edge_endpoints(edge).unwrap();
+        let (column, dst_column) = &g[edge];
+        println!(
+            "{}.{} -> {}.{}",
+            g[src].name, column, g[dst].name, dst_column
+        );
+    }
+    Ok(())
+}
+
+/// Handle comparing two versions of a schema.
+fn handle_graph_diff(
+    old: &Path,
+    new: &Path,
+    render: Option<&DisplayType>,
+    config: &Config,
+) -> Result<(), Box<dyn Error>> {
+    let read = |path: &Path| -> Result<Vec<DataFrame>, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read schema file {}: {}", path.display(), e))?;
+        sql::parse_sql(&content)
+    };
+    let old_tables = read(old)?;
+    let new_tables = read(new)?;
+    let diff = graph::diff_schemas(&old_tables, &new_tables);
+
+    if diff.is_empty() {
+        println!("No differences found");
+    }
+    for table in &diff.added_tables {
+        println!("+ table {}", table);
+    }
+    for table in &diff.removed_tables {
+        println!("- table {}", table);
+    }
+    for (table, column) in &diff.added_columns {
+        println!("+ column {}.{}", table, column);
+    }
+    for (table, column) in &diff.removed_columns {
+        println!("- column {}.{}", table, column);
+    }
+    for (sign, keys) in [
+        ("+", &diff.added_foreign_keys),
+        ("-", &diff.removed_foreign_keys),
+    ] {
+        for (table, column, dst, dst_column) in keys {
+            println!(
+                "{} foreign key {}.{} -> {}.{}",
+                sign, table, column, dst, dst_column
+            );
+        }
+    }
+
+    // The changes listed above already are the textual view of the diff.
+    if let Some(format) = render.filter(|format| !matches!(format, DisplayType::Ascii)) {
+        let dot_content =
+            graph::write_diff_dot_file(&old_tables, &new_tables, &diff, &config.graphviz_settings);
+        render_dot_content(&dot_content, config, "diff", get_type(format))?;
+    }
+    Ok(())
+}
+
+/// Handle listing the tables of the graph, sorted by name, with their number of
+/// columns, primary key, foreign key edges and, once counted, rows.
+fn handle_graph_tables(g: &UnGraph<DataFrame, (String, String)>) -> Result<(), Box<dyn Error>> {
+    let mut nodes: Vec<NodeIndex> = g.node_indices().collect();
+    nodes.sort_by(|&a, &b| g[a].name.cmp(&g[b].name));
+
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_BOX_CHARS);
+    table.set_titles(row![
+        "table",
+        "columns",
+        "primary key",
+        "foreign keys",
+        "rows"
+    ]);
+    for node in nodes {
+        let df = &g[node];
+        table.add_row(row![
+            df.name,
+            df.headers.len(),
+            df.primary_key.as_deref().unwrap_or(""),
+            g.edges(node).count(),
+            df.row_count
+                .map(|rows| rows.to_string())
+                .unwrap_or_default()
+        ]);
+    }
+    table.printstd();
+    print_info(&format!(
+        "{} tables, {} foreign keys",
+        g.node_count(),
+        g.edge_count()
+    ));
+    Ok(())
+}
+
+/// Lists the foreign keys of other tables that reference a node as `(table, column,
+/// referenced column)`, sorted by table. A table referencing itself is included.
+pub fn find_references(
+    g: &UnGraph<DataFrame, (String, String)>,
+    node: NodeIndex,
+) -> Vec<(String, String, String)> {
+    let mut references: Vec<(String, String, String)> = g
+        .node_weights()
+        .flat_map(|table| {
+            table
+                .foreign_keys
+                .iter()
+                .filter(|(_, dst_table, _)| {
+                    graph::find_table(g.node_weights(), dst_table) == Some(node.index())
+                })
+                .map(|(column, _, dst_column)| {
+                    (table.name.clone(), column.clone(), dst_column.clone())
+                })
+        })
+        .collect();
+    references.sort();
+    references
+}
+
+/// Handle describing a single table.
+fn handle_graph_info(
+    table: &str,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let node = find_node(g, table)?;
+    let df = &g[node];
+
+    println!("Table: {}", df.name);
+    println!(
+        "Primary key: {}",
+        df.primary_key.as_deref().unwrap_or("none")
+    );
+    if let Some(rows) = df.row_count {
+        println!("Rows: {}", rows);
+    }
+
+    let mut columns = Table::new();
+    columns.set_format(*format::consts::FORMAT_BOX_CHARS);
+    columns.set_titles(row!["column", "type", "key"]);
+    for (i, header) in df.headers.iter().enumerate() {
+        let is_header = |key: &str| key.eq_ignore_ascii_case(header);
+        let mut keys = Vec::new();
+        if df.primary_key.as_deref().is_some_and(is_header) {
+            keys.push("PK".to_string());
+        }
+        for (_, dst_table, dst_column) in df.foreign_keys.iter().filter(|(c, _, _)| is_header(c)) {
+            keys.push(format!("FK -> {}.{}", dst_table, dst_column));
+        }
+        columns.add_row(row![
+            header,
+            df.sql_types.get(i).map(String::as_str).unwrap_or(""),
+            keys.join(", ")
+        ]);
+    }
+    columns.printstd();
+
+    println!("References:");
+    if df.foreign_keys.is_empty() {
+        println!("  none");
+    }
+    for (column, dst_table, dst_column) in &df.foreign_keys {
+        let missing = match graph::find_table(g.node_weights(), dst_table) {
+            Some(_) => "",
+            None => " (table not in graph)",
+        };
+        println!(
+            "  {}.{} -> {}.{}{}",
+            df.name, column, dst_table, dst_column, missing
+        );
+    }
+
+    let references = find_references(g, node);
+    println!("Referenced by:");
+    if references.is_empty() {
+        println!("  none");
+    }
+    for (src_table, column, dst_column) in &references {
+        println!("  {}.{} -> {}.{}", src_table, column, df.name, dst_column);
+    }
+    Ok(())
+}
+
+/// Handle listing the foreign keys that reference a table.
+fn handle_graph_referenced_by(
+    table: &str,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let node = find_node(g, table)?;
+    let references = find_references(g, node);
+    if references.is_empty() {
+        println!("No tables reference '{}'", g[node].name);
+        return Ok(());
+    }
+    for (src_table, column, dst_column) in &references {
+        println!(
+            "{}.{} -> {}.{}",
+            src_table, column, g[node].name, dst_column
+        );
+    }
+    let tables: HashSet<&str> = references.iter().map(|(t, _, _)| t.as_str()).collect();
+    print_info(&format!(
+        "{} {} in {} {}",
+        references.len(),
+        if references.len() == 1 {
+            "reference"
+        } else {
+            "references"
+        },
+        tables.len(),
+        if tables.len() == 1 { "table" } else { "tables" }
+    ));
+    Ok(())
+}
+
+/// Lists the tables with no foreign key to or from another table, sorted by name. A
+/// table that only references itself is an orphan too, since it joins no other table.
+pub fn find_orphans(g: &UnGraph<DataFrame, (String, String)>) -> Vec<String> {
+    let mut orphans: Vec<String> = g
+        .node_indices()
+        .filter(|&node| g.neighbors(node).all(|n| n == node))
+        .map(|node| g[node].name.clone())
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+/// Handle listing the tables with no foreign key relationships.
+fn handle_graph_orphans(g: &UnGraph<DataFrame, (String, String)>) -> Result<(), Box<dyn Error>> {
+    let orphans = find_orphans(g);
+    if orphans.is_empty() {
+        println!("No orphan tables found");
+        return Ok(());
+    }
+    for orphan in &orphans {
+        let node = find_node(g, orphan)?;
+        // Keys to tables missing from the schema leave no edge behind.
+        let missing: Vec<&str> = g[node]
+            .foreign_keys
+            .iter()
+            .map(|(_, dst_table, _)| dst_table.as_str())
+            .filter(|&dst_table| graph::find_table(g.node_weights(), dst_table).is_none())
+            .collect();
+        match missing.is_empty() {
+            true => println!("{}", orphan),
+            false => println!("{} (references missing {})", orphan, missing.join(", ")),
+        }
+    }
+    print_info("Orphan tables cannot be joined to any other table.");
+    Ok(())
+}
+
+/// Handle listing the chains of tables that match a pattern, with the columns joining
+/// each pair of tables.
+fn handle_graph_query(
+    pattern: &str,
+    max: usize,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let steps = graph::parse_pattern(pattern)?;
+    let chains = graph::match_pattern(g, &steps);
+    if chains.is_empty() {
+        println!("No tables match '{}'", pattern);
+        return Ok(());
+    }
+    for chain in chains.iter().take(max) {
+        let names: Vec<&str> = chain.iter().map(|&n| g[n].name.as_str()).collect();
+        let joins: Vec<String> = chain
+            .windows(2)
+            .filter_map(|pair| {
+                let (_, column, neighbor_column) = find_neighbors(g, pair[0])
+                    .into_iter()
+                    .find(|(neighbor, _, _)| *neighbor == g[pair[1]].name)?;
+                Some(format!(
+                    "{}.{} = {}.{}",
+                    g[pair[0]].name, column, g[pair[1]].name, neighbor_column
+                ))
+            })
+            .collect();
+        println!("{} ({})", names.join(" -> "), joins.join(", "));
+    }
+    if chains.len() > max {
+        print_info(&format!(
+            "{} more chains not shown, see --max",
+            chains.len() - max
+        ));
+    }
+    Ok(())
+}
+
+/// Lists the foreign keys linking a node to its neighbors as `(neighbor, column,
+/// neighbor column)`, where `column` belongs to the node's table, sorted by neighbor.
+pub fn find_neighbors(
+    g: &UnGraph<DataFrame, (String, String)>,
+    node: NodeIndex,
+) -> Vec<(String, String, String)> {
+    let mut neighbors: Vec<(String, String, String)> = g
+        .edges(node)
+        .map(|edge| {
+            let (src_column, dst_column) = edge.weight().clone();
+            let (src, dst) = g.edge_endpoints(edge.id()).unwrap();
+            match src == node {
+                true => (g[dst].name.clone(), src_column, dst_column),
+                false => (g[src].name.clone(), dst_column, src_column),
+            }
+        })
+        .collect();
+    neighbors.sort();
+    neighbors
+}
+
+/// Handle listing the direct neighbors of a table.
+fn handle_graph_neighbors(
+    table: &str,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let node = find_node(g, table)?;
+    let neighbors = find_neighbors(g, node);
+    if neighbors.is_empty() {
+        println!("'{}' has no foreign key relationships", table);
+        return Ok(());
+    }
+    for (neighbor, column, neighbor_column) in &neighbors {
+        println!(
+            "{}: {}.{} = {}.{}",
+            neighbor, table, column, neighbor, neighbor_column
+        );
+    }
+    Ok(())
+}
+
+/// Ranks the tables as `(table, degree, betweenness)`, where degree counts the foreign
+/// keys linking a table to others and betweenness sums, over every pair of other tables,
+/// the share of shortest paths between them that pass through it. Tables on the most
+/// paths come first, then those with the most keys, then by name.
+pub fn find_hubs(g: &UnGraph<DataFrame, (String, String)>) -> Vec<(String, usize, f64)> {
+    let neighbors: Vec<Vec<NodeIndex>> = g
+        .node_indices()
+        .map(|node| {
+            let mut adjacent: Vec<NodeIndex> = g.neighbors(node).filter(|&n| n != node).collect();
+            adjacent.sort();
+            adjacent.dedup();
+            adjacent
+        })
+        .collect();
+
+    // Brandes' algorithm: a breadth-first search from every table counts the shortest
+    // paths to each other table, then walks back from the farthest to credit the tables
+    // those paths went through.
+    let mut betweenness = vec![0.0; g.node_count()];
+    for source in g.node_indices() {
+        let mut order = Vec::new();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); g.node_count()];
+        let mut paths = vec![0.0; g.node_count()];
+        let mut distance: Vec<Option<usize>> = vec![None; g.node_count()];
+        paths[source.index()] = 1.0;
+        distance[source.index()] = Some(0);
+        let mut queue = VecDeque::from([source.index()]);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            let next_distance = distance[node].map(|d| d + 1);
+            for &next in &neighbors[node] {
+                let next = next.index();
+                if distance[next].is_none() {
+                    distance[next] = next_distance;
+                    queue.push_back(next);
+                }
+                if distance[next] == next_distance {
+                    paths[next] += paths[node];
+                    predecessors[next].push(node);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; g.node_count()];
+        for &node in order.iter().rev() {
+            for &before in &predecessors[node] {
+                dependency[before] += paths[before] / paths[node] * (1.0 + dependency[node]);
+            }
+            if node != source.index() {
+                betweenness[node] += dependency[node];
+            }
+        }
+    }
+
+    let mut hubs: Vec<(String, usize, f64)> = g
+        .node_indices()
+        .map(|node| {
+            (
+                g[node].name.clone(),
+                g.edges(node).count(),
+                // Each path is counted once from either end.
+                betweenness[node.index()] / 2.0,
+            )
+        })
+        .collect();
+    hubs.sort_by(|a, b| {
+        b.2.total_cmp(&a.2)
+            .then_with(|| b.1.cmp(&a.1))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    hubs
+}
+
+/// Handle ranking the tables by degree and betweenness centrality.
+fn handle_graph_hubs(
+    top: usize,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    for (table, degree, betweenness) in find_hubs(g).iter().take(top) {
+        println!(
+            "{}: {} {}, betweenness {:.2}",
+            table,
+            degree,
+            if *degree == 1 { "key" } else { "keys" },
+            betweenness
+        );
+    }
+    Ok(())
+}
+
+/// Structural metrics of a schema, written by `graph report`.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SchemaReport {
+    pub tables: usize,
+    pub foreign_keys: usize,
+    /// Mean number of joins between two tables that can be joined, if any can.
+    pub average_path_length: Option<f64>,
+    /// Tables referenced by the most foreign keys.
+    pub most_referenced: Vec<RankedTable>,
+    /// Longest chains of tables each referencing the next, longest first.
+    pub longest_chains: Vec<Vec<String>>,
+    /// Tables with the most columns.
+    pub widest_tables: Vec<RankedTable>,
+}
+
+/// A table with the count it is ranked by in a [`SchemaReport`].
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RankedTable {
+    pub table: String,
+    pub count: usize,
+}
+
+/// Builds the report of a schema, listing up to `top` tables or chains per section.
+/// Ties are broken by table name.
+pub fn build_report(g: &UnGraph<DataFrame, (String, String)>, top: usize) -> SchemaReport {
+    let rank = |counts: Vec<(String, usize)>| -> Vec<RankedTable> {
+        let mut counts: Vec<(String, usize)> =
+            counts.into_iter().filter(|(_, count)| *count > 0).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+            .into_iter()
+            .take(top)
+            .map(|(table, count)| RankedTable { table, count })
+            .collect()
+    };
+
+    let mut lengths = Vec::new();
+    for start in g.node_indices() {
+        let mut dist = vec![usize::MAX; g.node_count()];
+        dist[start.index()] = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for next in g.neighbors(node) {
+                if dist[next.index()] == usize::MAX {
+                    dist[next.index()] = dist[node.index()] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        // Each pair of tables once.
+        lengths.extend(
+            dist.iter()
+                .skip(start.index() + 1)
+                .filter(|&&d| d != usize::MAX),
+        );
+    }
+
+    SchemaReport {
+        tables: g.node_count(),
+        foreign_keys: g.edge_count(),
+        average_path_length: match lengths.is_empty() {
+            true => None,
+            false => Some(lengths.iter().sum::<usize>() as f64 / lengths.len() as f64),
+        },
+        most_referenced: rank(
+            g.node_indices()
+                .map(|node| (g[node].name.clone(), find_references(g, node).len()))
+                .collect(),
+        ),
+        longest_chains: find_longest_chains(g)
+            .into_iter()
+            .take(top)
+            .map(|chain| chain.into_iter().map(|n| g[n].name.clone()).collect())
+            .collect(),
+        widest_tables: rank(
+            g.node_weights()
+                .map(|table| (table.name.clone(), table.headers.len()))
+                .collect(),
+        ),
+    }
+}
+
+/// Finds the longest chain of tables starting at each table, where each table holds a
+/// foreign key to the next, and lists those that are not the tail of another, longest
+/// first. Foreign keys closing a cycle are not followed.
+fn find_longest_chains(g: &UnGraph<DataFrame, (String, String)>) -> Vec<Vec<NodeIndex>> {
+    let mut referenced: Vec<Vec<NodeIndex>> = vec![Vec::new(); g.node_count()];
+    for edge in g.edge_indices() {
+        let (src, dst) = g.edge_endpoints(edge).unwrap();
+        if src != dst {
+            referenced[src.index()].push(dst);
+        }
+    }
+    fn longest(
+        g: &UnGraph<DataFrame, (String, String)>,
+        node: NodeIndex,
+        referenced: &[Vec<NodeIndex>],
+        visiting: &mut HashSet<NodeIndex>,
+        chains: &mut HashMap<NodeIndex, Vec<NodeIndex>>,
+    ) -> Vec<NodeIndex> {
+        if let Some(chain) = chains.get(&node) {
+            return chain.clone();
+        }
+        visiting.insert(node);
+        let mut best: Vec<NodeIndex> = Vec::new();
+        for &next in &referenced[node.index()] {
+            if visiting.contains(&next) {
+                continue;
+            }
+            let chain = longest(g, next, referenced, visiting, chains);
+            let names = |chain: &[NodeIndex]| -> Vec<String> {
+                chain.iter().map(|&n| g[n].name.clone()).collect()
+            };
+            if chain.len() > best.len()
+                || (chain.len() == best.len() && names(&chain) < names(&best))
+            {
+                best = chain;
+            }
+        }
+        visiting.remove(&node);
+        best.insert(0, node);
+        chains.insert(node, best.clone());
+        best
+    }
+
+    let mut chains = HashMap::new();
+    let mut all: Vec<Vec<NodeIndex>> = g
+        .node_indices()
+        .map(|node| longest(g, node, &referenced, &mut HashSet::new(), &mut chains))
+        .filter(|chain| chain.len() > 1)
+        .collect();
+    let tails: HashSet<Vec<NodeIndex>> = all.iter().map(|chain| chain[1..].to_vec()).collect();
+    all.retain(|chain| !tails.contains(chain));
+    all.sort_by(|a, b| {
+        let names = |chain: &[NodeIndex]| -> Vec<String> {
+            chain.iter().map(|&n| g[n].name.clone()).collect()
+        };
+        b.len().cmp(&a.len()).then_with(|| names(a).cmp(&names(b)))
+    });
+    all
+}
+
+/// Handle summarizing the structure of the schema as a Markdown or JSON report.
+fn handle_graph_report(
+    format: ReportFormat,
+    top: usize,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let report = build_report(g, top);
+    if format == ReportFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let average = match report.average_path_length {
+        Some(length) => format!(", average path length {:.2} joins", length),
+        None => String::new(),
+    };
+    println!("# Schema report\n");
+    println!(
+        "{} tables, {} foreign keys{}.",
+        report.tables, report.foreign_keys, average
+    );
+    let print_ranked = |title: &str, column: &str, tables: &[RankedTable]| {
+        println!("\n## {}\n", title);
+        if tables.is_empty() {
+            println!("None.");
+        } else {
+            println!("| Table | {} |\n| --- | --- |", column);
+            for RankedTable { table, count } in tables {
+                println!("| {} | {} |", table, count);
+            }
+        }
+    };
+    print_ranked(
+        "Most referenced tables",
+        "References",
+        &report.most_referenced,
+    );
+    println!("\n## Longest dependency chains\n");
+    if report.longest_chains.is_empty() {
+        println!("None.");
+    }
+    for (i, chain) in report.longest_chains.iter().enumerate() {
+        println!(
+            "{}. {} ({} {})",
+            i + 1,
+            chain.join(" -> "),
+            chain.len() - 1,
+            if chain.len() == 2 { "join" } else { "joins" }
+        );
+    }
+    print_ranked("Widest tables", "Columns", &report.widest_tables);
+    Ok(())
+}
+
+/// Handle counting the rows of every table with a file in the source path, caching the
+/// counts in the graph for display and join planning.
+fn handle_graph_count_rows(
+    config: &Config,
+    config_dir: &Path,
+    mut g: UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    for node in g.node_indices() {
+        let table = &g[node];
+        match open_table(config, table) {
+            Ok(file) => {
+                let count = count_table_rows(config, file)?;
+                println!("{}: {} rows", table.name, count);
+                g[node].row_count = Some(count);
+            }
+            Err(_) => {
+                print_info(&format!("No file found for '{}', skipping", table.name));
+                g[node].row_count = None;
+            }
+        }
+    }
+    config::write_graph_cache(&g, config_dir)?;
+    print_info("Row counts cached in the graph.");
+    Ok(())
+}
+
+/// Counts the rows of a table's file, not counting the header.
+fn count_table_rows(config: &Config, file: File) -> Result<u64, Box<dyn Error>> {
+    let mut reader = config
+        .csv_dialect
+        .reader_builder()
+        .flexible(true)
+        .from_reader(BufReader::new(file));
+    let mut record = ByteRecord::new();
+    let mut count = 0;
+    while reader.read_byte_record(&mut record)? {
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Handle writing the graph to stdout.
+fn handle_graph_export(
+    format: ExportFormat,
+    config: &Config,
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&graph::GraphExport::from(g))? + "\n",
+        ExportFormat::Dot => graph::write_dot_file(g, &config.graphviz_settings),
+    };
+    std::io::stdout().write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// Handle the Minimum Spanning Tree operation.
 fn handle_graph_mst(
     g: &UnGraph<DataFrame, (String, String)>,
     config: &Config,
+    format: MstFormat,
 ) -> Result<(), Box<dyn Error>> {
-    let mst = petgraph::algo::min_spanning_tree(g);
-    let mst: UnGraph<DataFrame, (String, String)> = petgraph::Graph::from_elements(mst);
-    handle_graph_display(&mst, &config, "mst", "png")
+    let mst = graph::minimum_spanning_tree(g);
+    match format {
+        MstFormat::Png => handle_graph_display(&mst, config, "mst", "png"),
+        MstFormat::Pdf => handle_graph_display(&mst, config, "mst", "pdf"),
+        MstFormat::Svg => handle_graph_display(&mst, config, "mst", "svg"),
+        MstFormat::Dot => handle_graph_export(ExportFormat::Dot, config, &mst),
+        MstFormat::Json => handle_graph_export(ExportFormat::Json, config, &mst),
+        MstFormat::Text => {
+            print!("{}", graph::write_edge_list(&mst));
+            Ok(())
+        }
+    }
 }
+
 /// Handle the display of the graph.
 fn handle_graph_display(
     g: &UnGraph<DataFrame, (String, String)>,
@@ -309,20 +2906,88 @@ fn handle_graph_display(
     output: &str,
     format: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let dot_content = graph::write_dot_file(g);
+    if format == "ascii" {
+        print!("{}", graph::write_ascii(g, &[]));
+        return Ok(());
+    }
+    if !graphviz_installed() {
+        return render_layered_svg(g, &[], config, output);
+    }
+    let dot_content = graph::write_dot_file(g, &config.graphviz_settings);
+    render_dot_content(&dot_content, config, output, format)
+}
+
+/// Whether the Graphviz `dot` binary can be run.
+fn graphviz_installed() -> bool {
+    Command::new("dot")
+        .arg("-V")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// Draws the graph with the built-in layout as `<output>.svg` in the output folder, with
+/// `path` highlighted, and opens it. Used in place of Graphviz when it is not installed.
+fn render_layered_svg(
+    g: &UnGraph<DataFrame, (String, String)>,
+    path: &[NodeIndex],
+    config: &Config,
+    output: &str,
+) -> Result<(), Box<dyn Error>> {
+    print_info("Graphviz `dot` was not found; drawing an SVG with the built-in layout.");
+    let output_dir = Path::new(&config.output_path);
+    std::fs::create_dir_all(output_dir)?;
+
+    let svg_file = output_dir.join(format!("{}.svg", output));
+    std::fs::write(
+        &svg_file,
+        graph::write_layered_svg(g, &config.graphviz_settings, path),
+    )?;
+    print_info(&format!("SVG file saved to {}", svg_file.display()));
+    open_rendered(&svg_file, config)?;
+
+    Ok(())
+}
+
+/// Saves DOT content as `<output>.dot` in the output folder, renders it as
+/// `<output>.<format>` and opens the result.
+fn render_dot_content(
+    dot_content: &str,
+    config: &Config,
+    output: &str,
+    format: &str,
+) -> Result<(), Box<dyn Error>> {
     let output_dir = Path::new(&config.output_path);
     std::fs::create_dir_all(output_dir)?;
 
     let dot_file = output_dir.join(format!("{}.dot", output));
     let png_file = output_dir.join(format!("{}.{}", output, format));
 
-    save_dot_file(&dot_file, &dot_content)?;
-    run_dot_command(&dot_file, &png_file, format)?;
-    graph::open_dot_file(&png_file)?;
+    save_dot_file(&dot_file, dot_content)?;
+    run_dot_command(
+        &dot_file,
+        &png_file,
+        format,
+        &config.graphviz_settings.engine,
+    )?;
+    open_rendered(&png_file, config)?;
 
     Ok(())
 }
 
+/// Opens a rendered diagram in the default viewer, or prints its path when opening is
+/// turned off, e.g. in CI or over SSH.
+fn open_rendered(file: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    match config.graphviz_settings.open_rendered {
+        true => graph::open_dot_file(file),
+        false => {
+            println!("{}", file.display());
+            Ok(())
+        }
+    }
+}
+
 /// Save the DOT file content to a file.
 fn save_dot_file(dot_file: &Path, content: &str) -> Result<(), Box<dyn Error>> {
     let mut file = File::create(dot_file)?;
@@ -331,14 +2996,17 @@ fn save_dot_file(dot_file: &Path, content: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-/// Run the 'dot' command to generate a PNG file from the DOT file.
+/// Run the 'dot' command to generate a PNG file from the DOT file, laying it out with
+/// the given Graphviz engine.
 fn run_dot_command(
     dot_file: &Path,
     output_file: &Path,
     format: &str,
+    engine: &str,
 ) -> Result<(), Box<dyn Error>> {
     let mut cmd = Command::new("dot")
-        .args(&[
+        .args([
+            &format!("-K{}", engine),
             &format!("-T{}", format),
             dot_file.to_str().unwrap(),
             "-o",
@@ -346,7 +3014,13 @@ fn run_dot_command(
         ])
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .spawn()?;
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                "Graphviz `dot` was not found; install Graphviz to render this diagram".into()
+            }
+            _ => Box::<dyn Error>::from(e),
+        })?;
 
     let status = cmd.wait()?;
 
@@ -358,6 +3032,10 @@ fn run_dot_command(
         ));
         Ok(())
     } else {
-        Err(format!("Failed to run `dot` command: {:?}", status).into())
+        Err(format!(
+            "Failed to run `dot` command with the {} engine: {:?}",
+            engine, status
+        )
+        .into())
     }
 }
@@ -1,6 +1,6 @@
 use crate::cli::InitArgs;
 use crate::config::{self, Config};
-use crate::sql::process_sql_schema;
+use crate::sql::process_sql_schemas;
 use std::error::Error;
 use std::path::Path;
 use std::process::exit;
@@ -34,19 +34,25 @@ pub fn execute(args: &InitArgs) -> Result<(), Box<dyn Error>> {
         config::display_relative_path(&config_file)
     );
 
-    if let Some(schema_path) = config::find_sql_schema() {
-        println!(
-            "Found SQL schema: {}",
-            config::display_relative_path(&schema_path)
-        );
-        process_sql_schema(&schema_path, &config_dir).unwrap_or_else(|e| {
+    let schemas = config::read_sql_schemas(&config).unwrap_or_else(|e| {
+        eprintln!("Failed to read SQL schema: {}", e);
+        exit(1);
+    });
+    if schemas.is_empty() {
+        println!("No SQL schema found in the current directory.");
+    } else {
+        for (schema_path, _) in &schemas {
+            println!(
+                "Found SQL schema: {}",
+                config::display_relative_path(schema_path)
+            );
+        }
+        process_sql_schemas(&schemas, &config_dir).unwrap_or_else(|e| {
             eprintln!("Failed to process SQL schema: {}", e);
             eprintln!("The configuration was created, but the SQL schema could not be processed.");
             exit(1);
         });
         println!("SQL schema processed successfully.");
-    } else {
-        println!("No SQL schema found in the current directory.");
     }
 
     println!("Configuration initialized successfully in the current working directory.");
@@ -4,7 +4,7 @@ use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::{env, fs, io};
 
-use crate::csv::DataFrame;
+use crate::csv::{DataFrame, Dialect};
 use crate::graph::SerializableGraph;
 use crate::utils;
 
@@ -16,6 +16,14 @@ pub struct Config {
     pub source_path: PathBuf,
     pub graphviz_settings: GraphvizSettings, // Graphviz rendering settings
     pub csv_output_path: PathBuf,            // Path for CSV files
+    #[serde(default)]
+    pub csv_dialect: Dialect, // Quoting used to read and write CSV files
+    #[serde(default)]
+    pub postgres_url: Option<String>, // Connection string for exporting to PostgreSQL
+    #[serde(default)]
+    pub exclude_tables: Vec<String>, // Table names or `*` patterns left out of the graph
+    #[serde(default)]
+    pub schema_paths: Vec<PathBuf>, // SQL schema files or folders merged into one graph
 }
 
 /// Graphviz rendering settings.
@@ -23,6 +31,65 @@ pub struct Config {
 pub struct GraphvizSettings {
     pub engine: String, // Engine to use (e.g., "dot")
     pub format: String, // Output format (e.g., "png")
+    #[serde(default = "default_show_column_types")]
+    pub show_column_types: bool, // Label columns with their SQL type (e.g., "id: INT")
+    #[serde(default)]
+    pub style: DiagramStyle, // Shapes, fonts, colors and spacing of the diagram
+    #[serde(default = "default_open_rendered")]
+    pub open_rendered: bool, // Open rendered diagrams in the default viewer
+}
+
+fn default_show_column_types() -> bool {
+    true
+}
+
+fn default_open_rendered() -> bool {
+    true
+}
+
+impl Default for GraphvizSettings {
+    fn default() -> Self {
+        GraphvizSettings {
+            engine: "dot".to_string(),
+            format: "png".to_string(),
+            show_column_types: default_show_column_types(),
+            style: DiagramStyle::default(),
+            open_rendered: default_open_rendered(),
+        }
+    }
+}
+
+/// Look of a rendered diagram. Missing keys keep their default.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiagramStyle {
+    pub node_shape: String,       // `record`, or `Mrecord` for rounded corners
+    pub font_name: String,        // Font of all labels
+    pub title_font_size: f64,     // Point size of table names
+    pub title_color: String,      // Color of table names
+    pub edge_font_size: f64,      // Point size of foreign key labels
+    pub edge_color: String,       // Color of foreign key lines
+    pub background_color: String, // Color behind the diagram
+    pub rankdir: String,          // Direction of the layout: `TB`, `LR`, `BT` or `RL`
+    pub nodesep: f64,             // Space between tables of the same rank, in inches
+    pub ranksep: f64,             // Space between ranks, in inches
+}
+
+impl Default for DiagramStyle {
+    fn default() -> Self {
+        DiagramStyle {
+            node_shape: "record".to_string(),
+            font_name: "Arial".to_string(),
+            title_font_size: 16.0,
+            title_color: "red".to_string(),
+            edge_font_size: 12.0,
+            edge_color: "black".to_string(),
+            background_color: "white".to_string(),
+            rankdir: "TB".to_string(),
+            nodesep: 1.0,
+            ranksep: 0.5,
+        }
+    }
 }
 
 impl Default for Config {
@@ -31,41 +98,34 @@ impl Default for Config {
             output_file: String::from("output.csv"),
             output_path: PathBuf::from(".csvgraph/generated-files"),
             source_path: PathBuf::from("./"),
-            graphviz_settings: GraphvizSettings {
-                engine: "dot".to_string(),
-                format: "png".to_string(),
-            },
+            graphviz_settings: GraphvizSettings::default(),
             csv_output_path: PathBuf::from("csv"),
+            csv_dialect: Dialect::default(),
+            postgres_url: None,
+            exclude_tables: Vec::new(),
+            schema_paths: Vec::new(),
         }
     }
 }
 
 /// Creates configuration folder and file if missing.
 pub fn create_config_folder() -> Result<PathBuf, io::Error> {
-    let current_dir = std::env::current_dir().map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to get current directory: {}", e),
-        )
-    })?;
+    let current_dir = std::env::current_dir()
+        .map_err(|e| io::Error::other(format!("Failed to get current directory: {}", e)))?;
     let config_dir = current_dir.join(".csvgraph");
     fs::create_dir_all(&config_dir).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to create config directory {:?}: {}", config_dir, e),
-        )
+        io::Error::other(format!(
+            "Failed to create config directory {:?}: {}",
+            config_dir, e
+        ))
     })?;
 
     let cfg = Config::default();
     let config_file = config_dir.join("config.json");
 
     if !config_file.exists() {
-        write_config(&cfg, &config_file).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to write initial config file: {}", e),
-            )
-        })?;
+        write_config(&cfg, &config_file)
+            .map_err(|e| io::Error::other(format!("Failed to write initial config file: {}", e)))?;
     }
 
     Ok(config_dir)
@@ -73,12 +133,8 @@ pub fn create_config_folder() -> Result<PathBuf, io::Error> {
 
 /// Writes configuration to a JSON file.
 pub fn write_config(config: &Config, config_path: &Path) -> io::Result<()> {
-    let config_json = serde_json::to_string_pretty(config).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to serialize config: {}", e),
-        )
-    })?;
+    let config_json = serde_json::to_string_pretty(config)
+        .map_err(|e| io::Error::other(format!("Failed to serialize config: {}", e)))?;
     fs::write(config_path, config_json)
 }
 
@@ -93,13 +149,48 @@ pub fn read_config(config_dir: &Path) -> std::io::Result<Config> {
     Ok(Config::default())
 }
 
-/// Finds the first `.sql` file in the current directory.
-pub fn find_sql_schema() -> Option<PathBuf> {
-    fs::read_dir(".")
-        .ok()?
+/// Finds the SQL schema files to build the graph from: those listed in `schema_paths`,
+/// where a folder stands for the `.sql` files in it, or else every `.sql` file in the
+/// current directory.
+pub fn find_sql_schemas(config: &Config) -> Vec<PathBuf> {
+    if config.schema_paths.is_empty() {
+        return sql_files_in(Path::new("."));
+    }
+    config
+        .schema_paths
+        .iter()
+        .flat_map(|path| match path.is_dir() {
+            true => sql_files_in(path),
+            false => vec![path.clone()],
+        })
+        .collect()
+}
+
+/// Lists the `.sql` files in `dir` by name.
+fn sql_files_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
         .filter_map(Result::ok)
-        .find(|entry| entry.path().extension().map_or(false, |ext| ext == "sql"))
         .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Reads the schema files found by [`find_sql_schemas`] as `(path, content)` pairs.
+pub fn read_sql_schemas(config: &Config) -> io::Result<Vec<(PathBuf, String)>> {
+    find_sql_schemas(config)
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path).map_err(|e| {
+                io::Error::new(e.kind(), format!("{}: {}", display_relative_path(&path), e))
+            })?;
+            Ok((path, content))
+        })
+        .collect()
 }
 
 /// Serializes and caches the graph to a file.
@@ -109,12 +200,8 @@ pub fn write_graph_cache(
 ) -> io::Result<()> {
     let graph_path = config_dir.join("graph.json");
     let serializable = SerializableGraph::from(graph);
-    let serialized = serde_json::to_string(&serializable).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to serialize graph: {}", e),
-        )
-    })?;
+    let serialized = serde_json::to_string(&serializable)
+        .map_err(|e| io::Error::other(format!("Failed to serialize graph: {}", e)))?;
     fs::write(graph_path, serialized)?;
     Ok(())
 }
@@ -123,15 +210,49 @@ pub fn write_graph_cache(
 pub fn read_graph_cache(config_dir: &Path) -> io::Result<UnGraph<DataFrame, (String, String)>> {
     let graph_path = config_dir.join("graph.json");
     let serialized = fs::read_to_string(graph_path)?;
-    let serializable: SerializableGraph = serde_json::from_str(&serialized).map_err(|e| {
-        io::Error::new(
-            io::ErrorKind::Other,
-            format!("Failed to deserialize graph: {}", e),
-        )
-    })?;
+    let serializable: SerializableGraph = serde_json::from_str(&serialized)
+        .map_err(|e| io::Error::other(format!("Failed to deserialize graph: {}", e)))?;
     Ok(serializable.into_graph())
 }
 
+/// Hashes schema content with 64-bit FNV-1a, which unlike the standard library's
+/// hasher gives the same result across Rust versions.
+pub fn schema_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Hashes the contents of several schema files together. A single file hashes the same
+/// as its content alone.
+pub fn schemas_hash(schemas: &[(PathBuf, String)]) -> String {
+    let contents: Vec<&str> = schemas
+        .iter()
+        .map(|(_, content)| content.as_str())
+        .collect();
+    schema_hash(&contents.join("\n"))
+}
+
+/// Records the hash of the schema the cached graph was built from.
+pub fn write_schema_hash(hash: &str, config_dir: &Path) -> io::Result<()> {
+    fs::write(config_dir.join("graph.hash"), hash)
+}
+
+/// Forgets the schema hash, for a cached graph that was not built from a schema file.
+pub fn remove_schema_hash(config_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(config_dir.join("graph.hash")) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Checks whether `hash` differs from the hash of the schema the cached graph was built
+/// from. A cache without a recorded hash is never stale.
+pub fn graph_cache_is_stale(hash: &str, config_dir: &Path) -> bool {
+    fs::read_to_string(config_dir.join("graph.hash")).is_ok_and(|cached| hash != cached.trim())
+}
+
 /// Checks if the graph cache file exists.
 pub fn graph_cache_exists(config_dir: &Path) -> bool {
     config_dir.join("graph.json").exists()
@@ -0,0 +1,168 @@
+//! Converting CSV rows to Arrow record batches, and writing them as an Arrow IPC stream.
+use super::{ColumnSchema, ColumnType, DataFrame, Dialect};
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::error::Error;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Rows gathered into each record batch.
+const BATCH_SIZE: usize = 8192;
+
+/// The rows of a CSV file as Arrow record batches. Column types are inferred from every
+/// row; ints, floats and booleans keep their type, dates and text are stored as
+/// strings, and empty fields become nulls.
+pub(crate) struct CsvBatches {
+    df: DataFrame,
+    path: PathBuf,
+    columns: Vec<ColumnSchema>,
+    pub schema: SchemaRef,
+}
+
+impl CsvBatches {
+    /// Reads the header of the CSV file at `path` and infers its column types.
+    pub fn infer(path: &Path, dialect: &Dialect) -> Result<Self, Box<dyn Error>> {
+        let mut df = DataFrame::new("arrow".to_string()).with_dialect(dialect.clone());
+        df.read_headers(path)?;
+        let mut input = df.open_rows(path)?;
+        let columns = df.infer_types(&mut input, None)?;
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|column| Field::new(&column.name, data_type(column.column_type), true))
+                .collect::<Vec<_>>(),
+        ));
+        Ok(CsvBatches {
+            df,
+            path: path.to_path_buf(),
+            columns,
+            schema,
+        })
+    }
+
+    /// Calls `f` with each batch of rows in order and returns the number of rows.
+    pub fn for_each(
+        &self,
+        mut f: impl FnMut(&RecordBatch) -> Result<(), Box<dyn Error>>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut batch: Vec<Vec<String>> = Vec::with_capacity(BATCH_SIZE);
+        let mut rows = 0;
+        self.df
+            .process_rows(&mut self.df.open_rows(&self.path)?, |row| {
+                batch.push(row.to_vec());
+                if batch.len() == BATCH_SIZE {
+                    f(&record_batch(&self.schema, &self.columns, &batch)?)?;
+                    rows += batch.len();
+                    batch.clear();
+                }
+                Ok(())
+            })?;
+        if !batch.is_empty() {
+            f(&record_batch(&self.schema, &self.columns, &batch)?)?;
+            rows += batch.len();
+        }
+        Ok(rows)
+    }
+}
+
+/// Writes the CSV file at `path` as an Arrow IPC stream, with the column types described
+/// in [`CsvBatches`]. Returns the number of rows.
+pub fn write_arrow<W: Write>(
+    path: &Path,
+    output: W,
+    dialect: &Dialect,
+) -> Result<usize, Box<dyn Error>> {
+    let batches = CsvBatches::infer(path, dialect)?;
+    let mut writer = StreamWriter::try_new(output, &batches.schema)?;
+    let rows = batches.for_each(|batch| Ok(writer.write(batch)?))?;
+    writer.finish()?;
+    Ok(rows)
+}
+
+/// The Arrow type a column of this type is stored as.
+fn data_type(column_type: ColumnType) -> DataType {
+    match column_type {
+        ColumnType::Int => DataType::Int64,
+        ColumnType::Float => DataType::Float64,
+        ColumnType::Bool => DataType::Boolean,
+        ColumnType::Date | ColumnType::String => DataType::Utf8,
+    }
+}
+
+/// Builds one record batch from rows whose columns have the inferred types.
+fn record_batch(
+    schema: &SchemaRef,
+    columns: &[ColumnSchema],
+    rows: &[Vec<String>],
+) -> Result<RecordBatch, Box<dyn Error>> {
+    let arrays = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let values = rows.iter().map(|row| row[i].as_str());
+            column_array(column, values)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+/// Builds the array of one column, turning empty fields into nulls.
+fn column_array<'a>(
+    column: &ColumnSchema,
+    values: impl Iterator<Item = &'a str>,
+) -> Result<ArrayRef, Box<dyn Error>> {
+    let cast = |value: &str| {
+        column.column_type.cast(value).ok_or_else(|| {
+            format!(
+                "Value '{}' in column '{}' is not a {}",
+                value, column.name, column.column_type
+            )
+        })
+    };
+    let array: ArrayRef = match column.column_type {
+        ColumnType::Int => {
+            let mut builder = Int64Builder::new();
+            for value in values {
+                match value.trim().is_empty() {
+                    true => builder.append_null(),
+                    false => builder.append_value(cast(value)?.parse()?),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Float => {
+            let mut builder = Float64Builder::new();
+            for value in values {
+                match value.trim().is_empty() {
+                    true => builder.append_null(),
+                    false => builder.append_value(cast(value)?.parse()?),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Bool => {
+            let mut builder = BooleanBuilder::new();
+            for value in values {
+                match value.trim().is_empty() {
+                    true => builder.append_null(),
+                    false => builder.append_value(cast(value)? == "true"),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+        ColumnType::Date | ColumnType::String => {
+            let mut builder = StringBuilder::new();
+            for value in values {
+                match value.is_empty() {
+                    true => builder.append_null(),
+                    false => builder.append_value(value),
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    };
+    Ok(array)
+}
@@ -0,0 +1,101 @@
+//! Validating and converting column values to a type.
+use super::{ColumnType, DataFrame};
+use crate::cli::CastOnError;
+use crate::utils::print_info;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// A value that could not be converted by [`DataFrame::cast_stream`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastFailure {
+    /// Line in the file, counting the header as line 1.
+    pub line: usize,
+    pub column: String,
+    pub value: String,
+    pub column_type: ColumnType,
+}
+
+/// Parses a `column:type` cast specification.
+pub fn parse_cast_spec(spec: &str) -> Result<(String, ColumnType), Box<dyn Error>> {
+    let (column, column_type) = spec
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Invalid cast '{}', expected <COLUMN>:<TYPE>", spec))?;
+    if column.is_empty() {
+        return Err(format!("Invalid cast '{}', missing column name", spec).into());
+    }
+    Ok((column.to_string(), column_type.parse()?))
+}
+
+impl DataFrame {
+    /// Converts the given columns to their types, leaving empty values empty. Values
+    /// that fail to convert are handled according to `on_error` and returned so the
+    /// caller can report them.
+    pub fn cast_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        casts: &[(String, ColumnType)],
+        on_error: &CastOnError,
+    ) -> Result<Vec<CastFailure>, Box<dyn Error>> {
+        let casts = casts
+            .iter()
+            .map(|(column, column_type)| {
+                Ok((
+                    Self::extract_header_index(&self.headers, column)?,
+                    *column_type,
+                ))
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut failures = Vec::new();
+        let mut reader = self.row_reader(input);
+        for result in reader.records() {
+            let record = result?;
+            let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            if !self.fit_row(&mut row, &record)? {
+                continue;
+            }
+            let line = Self::line_of(record.position()) as usize;
+            let mut failed = false;
+            for &(index, column_type) in &casts {
+                let Some(value) = row.get_mut(index) else {
+                    continue;
+                };
+                if value.trim().is_empty() {
+                    continue;
+                }
+                match column_type.cast(value) {
+                    Some(converted) => *value = converted,
+                    None => {
+                        let failure = CastFailure {
+                            line,
+                            column: self.headers[index].clone(),
+                            value: value.clone(),
+                            column_type,
+                        };
+                        if let CastOnError::Fail = on_error {
+                            return Err(format!(
+                                "Line {}: cannot cast '{}' in column '{}' to {}",
+                                failure.line, failure.value, failure.column, failure.column_type
+                            )
+                            .into());
+                        }
+                        value.clear();
+                        failures.push(failure);
+                        failed = true;
+                    }
+                }
+            }
+            if !(failed && matches!(on_error, CastOnError::Skip)) {
+                self.write_row(output, &row)?;
+            }
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(failures)
+    }
+}
@@ -0,0 +1,116 @@
+//! Normalization of header names and field values.
+use super::DataFrame;
+use crate::utils::print_info;
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// Trims a value and collapses each run of internal whitespace into a single space.
+pub fn normalize_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Converts a header name such as `Order ID` or `orderId` into `order_id`.
+pub fn snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut previous: Option<char> = None;
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            // Split camelCase words, but keep acronyms like `ID` together.
+            let boundary = c.is_uppercase()
+                && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit());
+            if boundary && !result.ends_with('_') {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else if !result.is_empty() && !result.ends_with('_') {
+            result.push('_');
+        }
+        previous = Some(c);
+    }
+    result.trim_end_matches('_').to_string()
+}
+
+/// Letter case applied by [`DataFrame::transform_stream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextCase {
+    Upper,
+    Lower,
+}
+
+impl TextCase {
+    pub fn apply(self, value: &str) -> String {
+        match self {
+            TextCase::Upper => value.to_uppercase(),
+            TextCase::Lower => value.to_lowercase(),
+        }
+    }
+}
+
+impl DataFrame {
+    /// Normalizes whitespace in the header and every field, optionally converting the
+    /// header names to snake_case.
+    pub fn clean_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        snake_case_headers: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|h| match snake_case_headers {
+                true => snake_case(h),
+                false => normalize_whitespace(h),
+            })
+            .collect();
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = headers.iter().find(|h| !seen.insert(h.as_str())) {
+            return Err(format!("Cleaned headers contain duplicate column '{}'", duplicate).into());
+        }
+        self.write_row(output, &headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let cleaned: Vec<String> = row.iter().map(|v| normalize_whitespace(v)).collect();
+            self.write_row(output, &cleaned)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+
+    /// Converts the values of `columns` to the given letter case.
+    pub fn transform_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        columns: &[String],
+        case: TextCase,
+    ) -> Result<(), Box<dyn Error>> {
+        let positions = columns
+            .iter()
+            .map(|column| Self::extract_header_index(&self.headers, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let mut row = row.to_vec();
+            for &i in &positions {
+                if let Some(value) = row.get_mut(i) {
+                    *value = case.apply(value);
+                }
+            }
+            self.write_row(output, &row)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! Gzip and zstd compressed CSV files, recognised by their extension.
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use std::error::Error;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// A compression format applied to a whole CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression implied by a `.gz` or `.zst` extension, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Wraps `input` so that reading from it yields the decompressed bytes.
+    pub fn decoder<'a, R: Read + 'a>(
+        &self,
+        input: R,
+    ) -> Result<Box<dyn Read + 'a>, Box<dyn Error>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(MultiGzDecoder::new(input)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(input)?),
+        })
+    }
+
+    /// Compresses everything left in `input` into `output`, returning `output` once the
+    /// compressed stream has been finished.
+    pub fn compress<R: Read, W: Write>(
+        &self,
+        input: &mut R,
+        output: W,
+    ) -> Result<W, Box<dyn Error>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(output, flate2::Compression::default());
+                io::copy(input, &mut encoder)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(output, 0)?;
+                io::copy(input, &mut encoder)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+}
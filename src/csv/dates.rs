@@ -0,0 +1,93 @@
+//! Parsing and reformatting date columns.
+use super::{CastFailure, ColumnType, DataFrame};
+use crate::cli::CastOnError;
+use crate::utils::print_info;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// Fails when `format` contains an unknown strftime specifier.
+fn validate_format(format: &str) -> Result<(), Box<dyn Error>> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date format '{}'", format).into());
+    }
+    Ok(())
+}
+
+/// Parses `value` with the strftime format `from` and renders it with `to`. Formats
+/// with a UTC offset, a date and time, or only a date are all accepted; a date alone
+/// is treated as midnight.
+pub fn reformat_date(value: &str, from: &str, to: &str) -> Option<String> {
+    let value = value.trim();
+    let mut formatted = String::new();
+    let written = if let Ok(datetime) = DateTime::parse_from_str(value, from) {
+        write!(formatted, "{}", datetime.format(to))
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(value, from) {
+        write!(formatted, "{}", datetime.format(to))
+    } else {
+        let date = NaiveDate::parse_from_str(value, from).ok()?;
+        write!(formatted, "{}", date.and_hms_opt(0, 0, 0)?.format(to))
+    };
+    // Formatting fails when `to` asks for an offset the value does not carry.
+    written.ok().map(|_| formatted)
+}
+
+impl DataFrame {
+    /// Rewrites the dates in `column` from the `from` format to the `to` format,
+    /// leaving empty values empty. Values that do not match `from` are handled
+    /// according to `on_error` and returned so the caller can report them.
+    pub fn dateformat_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        column: &str,
+        from: &str,
+        to: &str,
+        on_error: &CastOnError,
+    ) -> Result<Vec<CastFailure>, Box<dyn Error>> {
+        validate_format(from)?;
+        validate_format(to)?;
+        let index = Self::extract_header_index(&self.headers, column)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut failures = Vec::new();
+        let mut line = 1;
+        self.process_rows(input, |row| {
+            line += 1;
+            let mut row = row.to_vec();
+            if let Some(value) = row.get_mut(index).filter(|v| !v.trim().is_empty()) {
+                match reformat_date(value, from, to) {
+                    Some(formatted) => *value = formatted,
+                    None => {
+                        if let CastOnError::Fail = on_error {
+                            return Err(format!(
+                                "Line {}: '{}' in column '{}' does not match format '{}'",
+                                line, value, column, from
+                            )
+                            .into());
+                        }
+                        failures.push(CastFailure {
+                            line,
+                            column: column.to_string(),
+                            value: std::mem::take(value),
+                            column_type: ColumnType::Date,
+                        });
+                        if let CastOnError::Skip = on_error {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            self.write_row(output, &row)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(failures)
+    }
+}
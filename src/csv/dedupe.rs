@@ -0,0 +1,74 @@
+//! Removing duplicate rows.
+use super::DataFrame;
+use crate::cli::DedupeKeep;
+use crate::utils::print_info;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+impl DataFrame {
+    /// Writes one row per distinct value of the `key` columns, or per distinct row when
+    /// `key` is empty, and returns how many rows were dropped. `DedupeKeep::First`
+    /// streams; `DedupeKeep::Last` holds the rows in memory and outputs each survivor
+    /// at the position of its last occurrence.
+    pub fn dedupe_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        key: &[String],
+        keep: &DedupeKeep,
+    ) -> Result<usize, Box<dyn Error>> {
+        let key_positions = key
+            .iter()
+            .map(|column| Self::extract_header_index(&self.headers, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key_of = |row: &[String]| -> Vec<String> {
+            match key_positions.is_empty() {
+                true => row.to_vec(),
+                false => key_positions
+                    .iter()
+                    .map(|&i| row.get(i).cloned().unwrap_or_default())
+                    .collect(),
+            }
+        };
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut total = 0;
+        let mut kept = 0;
+        match keep {
+            DedupeKeep::First => {
+                let mut seen = HashSet::new();
+                self.process_rows(input, |row| {
+                    total += 1;
+                    if seen.insert(key_of(row)) {
+                        kept += 1;
+                        self.write_row(output, row)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            DedupeKeep::Last => {
+                let mut rows = Vec::new();
+                let mut last = HashMap::new();
+                self.process_rows(input, |row| {
+                    last.insert(key_of(row), rows.len());
+                    rows.push(row.to_vec());
+                    Ok(())
+                })?;
+                total = rows.len();
+                let mut survivors: Vec<usize> = last.into_values().collect();
+                survivors.sort_unstable();
+                kept = survivors.len();
+                for position in survivors {
+                    self.write_row(output, &rows[position])?;
+                }
+            }
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(total - kept)
+    }
+}
@@ -0,0 +1,218 @@
+//! Quoting, escaping and layout conventions used to read and write CSV.
+use crate::cli::{DuplicateHeaders, LineEnding, RaggedPolicy};
+use ::csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// How fields are quoted and escaped, how records end, and how rows that do not match
+/// the header are treated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dialect {
+    /// Character wrapping fields that contain special characters.
+    pub quote: char,
+    /// Character placed before a quote inside a quoted field. When unset, quotes are
+    /// escaped by doubling them as RFC 4180 describes.
+    pub escape: Option<char>,
+    /// Quote every field on output rather than only the fields that need it.
+    pub always_quote: bool,
+    /// Terminator written after each record.
+    pub line_ending: LineEnding,
+    /// Treatment of rows with more or fewer fields than the header.
+    pub on_ragged: RaggedPolicy,
+    /// Treatment of header names that appear more than once.
+    pub on_duplicate_headers: DuplicateHeaders,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            quote: '"',
+            escape: None,
+            always_quote: false,
+            line_ending: LineEnding::Lf,
+            on_ragged: RaggedPolicy::Error,
+            on_duplicate_headers: DuplicateHeaders::Rename,
+        }
+    }
+}
+
+impl Dialect {
+    /// Checks that the quote and escape characters can be used by the CSV parser.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for (name, c) in [("Quote", Some(self.quote)), ("Escape", self.escape)] {
+            match c {
+                Some(c) if !c.is_ascii() || c == ',' || c == '\n' || c == '\r' => {
+                    return Err(format!(
+                        "{} character '{}' must be ASCII and not a delimiter or line break",
+                        name, c
+                    )
+                    .into());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the record terminator. An unresolved `Preserve` falls back to `\n`.
+    pub fn line_terminator(&self) -> &'static str {
+        match self.line_ending {
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Lf | LineEnding::Preserve => "\n",
+        }
+    }
+
+    /// Replaces `Preserve` with the line ending that terminates the first line of
+    /// `path`, so output matches the input file.
+    pub(crate) fn resolve_line_ending(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if self.line_ending != LineEnding::Preserve {
+            return Ok(());
+        }
+        let mut first_line = Vec::new();
+        BufReader::new(File::open(path)?).read_until(b'\n', &mut first_line)?;
+        self.line_ending = match first_line.ends_with(b"\r\n") {
+            true => LineEnding::Crlf,
+            false => LineEnding::Lf,
+        };
+        Ok(())
+    }
+
+    /// Decides whether a row of `found` fields read from `line` is kept when `width`
+    /// fields are expected, warning about every ragged row that is fixed or skipped.
+    /// A `width` of zero disables the check.
+    pub(crate) fn keep_ragged(
+        &self,
+        width: usize,
+        found: usize,
+        line: u64,
+    ) -> Result<bool, Box<dyn Error>> {
+        if width == 0 || found == width {
+            return Ok(true);
+        }
+        let keep = match self.on_ragged {
+            RaggedPolicy::Error => {
+                return Err(format!(
+                    "Line {}: expected {} fields but found {}",
+                    line, width, found
+                )
+                .into())
+            }
+            RaggedPolicy::Pad => true,
+            RaggedPolicy::Truncate => found > width,
+            RaggedPolicy::Skip => false,
+        };
+        eprintln!(
+            "Warning: line {}: expected {} fields but found {}, {} the row",
+            line,
+            width,
+            found,
+            if keep { "resized" } else { "skipped" }
+        );
+        Ok(keep)
+    }
+
+    /// Applies the ragged row policy to `row`, resizing it to `width` fields when it
+    /// is kept. Returns `false` when the row should be skipped.
+    pub(crate) fn fit_row(
+        &self,
+        row: &mut Vec<String>,
+        width: usize,
+        line: u64,
+    ) -> Result<bool, Box<dyn Error>> {
+        if !self.keep_ragged(width, row.len(), line)? {
+            return Ok(false);
+        }
+        if width > 0 {
+            row.resize(width, String::new());
+        }
+        Ok(true)
+    }
+
+    /// Makes every header name unique so columns are never addressed ambiguously.
+    /// Repeated names are suffixed with their occurrence number (`name`, `name_2`),
+    /// skipping any suffix already taken, unless the policy asks for an error.
+    pub(crate) fn unique_headers(
+        &self,
+        headers: Vec<String>,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut unique = Vec::with_capacity(headers.len());
+        for header in &headers {
+            if seen.insert(header.clone()) {
+                unique.push(header.clone());
+                continue;
+            }
+            if self.on_duplicate_headers == DuplicateHeaders::Error {
+                return Err(format!("Duplicate header '{}'", header).into());
+            }
+            let renamed = (2..)
+                .map(|n| format!("{}_{}", header, n))
+                .find(|name| !seen.contains(name) && !headers.contains(name))
+                .unwrap_or_default();
+            eprintln!(
+                "Warning: duplicate header '{}' renamed to '{}'",
+                header, renamed
+            );
+            seen.insert(renamed.clone());
+            unique.push(renamed);
+        }
+        Ok(unique)
+    }
+
+    /// Returns a reader builder configured for this dialect.
+    pub(crate) fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .quote(self.quote as u8)
+            .escape(self.escape.map(|c| c as u8))
+            .double_quote(self.escape.is_none());
+        builder
+    }
+
+    /// Returns a writer builder producing records this dialect reads back.
+    pub(crate) fn writer_builder(&self) -> WriterBuilder {
+        let mut builder = WriterBuilder::new();
+        builder
+            .quote(self.quote as u8)
+            .escape(self.escape.map_or(self.quote as u8, |c| c as u8))
+            .double_quote(self.escape.is_none());
+        if self.always_quote {
+            builder.quote_style(QuoteStyle::Always);
+        }
+        builder
+    }
+
+    /// Writes a single field, quoting and escaping it as needed.
+    pub(crate) fn write_field<W: Write + ?Sized>(
+        &self,
+        output: &mut W,
+        field: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let special = |c: char| {
+            c == ',' || c == '\n' || c == '\r' || c == self.quote || Some(c) == self.escape
+        };
+        if !self.always_quote && !field.contains(special) {
+            output.write_all(field.as_bytes())?;
+            return Ok(());
+        }
+
+        let mut quoted = String::with_capacity(field.len() + 2);
+        quoted.push(self.quote);
+        for c in field.chars() {
+            match self.escape {
+                Some(escape) if c == self.quote || c == escape => quoted.push(escape),
+                None if c == self.quote => quoted.push(self.quote),
+                _ => {}
+            }
+            quoted.push(c);
+        }
+        quoted.push(self.quote);
+        output.write_all(quoted.as_bytes())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Approximate string matching used by fuzzy joins.
+use std::cmp::Ordering;
+
+/// How close two keys must be for a fuzzy join to match them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FuzzyThreshold {
+    /// At most this many single-character insertions, deletions or substitutions.
+    Distance(usize),
+    /// At least this similarity, from 0 (nothing in common) to 1 (identical).
+    Similarity(f64),
+}
+
+/// The edit distance between two composite keys and their similarity derived from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct KeyDistance {
+    pub distance: usize,
+    pub similarity: f64,
+}
+
+impl FuzzyThreshold {
+    /// Returns whether keys this far apart are close enough to match.
+    pub(crate) fn accepts(&self, key_distance: &KeyDistance) -> bool {
+        match *self {
+            FuzzyThreshold::Distance(max) => key_distance.distance <= max,
+            FuzzyThreshold::Similarity(min) => key_distance.similarity >= min,
+        }
+    }
+
+    /// Ranks two key distances by the measure this threshold uses, so `Greater` means
+    /// `a` is the closer match: fewer edits for a distance, more in common for a
+    /// similarity.
+    pub(crate) fn rank(&self, a: &KeyDistance, b: &KeyDistance) -> Ordering {
+        match self {
+            FuzzyThreshold::Distance(_) => b.distance.cmp(&a.distance),
+            FuzzyThreshold::Similarity(_) => a.similarity.total_cmp(&b.similarity),
+        }
+    }
+}
+
+/// Compares two keys column by column, summing the edit distances. Similarity is one
+/// minus the distance over the combined length of the longer value of each column.
+pub(crate) fn key_distance(a: &[String], b: &[String]) -> KeyDistance {
+    let (distance, longest) = a.iter().zip(b).fold((0, 0), |(distance, longest), (a, b)| {
+        (
+            distance + levenshtein(a, b),
+            longest + a.chars().count().max(b.chars().count()),
+        )
+    });
+    let similarity = match longest {
+        0 => 1.0,
+        _ => 1.0 - distance as f64 / longest as f64,
+    };
+    KeyDistance {
+        distance,
+        similarity,
+    }
+}
+
+/// Counts the single-character edits needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,77 @@
+//! Rendering CSV rows as a standalone, styled HTML table.
+use super::Dialect;
+use std::error::Error;
+use std::io::{Read, Write};
+
+const STYLE: &str = "\
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; font-size: 14px; }
+th, td { border: 1px solid #d0d7de; padding: 6px 12px; text-align: left; }
+th { background: #f3f4f6; position: sticky; top: 0; }
+tr:nth-child(even) td { background: #fafafa; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+p.note { color: #666; font-size: 13px; }
+";
+
+/// Rewrites the CSV read from `input` as a standalone HTML page holding one table, with
+/// the header row as column headings and numbers aligned right. With `max_rows`, only
+/// that many rows are shown, followed by a note of how many there were in total.
+pub fn write_html<R: Read, W: Write>(
+    input: R,
+    output: &mut W,
+    dialect: &Dialect,
+    max_rows: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = dialect.reader_builder().flexible(true).from_reader(input);
+    writeln!(output, "<!DOCTYPE html>")?;
+    writeln!(output, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(output, "<title>csvg</title>\n<style>\n{}</style>", STYLE)?;
+    writeln!(output, "</head>\n<body>\n<table>\n<thead>\n<tr>")?;
+    for header in reader.headers()? {
+        writeln!(output, "<th>{}</th>", escape(header))?;
+    }
+    writeln!(output, "</tr>\n</thead>\n<tbody>")?;
+
+    let mut rows = 0;
+    for record in reader.records() {
+        let record = record?;
+        rows += 1;
+        if max_rows.is_some_and(|max_rows| rows > max_rows) {
+            continue;
+        }
+        writeln!(output, "<tr>")?;
+        for value in record.iter() {
+            match value.trim().parse::<f64>() {
+                Ok(_) => writeln!(output, "<td class=\"num\">{}</td>", escape(value))?,
+                Err(_) => writeln!(output, "<td>{}</td>", escape(value))?,
+            }
+        }
+        writeln!(output, "</tr>")?;
+    }
+    writeln!(output, "</tbody>\n</table>")?;
+    if let Some(max_rows) = max_rows.filter(|&max_rows| rows > max_rows) {
+        writeln!(
+            output,
+            "<p class=\"note\">Showing {} of {} rows.</p>",
+            max_rows, rows
+        )?;
+    }
+    writeln!(output, "</body>\n</html>")?;
+    Ok(())
+}
+
+/// Escapes the characters that are special in HTML text and attributes.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,990 @@
+//! Joining two CSV inputs on one or more key columns.
+use super::fuzzy::{key_distance, FuzzyThreshold, KeyDistance};
+use super::merge::compare_keys;
+use super::DataFrame;
+use crate::cli::{JoinStrategy, JoinType};
+use crate::utils::print_info;
+use ::csv::{Reader, StringRecord};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::time::Instant;
+
+/// Rows of the right-hand join input, grouped by their join key.
+type KeyMap = BTreeMap<Vec<String>, Vec<Vec<String>>>;
+
+/// A row of a join input paired with its normalized key.
+type KeyedRow = (Vec<String>, Vec<String>);
+
+/// Number of partitions each input is split into by the `spill` strategy.
+const DEFAULT_PARTITIONS: usize = 64;
+
+/// Settings that change how [`DataFrame::join_stream`] matches key values.
+#[derive(Debug, Clone)]
+pub struct JoinOptions {
+    /// Compare key values that parse as numbers by value, so `001` matches `1` and
+    /// `2.50` matches `2.5`.
+    pub numeric_keys: bool,
+    /// Compare key values without regard to case, so `ABC123` matches `abc123`.
+    pub ignore_case: bool,
+    /// Appended to left columns whose name also appears among the right non-key columns.
+    pub left_suffix: String,
+    /// Appended to right non-key columns whose name also appears on the left.
+    pub right_suffix: String,
+    /// How matching rows are found.
+    pub strategy: JoinStrategy,
+    /// Number of temporary partitions per input used by the `spill` strategy.
+    pub partitions: usize,
+    /// Largest gap an as-of join accepts between a left key and the preceding right
+    /// key, in seconds for dates and times. `None` accepts any gap.
+    pub tolerance: Option<f64>,
+    /// Output columns to write, in order. Empty writes every joined column.
+    pub select: Vec<String>,
+    /// Leave out selected columns the output lacks instead of failing.
+    pub skip_missing_select: bool,
+    /// Match keys that are merely close rather than equal, adding a `match_score`
+    /// column with the similarity of each match.
+    pub fuzzy: Option<FuzzyThreshold>,
+    /// Let empty key values match each other. By default a row with an empty key
+    /// field matches nothing.
+    pub null_keys_match: bool,
+    /// Fill the key columns of rows found only on the right with the right row's key
+    /// values instead of leaving them empty, so a later join on those keys can still
+    /// match them.
+    pub keep_right_keys: bool,
+    /// Prefix for every left column in the output, written as `alias.column`.
+    pub left_alias: Option<String>,
+    /// Prefix for every right column in the output, written as `alias.column`.
+    pub right_alias: Option<String>,
+    /// Additionally require a left value to fall within an interval of the right row.
+    pub range: Option<RangeCondition>,
+}
+
+/// Matches rows whose left `column` lies between the right `start` and `end` columns,
+/// both ends included, comparing numbers, dates and times by value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeCondition {
+    pub column: String,
+    pub start: String,
+    pub end: String,
+}
+
+/// Right rows of a range join sorted by the start of their interval, with the largest
+/// end seen up to each position so that a lookup can stop scanning early.
+#[derive(Default)]
+struct IntervalIndex {
+    /// Interval start, end and the position of its row, ordered by start.
+    intervals: Vec<(String, String, usize)>,
+    max_end: Vec<String>,
+}
+
+impl IntervalIndex {
+    fn new(mut intervals: Vec<(String, String, usize)>) -> Self {
+        intervals.sort_by(|a, b| compare_asof(&a.0, &b.0));
+        let mut max_end: Vec<String> = Vec::with_capacity(intervals.len());
+        for (_, end, _) in &intervals {
+            let longest = match max_end.last() {
+                Some(last) if compare_asof(last, end) == Ordering::Greater => last.clone(),
+                _ => end.clone(),
+            };
+            max_end.push(longest);
+        }
+        Self { intervals, max_end }
+    }
+
+    /// Returns the rows whose interval contains `value`, in order of interval start.
+    fn containing(&self, value: &str) -> Vec<usize> {
+        let candidates = self
+            .intervals
+            .partition_point(|(start, _, _)| compare_asof(start, value) != Ordering::Greater);
+        let mut rows: Vec<usize> = (0..candidates)
+            .rev()
+            .take_while(|&i| compare_asof(&self.max_end[i], value) != Ordering::Less)
+            .filter(|&i| compare_asof(&self.intervals[i].1, value) != Ordering::Less)
+            .map(|i| self.intervals[i].2)
+            .collect();
+        rows.reverse();
+        rows
+    }
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        Self {
+            numeric_keys: false,
+            ignore_case: false,
+            left_suffix: String::new(),
+            right_suffix: String::new(),
+            strategy: JoinStrategy::Hash,
+            partitions: DEFAULT_PARTITIONS,
+            tolerance: None,
+            fuzzy: None,
+            null_keys_match: false,
+            keep_right_keys: false,
+            left_alias: None,
+            right_alias: None,
+            range: None,
+            select: Vec::new(),
+            skip_missing_select: false,
+        }
+    }
+}
+
+impl JoinOptions {
+    /// Returns the form of a key value that is compared when matching rows.
+    fn normalize_key(&self, value: &str) -> String {
+        if self.numeric_keys {
+            if let Some(number) = numeric_key(value) {
+                return number;
+            }
+        }
+        match self.ignore_case {
+            true => value.to_lowercase(),
+            false => value.to_string(),
+        }
+    }
+}
+
+/// Canonical text of a numeric value, or `None` if it is not a finite number.
+fn numeric_key(value: &str) -> Option<String> {
+    let value = value.trim();
+    if let Ok(n) = value.parse::<i128>() {
+        return Some(n.to_string());
+    }
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(|f| match f.fract() == 0.0 && f.abs() < 1e15 {
+            true => (f as i64).to_string(),
+            false => f.to_string(),
+        })
+}
+
+/// Orders composite join keys column by column, numerically where both values are
+/// numbers.
+fn compare_join_keys(a: &[String], b: &[String]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| compare_keys(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Reads an as-of key as a number: numbers as they are, and dates and times as seconds
+/// since the Unix epoch.
+fn asof_value(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Ok(number) = value.parse::<f64>() {
+        return Some(number);
+    }
+    let seconds = |datetime: NaiveDateTime| datetime.and_utc().timestamp_millis() as f64 / 1000.0;
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(seconds(datetime.naive_utc()));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(seconds)
+}
+
+/// Orders as-of keys by time or number when both parse, and as strings otherwise.
+fn compare_asof(a: &str, b: &str) -> Ordering {
+    match (asof_value(a), asof_value(b)) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Fails when an as-of key sorts before the previous key read from the same input.
+fn check_asof_order(
+    side: &str,
+    key: &str,
+    last: &mut Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(last) = last.as_deref() {
+        if compare_asof(key, last) == Ordering::Less {
+            return Err(format!(
+                "The {} input is not sorted on the as-of key: '{}' follows '{}'",
+                side, key, last
+            )
+            .into());
+        }
+    }
+    *last = Some(key.to_string());
+    Ok(())
+}
+
+/// Prefix of the value standing in for an empty key field when nulls never match.
+/// It is followed by the side's name, so null keys differ across the two inputs.
+const NULL_KEY: char = '\0';
+
+/// Returns whether a normalized key holds an empty field that must not match.
+fn is_null_key(key: &[String]) -> bool {
+    key.iter().any(|value| value.starts_with(NULL_KEY))
+}
+
+/// Returns a normalized key as written in messages, with empty fields shown empty.
+fn display_key(key: &[String]) -> String {
+    key.iter()
+        .map(|value| match value.starts_with(NULL_KEY) {
+            true => "",
+            false => value.as_str(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// One of the two inputs of a join.
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    fn name(self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+        }
+    }
+}
+
+/// Where the key columns sit on each side of a join, and how rows are matched.
+struct JoinPlan<'a> {
+    left_indices: Vec<usize>,
+    left_width: usize,
+    right_indices: Vec<usize>,
+    right_width: usize,
+    join_type: &'a JoinType,
+    options: &'a JoinOptions,
+    /// Positions of the selected output columns, or `None` to write them all.
+    projection: Option<Vec<usize>>,
+    /// Positions of the range condition's left column and right start and end columns.
+    range: Option<(usize, usize, usize)>,
+}
+
+impl JoinPlan<'_> {
+    /// Returns the key column positions and field count of one input.
+    fn side(&self, side: Side) -> (&[usize], usize) {
+        match side {
+            Side::Left => (&self.left_indices, self.left_width),
+            Side::Right => (&self.right_indices, self.right_width),
+        }
+    }
+}
+
+impl DataFrame {
+    /// Collects the normalized values of the key columns of `record`, or `None` if
+    /// the record is too short to hold them all. Unless nulls match, empty fields are
+    /// replaced by a marker that never equals a key from the other side.
+    fn join_key(
+        record: &[String],
+        indices: &[usize],
+        options: &JoinOptions,
+        side: Side,
+    ) -> Option<Vec<String>> {
+        indices
+            .iter()
+            .map(|&i| {
+                record.get(i).map(|value| match value.trim().is_empty() {
+                    true if !options.null_keys_match => format!("{}{}", NULL_KEY, side.name()),
+                    _ => options.normalize_key(value),
+                })
+            })
+            .collect()
+    }
+
+    /// Reads the next record of a join input and applies the ragged row policy,
+    /// skipping the rows it drops. Returns the row with its normalized key.
+    fn next_join_row<R: Read>(
+        &self,
+        reader: &mut Reader<R>,
+        side: Side,
+        plan: &JoinPlan,
+    ) -> Result<Option<KeyedRow>, Box<dyn Error>> {
+        let (indices, width) = plan.side(side);
+        let mut record = StringRecord::new();
+        while reader.read_record(&mut record)? {
+            let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            let line = record.position().map_or(0, |p| p.line());
+            if !self.dialect.fit_row(&mut row, width, line)? {
+                continue;
+            }
+            if let Some(key) = Self::join_key(&row, indices, plan.options, side) {
+                return Ok(Some((key, row)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses and stores the right input data into a map using the join key
+    fn build_right_key_map<R: Read>(
+        &self,
+        right_reader: &mut Reader<R>,
+        plan: &JoinPlan,
+    ) -> Result<KeyMap, Box<dyn Error>> {
+        let mut right_index_map: KeyMap = BTreeMap::new();
+        while let Some((key, row)) = self.next_join_row(right_reader, Side::Right, plan)? {
+            right_index_map.entry(key).or_default().push(row);
+        }
+        Ok(right_index_map)
+    }
+
+    /// Returns the header of a join's output: the left columns followed by the right
+    /// non-key columns, each prefixed with its side's alias if it has one. Names found
+    /// on both sides get the configured suffixes, and any name still repeated is made
+    /// unique as the dialect's duplicate policy says.
+    fn joined_headers(
+        &self,
+        right_headers: &[String],
+        right_indices: &[usize],
+        options: &JoinOptions,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let aliased = |alias: &Option<String>, name: &String| match alias {
+            Some(alias) => format!("{}.{}", alias, name),
+            None => name.clone(),
+        };
+        let left_headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|h| aliased(&options.left_alias, h))
+            .collect();
+        let right_headers: Vec<String> = Self::without_keys(right_headers, right_indices)
+            .map(|h| aliased(&options.right_alias, &h))
+            .collect();
+
+        let mut joined_headers: Vec<String> = left_headers
+            .iter()
+            .map(|h| match right_headers.contains(h) {
+                true => format!("{}{}", h, options.left_suffix),
+                false => h.clone(),
+            })
+            .collect();
+        joined_headers.extend(
+            right_headers
+                .iter()
+                .map(|h| match left_headers.contains(h) {
+                    true => format!("{}{}", h, options.right_suffix),
+                    false => h.clone(),
+                }),
+        );
+        if options.fuzzy.is_some() {
+            joined_headers.push("match_score".to_string());
+        }
+        self.dialect.unique_headers(joined_headers)
+    }
+
+    /// Positions of the selected columns in a join's output header, or `None` to write
+    /// them all.
+    fn projection(
+        joined_headers: &[String],
+        options: &JoinOptions,
+    ) -> Result<Option<Vec<usize>>, Box<dyn Error>> {
+        if options.select.is_empty() {
+            return Ok(None);
+        }
+        let projection = options
+            .select
+            .iter()
+            .filter(|&column| !options.skip_missing_select || joined_headers.contains(column))
+            .map(|column| {
+                joined_headers
+                    .iter()
+                    .position(|h| h == column)
+                    .ok_or_else(|| format!("Column '{}' not found in join output", column))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(projection))
+    }
+
+    /// Returns the header a join with a right side headed by `right_headers` writes,
+    /// without reading any rows.
+    pub fn join_output_headers<K: AsRef<str>>(
+        &self,
+        right_headers: &[String],
+        keys: &[(K, K)],
+        options: &JoinOptions,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let right_headers = self.dialect.unique_headers(right_headers.to_vec())?;
+        let right_indices = keys
+            .iter()
+            .map(|(_, right_key)| Self::extract_header_index(&right_headers, right_key.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let joined_headers = self.joined_headers(&right_headers, &right_indices, options)?;
+        Ok(match Self::projection(&joined_headers, options)? {
+            Some(projection) => projection
+                .iter()
+                .map(|&i| joined_headers[i].clone())
+                .collect(),
+            None => joined_headers,
+        })
+    }
+
+    /// Returns the fields of a right row that are not part of the join key.
+    fn without_keys<'a>(
+        row: &'a [String],
+        right_indices: &'a [usize],
+    ) -> impl Iterator<Item = String> + 'a {
+        row.iter()
+            .enumerate()
+            .filter(|(i, _)| !right_indices.contains(i))
+            .map(|(_, v)| v.clone())
+    }
+
+    /// Handles joining logic for each left record
+    fn join_left_record<W: Write>(
+        &self,
+        left_record: Vec<String>,
+        right_rows: Option<&[Vec<String>]>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(right_rows) = right_rows {
+            for right_row in right_rows {
+                let mut joined_row = left_record.clone();
+                joined_row.extend(Self::without_keys(right_row, &plan.right_indices));
+                self.write_joined_row(output, &joined_row, plan)?;
+            }
+        } else if matches!(
+            plan.join_type,
+            JoinType::Left | JoinType::Full | JoinType::Asof
+        ) {
+            let mut joined_row = left_record;
+            joined_row.extend(vec![
+                "".to_string();
+                plan.right_width - plan.right_indices.len()
+            ]);
+            self.write_joined_row(output, &joined_row, plan)?;
+        }
+        Ok(())
+    }
+
+    /// Writes right rows that no left row matched, for Right or Full join types
+    fn join_right_unmatched<W: Write>(
+        &self,
+        right_rows: &[Vec<String>],
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        for right_row in right_rows {
+            let joined_row = self.right_only_row(right_row, plan);
+            self.write_joined_row(output, &joined_row, plan)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the joined row for a right row no left row matched: empty left fields,
+    /// but for the keys when the options keep the right key values, followed by the
+    /// right non-key fields.
+    fn right_only_row(&self, right_row: &[String], plan: &JoinPlan) -> Vec<String> {
+        let mut joined_row = vec![String::new(); self.headers.len()];
+        if plan.options.keep_right_keys {
+            for (&left, &right) in plan.left_indices.iter().zip(&plan.right_indices) {
+                if let (Some(field), Some(value)) = (joined_row.get_mut(left), right_row.get(right))
+                {
+                    *field = value.clone();
+                }
+            }
+        }
+        joined_row.extend(Self::without_keys(right_row, &plan.right_indices));
+        joined_row
+    }
+
+    /// Writes a joined row, keeping only the selected columns.
+    fn write_joined_row<W: Write>(
+        &self,
+        output: &mut W,
+        joined_row: &[String],
+        plan: &JoinPlan,
+    ) -> Result<(), Box<dyn Error>> {
+        match &plan.projection {
+            Some(projection) => {
+                let row: Vec<&String> = projection.iter().map(|&i| &joined_row[i]).collect();
+                self.write_row(output, &row)
+            }
+            None => self.write_row(output, joined_row),
+        }
+    }
+
+    /// Streams the left rows against the map of right rows, then writes the right
+    /// rows no left row matched when the join type keeps them.
+    fn probe_right_key_map<R: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R>,
+        right_index_map: &KeyMap,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut processed_left_keys = HashSet::new();
+        while let Some((left_key_value, left_record)) =
+            self.next_join_row(left_reader, Side::Left, plan)?
+        {
+            self.join_left_record(
+                left_record,
+                right_index_map.get(&left_key_value).map(Vec::as_slice),
+                plan,
+                output,
+            )?;
+            processed_left_keys.insert(left_key_value);
+        }
+
+        if matches!(plan.join_type, JoinType::Right | JoinType::Full) {
+            for (right_key, right_rows) in right_index_map.iter() {
+                if !processed_left_keys.contains(right_key) {
+                    self.join_right_unmatched(right_rows, plan, output)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Matches each left row to the right rows with an equal key whose interval
+    /// contains the left row's range column. Right rows are indexed by interval, so a
+    /// lookup only visits intervals that start at or before the value.
+    fn range_join<R1: Read, R2: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R1>,
+        right_reader: &mut Reader<R2>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some((column, start, end)) = plan.range else {
+            return Err("Range join needs a range condition".into());
+        };
+        let mut right_rows = Vec::new();
+        let mut intervals: HashMap<Vec<String>, Vec<(String, String, usize)>> = HashMap::new();
+        while let Some((key, row)) = self.next_join_row(right_reader, Side::Right, plan)? {
+            // Open-ended intervals have no place in the index and match nothing.
+            if !row[start].trim().is_empty() && !row[end].trim().is_empty() {
+                let interval = (row[start].clone(), row[end].clone(), right_rows.len());
+                intervals.entry(key).or_default().push(interval);
+            }
+            right_rows.push(row);
+        }
+        let index: HashMap<Vec<String>, IntervalIndex> = intervals
+            .into_iter()
+            .map(|(key, intervals)| (key, IntervalIndex::new(intervals)))
+            .collect();
+
+        let mut matched = vec![false; right_rows.len()];
+        while let Some((key, left_record)) = self.next_join_row(left_reader, Side::Left, plan)? {
+            let value = &left_record[column];
+            let found = match index.get(&key) {
+                Some(intervals) if !value.trim().is_empty() => intervals.containing(value),
+                _ => Vec::new(),
+            };
+            if found.is_empty() {
+                self.join_left_record(left_record, None, plan, output)?;
+                continue;
+            }
+            for i in found {
+                matched[i] = true;
+                let mut joined_row = left_record.clone();
+                joined_row.extend(Self::without_keys(&right_rows[i], &plan.right_indices));
+                self.write_joined_row(output, &joined_row, plan)?;
+            }
+        }
+
+        if matches!(plan.join_type, JoinType::Right | JoinType::Full) {
+            let unmatched: Vec<Vec<String>> = right_rows
+                .into_iter()
+                .zip(matched)
+                .filter(|(_, matched)| !matched)
+                .map(|(row, _)| row)
+                .collect();
+            self.join_right_unmatched(&unmatched, plan, output)?;
+        }
+        Ok(())
+    }
+
+    /// Matches each left row to the right rows whose key is closest to its own, by the
+    /// measure of the fuzzy threshold and as long as it accepts them. Ties all match. Every output row ends
+    /// with the similarity of its match, left empty for unmatched rows.
+    fn fuzzy_join<R1: Read, R2: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R1>,
+        right_reader: &mut Reader<R2>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(threshold) = plan.options.fuzzy else {
+            return Err("Fuzzy join needs a distance or similarity threshold".into());
+        };
+        let right_index_map = self.build_right_key_map(right_reader, plan)?;
+        let right_only_width = plan.right_width - plan.right_indices.len();
+        // Left keys often repeat, and every lookup compares against all right keys.
+        let mut best_matches: HashMap<Vec<String>, Vec<(&Vec<String>, f64)>> = HashMap::new();
+        let mut matched_right_keys = HashSet::new();
+
+        while let Some((left_key, left_record)) =
+            self.next_join_row(left_reader, Side::Left, plan)?
+        {
+            let matches = best_matches.entry(left_key).or_insert_with_key(|left_key| {
+                let mut best = Vec::new();
+                let mut best_distance: Option<KeyDistance> = None;
+                if is_null_key(left_key) {
+                    return best;
+                }
+                for right_key in right_index_map.keys().filter(|key| !is_null_key(key)) {
+                    let key_distance = key_distance(left_key, right_key);
+                    if !threshold.accepts(&key_distance) {
+                        continue;
+                    }
+                    let rank = best_distance.as_ref().map_or(Ordering::Greater, |best| {
+                        threshold.rank(&key_distance, best)
+                    });
+                    match rank {
+                        Ordering::Less => continue,
+                        Ordering::Greater => {
+                            best.clear();
+                            best_distance = Some(key_distance);
+                        }
+                        Ordering::Equal => {}
+                    }
+                    best.push((right_key, key_distance.similarity));
+                }
+                best
+            });
+
+            if matches.is_empty() {
+                if matches!(plan.join_type, JoinType::Left | JoinType::Full) {
+                    let mut joined_row = left_record;
+                    joined_row.extend(vec![String::new(); right_only_width + 1]);
+                    self.write_joined_row(output, &joined_row, plan)?;
+                }
+                continue;
+            }
+            for &(right_key, similarity) in matches.iter() {
+                matched_right_keys.insert(right_key);
+                for right_row in &right_index_map[right_key] {
+                    let mut joined_row = left_record.clone();
+                    joined_row.extend(Self::without_keys(right_row, &plan.right_indices));
+                    joined_row.push(format!("{:.3}", similarity));
+                    self.write_joined_row(output, &joined_row, plan)?;
+                }
+            }
+        }
+
+        if matches!(plan.join_type, JoinType::Right | JoinType::Full) {
+            for (right_key, right_rows) in right_index_map.iter() {
+                if matched_right_keys.contains(right_key) {
+                    continue;
+                }
+                for right_row in right_rows {
+                    let mut joined_row = self.right_only_row(right_row, plan);
+                    joined_row.push(String::new());
+                    self.write_joined_row(output, &joined_row, plan)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Joins inputs too large to hold in memory. Both sides are split into
+    /// temporary partitions by the hash of their key, so matching rows always land
+    /// in the same partition, and each partition pair is then joined in memory.
+    /// Output rows are grouped by partition rather than kept in left input order.
+    fn spill_join<R1: Read, R2: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R1>,
+        right_reader: &mut Reader<R2>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let partitions = plan.options.partitions.max(1);
+        let right_parts = self.partition_rows(right_reader, Side::Right, plan, partitions)?;
+        let left_parts = self.partition_rows(left_reader, Side::Left, plan, partitions)?;
+
+        for (left_part, right_part) in left_parts.into_iter().zip(right_parts) {
+            let mut right_reader = self.row_reader(BufReader::new(right_part));
+            let right_index_map = self.build_right_key_map(&mut right_reader, plan)?;
+            let mut left_reader = self.row_reader(BufReader::new(left_part));
+            self.probe_right_key_map(&mut left_reader, &right_index_map, plan, output)?;
+        }
+        Ok(())
+    }
+
+    /// Writes every row of `reader` to one of `partitions` temporary files chosen by
+    /// the hash of its normalized key, and returns the files rewound for reading.
+    fn partition_rows<R: Read>(
+        &self,
+        reader: &mut Reader<R>,
+        side: Side,
+        plan: &JoinPlan,
+        partitions: usize,
+    ) -> Result<Vec<File>, Box<dyn Error>> {
+        let mut writers = (0..partitions)
+            .map(|_| Ok(BufWriter::new(tempfile::tempfile()?)))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+
+        while let Some((key, row)) = self.next_join_row(reader, side, plan)? {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            let partition = (hasher.finish() % partitions as u64) as usize;
+            self.write_row(&mut writers[partition], &row)?;
+        }
+
+        writers
+            .into_iter()
+            .map(|writer| {
+                let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+                file.seek(SeekFrom::Start(0))?;
+                Ok(file)
+            })
+            .collect()
+    }
+
+    /// Like [`DataFrame::next_join_row`], but fails when the key sorts before the
+    /// key of the previous row read from the same input.
+    fn next_sorted_join_row<R: Read>(
+        &self,
+        reader: &mut Reader<R>,
+        side: Side,
+        plan: &JoinPlan,
+        last_key: &mut Option<Vec<String>>,
+    ) -> Result<Option<KeyedRow>, Box<dyn Error>> {
+        let next = self.next_join_row(reader, side, plan)?;
+        if let Some((key, _)) = &next {
+            if let Some(last) = last_key.as_ref() {
+                if compare_join_keys(key, last) == Ordering::Less {
+                    return Err(format!(
+                        "The {} input is not sorted on the join key: '{}' follows '{}'",
+                        side.name(),
+                        display_key(key),
+                        display_key(last)
+                    )
+                    .into());
+                }
+            }
+            *last_key = Some(key.clone());
+        }
+        Ok(next)
+    }
+
+    /// Joins inputs that are both sorted ascending on the key in a single pass. Only
+    /// the current left row and the right rows sharing one key are held in memory.
+    fn merge_join<R1: Read, R2: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R1>,
+        right_reader: &mut Reader<R2>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let (mut last_left, mut last_right) = (None, None);
+        let mut next_left = |reader: &mut Reader<R1>| {
+            self.next_sorted_join_row(reader, Side::Left, plan, &mut last_left)
+        };
+        let mut next_right = |reader: &mut Reader<R2>| {
+            self.next_sorted_join_row(reader, Side::Right, plan, &mut last_right)
+        };
+
+        let mut left = next_left(left_reader)?;
+        let mut right = next_right(right_reader)?;
+        while let Some((group_key, first)) = right.take() {
+            let mut group = vec![first];
+            loop {
+                match next_right(right_reader)? {
+                    Some((key, row)) if key == group_key => group.push(row),
+                    next => {
+                        right = next;
+                        break;
+                    }
+                }
+            }
+
+            let mut matched = false;
+            while let Some((key, row)) = left.take() {
+                if key != group_key && compare_join_keys(&key, &group_key) == Ordering::Greater {
+                    left = Some((key, row));
+                    break;
+                }
+                let right_rows = (key == group_key).then_some(group.as_slice());
+                matched |= right_rows.is_some();
+                self.join_left_record(row, right_rows, plan, output)?;
+                left = next_left(left_reader)?;
+            }
+            if !matched && matches!(plan.join_type, JoinType::Right | JoinType::Full) {
+                self.join_right_unmatched(&group, plan, output)?;
+            }
+        }
+
+        while let Some((_, row)) = left.take() {
+            self.join_left_record(row, None, plan, output)?;
+            left = next_left(left_reader)?;
+        }
+        Ok(())
+    }
+
+    /// Matches each left row to the closest right row at or before it on the last key
+    /// column, among right rows whose other key columns are equal. Both inputs must be
+    /// sorted ascending on that last column. Left rows without such a right row, or
+    /// whose closest one lies beyond the tolerance, are kept with empty right columns.
+    fn asof_join<R1: Read, R2: Read, W: Write>(
+        &self,
+        left_reader: &mut Reader<R1>,
+        right_reader: &mut Reader<R2>,
+        plan: &JoinPlan,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(asof_index) = plan.right_indices.len().checked_sub(1) else {
+            return Err("An as-of join needs a key column to order rows by".into());
+        };
+        let (mut last_left, mut last_right) = (None, None);
+        // The latest right row seen for each combination of the other key columns.
+        let mut latest: HashMap<Vec<String>, (String, Vec<String>)> = HashMap::new();
+        let next_right = |reader: &mut Reader<R2>| self.next_join_row(reader, Side::Right, plan);
+
+        let mut right = next_right(right_reader)?;
+        while let Some((mut left_key, left_row)) =
+            self.next_join_row(left_reader, Side::Left, plan)?
+        {
+            // A row without a time has no place in the ordering and matches nothing.
+            if left_key
+                .last()
+                .is_some_and(|time| time.starts_with(NULL_KEY))
+            {
+                self.join_left_record(left_row, None, plan, output)?;
+                continue;
+            }
+            let left_time = left_key.pop().unwrap_or_default();
+            check_asof_order("left", &left_time, &mut last_left)?;
+
+            loop {
+                match right.take() {
+                    Some((mut right_key, right_row))
+                        if compare_asof(&right_key[asof_index], &left_time)
+                            != Ordering::Greater =>
+                    {
+                        let right_time = right_key.pop().unwrap_or_default();
+                        if right_time.starts_with(NULL_KEY) {
+                            right = next_right(right_reader)?;
+                            continue;
+                        }
+                        check_asof_order("right", &right_time, &mut last_right)?;
+                        latest.insert(right_key, (right_time, right_row));
+                        right = next_right(right_reader)?;
+                    }
+                    next => {
+                        right = next;
+                        break;
+                    }
+                }
+            }
+
+            let within_tolerance = |right_time: &String| match plan.options.tolerance {
+                None => true,
+                Some(tolerance) => {
+                    *right_time == left_time
+                        || matches!(
+                            (asof_value(&left_time), asof_value(right_time)),
+                            (Some(left), Some(right)) if left - right <= tolerance
+                        )
+                }
+            };
+            let right_rows = latest
+                .get(&left_key)
+                .filter(|(right_time, _)| within_tolerance(right_time))
+                .map(|(_, right_row)| std::slice::from_ref(right_row));
+            self.join_left_record(left_row, right_rows, plan, output)?;
+        }
+        Ok(())
+    }
+
+    /// Performs a join operation on two CSV streams. Both inputs start with their
+    /// header line. Rows match when every `(left, right)` column pair in `keys` holds
+    /// equal values.
+    pub fn join_stream<R1: BufRead, R2: BufRead, W: Write, K: AsRef<str>>(
+        &self,
+        left_input: &mut R1,
+        right_input: &mut R2, // Mutably borrow right_input
+        output: &mut W,
+        keys: &[(K, K)],
+        join_type: &JoinType,
+        options: &JoinOptions,
+    ) -> Result<(), Box<dyn Error>> {
+        let timer = Instant::now();
+        if keys.is_empty() && options.range.is_none() {
+            return Err("At least one join key or a range condition is required".into());
+        }
+
+        let left_indices = keys
+            .iter()
+            .map(|(left_key, _)| Self::extract_header_index(&self.headers, left_key.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut right_reader = self.row_reader(right_input);
+
+        let mut right_headers = StringRecord::new();
+        right_reader.read_record(&mut right_headers)?;
+        let right_headers = self
+            .dialect
+            .unique_headers(right_headers.iter().map(|s| s.to_string()).collect())?;
+        let right_indices = keys
+            .iter()
+            .map(|(_, right_key)| Self::extract_header_index(&right_headers, right_key.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let joined_headers = self.joined_headers(&right_headers, &right_indices, options)?;
+        let projection = Self::projection(&joined_headers, options)?;
+
+        let range = match &options.range {
+            Some(range) => Some((
+                Self::extract_header_index(&self.headers, &range.column)?,
+                Self::extract_header_index(&right_headers, &range.start)?,
+                Self::extract_header_index(&right_headers, &range.end)?,
+            )),
+            None => None,
+        };
+
+        let plan = JoinPlan {
+            left_indices,
+            left_width: self.headers.len(),
+            right_indices,
+            right_width: right_headers.len(),
+            join_type,
+            options,
+            projection,
+            range,
+        };
+        self.write_joined_row(output, &joined_headers, &plan)?;
+        let mut left_reader = self.row_reader(left_input);
+        left_reader.read_record(&mut StringRecord::new())?; // Skip the header line
+
+        match (join_type, options.strategy) {
+            (JoinType::Asof, _) => {
+                self.asof_join(&mut left_reader, &mut right_reader, &plan, output)?;
+            }
+            _ if plan.range.is_some() => {
+                self.range_join(&mut left_reader, &mut right_reader, &plan, output)?;
+            }
+            _ if options.fuzzy.is_some() => {
+                self.fuzzy_join(&mut left_reader, &mut right_reader, &plan, output)?;
+            }
+            (_, JoinStrategy::Hash) => {
+                let right_index_map = self.build_right_key_map(&mut right_reader, &plan)?;
+                self.probe_right_key_map(&mut left_reader, &right_index_map, &plan, output)?;
+            }
+            (_, JoinStrategy::Spill) => {
+                self.spill_join(&mut left_reader, &mut right_reader, &plan, output)?;
+            }
+            (_, JoinStrategy::Merge) => {
+                self.merge_join(&mut left_reader, &mut right_reader, &plan, output)?;
+            }
+        }
+
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! Converting between CSV rows and JSON objects keyed by header name.
+use super::{DataFrame, Dialect};
+use crate::cli::OutputFormat;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+
+/// Rewrites the CSV read from `input` as JSON, one object per row with the header
+/// names as keys in column order. `OutputFormat::Json` writes a single array and
+/// `OutputFormat::Jsonl` one object per line; values are always strings.
+pub fn write_json<R: Read, W: Write>(
+    input: R,
+    output: &mut W,
+    format: OutputFormat,
+    dialect: &Dialect,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = dialect.reader_builder().flexible(true).from_reader(input);
+    let headers = reader
+        .headers()?
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (open, separator, close) = match format {
+        OutputFormat::Json => ("[\n", ",\n", "\n]\n"),
+        OutputFormat::Jsonl => ("", "\n", "\n"),
+        OutputFormat::Csv
+        | OutputFormat::Html
+        | OutputFormat::Latex
+        | OutputFormat::Parquet
+        | OutputFormat::Arrow => return Err(format!("{:?} is not a JSON format", format).into()),
+    };
+    output.write_all(open.as_bytes())?;
+    let mut rows = 0;
+    for record in reader.records() {
+        let record = record?;
+        if rows > 0 {
+            output.write_all(separator.as_bytes())?;
+        }
+        output.write_all(b"{")?;
+        for (i, (key, value)) in headers.iter().zip(record.iter()).enumerate() {
+            if i > 0 {
+                output.write_all(b",")?;
+            }
+            write!(output, "{}:{}", key, serde_json::to_string(value)?)?;
+        }
+        output.write_all(b"}")?;
+        rows += 1;
+    }
+    match (format, rows) {
+        (OutputFormat::Json, 0) => output.write_all(b"]\n")?,
+        (_, 0) => {}
+        _ => output.write_all(close.as_bytes())?,
+    }
+    Ok(())
+}
+
+impl DataFrame {
+    /// Converts JSON Lines read from `input` to CSV, one column per top-level key in
+    /// order of first appearance. Missing keys and nulls become empty fields, and
+    /// nested arrays and objects are written as JSON text. Returns the number of rows.
+    pub fn jsonl_to_csv<R: BufRead + Seek, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut headers: Vec<String> = Vec::new();
+        let mut seen = HashSet::new();
+        for_each_object(input, |object| {
+            for key in object.keys() {
+                if seen.insert(key.clone()) {
+                    headers.push(key.clone());
+                }
+            }
+            Ok(())
+        })?;
+        self.write_row(output, &headers)?;
+
+        input.seek(SeekFrom::Start(0))?;
+        let mut rows = 0;
+        for_each_object(input, |object| {
+            let row: Vec<String> = headers
+                .iter()
+                .map(|key| match object.get(key) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect();
+            rows += 1;
+            self.write_row(output, &row)
+        })?;
+        Ok(rows)
+    }
+}
+
+/// Calls `f` with each object of a JSON Lines stream, skipping blank lines.
+fn for_each_object<R: BufRead>(
+    input: &mut R,
+    mut f: impl FnMut(Map<String, Value>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(Value::Object(object)) => f(object)?,
+            Ok(_) => return Err(format!("Line {} is not a JSON object", i + 1).into()),
+            Err(e) => return Err(format!("Invalid JSON on line {}: {}", i + 1, e).into()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Rendering CSV rows as a LaTeX `tabular` for reports.
+use super::Dialect;
+use std::error::Error;
+use std::io::{Read, Write};
+
+/// Rewrites the CSV read from `input` as a LaTeX `tabular` with `booktabs` rules, the
+/// header row set apart from the rest. Columns whose values are all numbers are aligned
+/// right, and characters special to LaTeX are escaped.
+pub fn write_latex<R: Read, W: Write>(
+    input: R,
+    output: &mut W,
+    dialect: &Dialect,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = dialect.reader_builder().flexible(true).from_reader(input);
+    let headers: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+    let rows = reader
+        .records()
+        .map(|record| Ok(record?.iter().map(String::from).collect()))
+        .collect::<Result<Vec<Vec<String>>, ::csv::Error>>()?;
+
+    let alignment: String = (0..headers.len())
+        .map(|i| {
+            let mut values = rows
+                .iter()
+                .filter_map(|row| row.get(i))
+                .filter(|value| !value.trim().is_empty())
+                .peekable();
+            match values.peek().is_some() && values.all(|v| v.trim().parse::<f64>().is_ok()) {
+                true => 'r',
+                false => 'l',
+            }
+        })
+        .collect();
+
+    writeln!(output, "\\begin{{tabular}}{{{}}}", alignment)?;
+    writeln!(output, "\\toprule")?;
+    write_line(output, &headers)?;
+    writeln!(output, "\\midrule")?;
+    for row in &rows {
+        write_line(output, row)?;
+    }
+    writeln!(output, "\\bottomrule")?;
+    writeln!(output, "\\end{{tabular}}")?;
+    Ok(())
+}
+
+/// Writes one row of the table, cells separated by `&`.
+fn write_line<W: Write>(output: &mut W, row: &[String]) -> Result<(), Box<dyn Error>> {
+    let cells: Vec<String> = row.iter().map(|value| escape(value)).collect();
+    writeln!(output, "{} \\\\", cells.join(" & "))?;
+    Ok(())
+}
+
+/// Escapes the characters that LaTeX treats specially, and folds line breaks into spaces.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '\r' => {}
+            '\n' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
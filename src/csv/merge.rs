@@ -0,0 +1,109 @@
+//! Merging several inputs that are already sorted on a key.
+use super::DataFrame;
+use crate::utils::print_info;
+use ::csv::StringRecord;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// Orders key values numerically when both are numbers, and as strings otherwise.
+pub(crate) fn compare_keys(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// The current row of one input, ordered so the smallest key pops first from a max-heap
+/// and ties go to the input listed first.
+struct Head {
+    key: String,
+    input: usize,
+    row: Vec<String>,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&other.key, &self.key).then_with(|| other.input.cmp(&self.input))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl DataFrame {
+    /// Merges inputs that are each sorted ascending on `key` into one sorted output in
+    /// a single pass, holding only the current row of every input in memory. All
+    /// inputs must share this frame's headers.
+    pub fn merge_sorted_stream<R: BufRead, W: Write>(
+        &self,
+        inputs: &mut [R],
+        output: &mut W,
+        key: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let index = Self::extract_header_index(&self.headers, key)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut readers: Vec<_> = inputs
+            .iter_mut()
+            .map(|input| self.row_reader(input))
+            .collect();
+        let readers_len = readers.len();
+        let mut heap = BinaryHeap::new();
+
+        let mut next_head = |input: usize| -> Result<Option<Head>, Box<dyn Error>> {
+            let mut record = StringRecord::new();
+            loop {
+                if !readers[input].read_record(&mut record)? {
+                    return Ok(None);
+                }
+                let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+                if self.fit_row(&mut row, &record)? {
+                    let key = row.get(index).cloned().unwrap_or_default();
+                    return Ok(Some(Head { key, input, row }));
+                }
+            }
+        };
+
+        for input in 0..readers_len {
+            if let Some(head) = next_head(input)? {
+                heap.push(head);
+            }
+        }
+        while let Some(head) = heap.pop() {
+            self.write_row(output, &head.row)?;
+
+            if let Some(next) = next_head(head.input)? {
+                if compare_keys(&next.key, &head.key) == Ordering::Less {
+                    return Err(format!(
+                        "Input {} is not sorted on '{}': '{}' follows '{}'",
+                        head.input + 1,
+                        key,
+                        next.key,
+                        head.key
+                    )
+                    .into());
+                }
+                heap.push(next);
+            }
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+}
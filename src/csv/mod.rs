@@ -1,24 +1,96 @@
-use prettytable::csv::{ReaderBuilder, Writer};
-use prettytable::{format, Table};
+use ::csv::{ByteRecord, Position, Reader, StringRecord};
+use prettytable::{format, Cell, Row, Table};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::error::Error;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::time::Instant;
 
-use crate::cli::JoinType;
+use crate::cli::OutputFormat;
+use crate::expr::Expr;
 use crate::utils::print_info;
 
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "avro")]
+mod avro;
+mod cast;
+mod clean;
+mod compress;
+mod dates;
+mod dedupe;
+mod dialect;
+mod fuzzy;
+mod html;
+mod join;
+mod json;
+mod latex;
+mod merge;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod reshape;
+mod reverse;
+#[cfg(feature = "s3")]
+mod s3;
+mod search;
+mod shuffle;
+mod split;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stats;
+mod types;
+
+#[cfg(feature = "arrow")]
+pub use arrow::write_arrow;
+pub use cast::{parse_cast_spec, CastFailure};
+pub use clean::{normalize_whitespace, snake_case, TextCase};
+pub use compress::Compression;
+pub use dates::reformat_date;
+pub use dialect::Dialect;
+pub use fuzzy::{levenshtein, FuzzyThreshold};
+pub use html::write_html;
+pub use join::{JoinOptions, RangeCondition};
+pub use json::write_json;
+pub use latex::write_latex;
+#[cfg(feature = "parquet")]
+pub use parquet::write_parquet;
+#[cfg(feature = "s3")]
+pub use s3::read_s3_object;
+pub use split::{sanitize_file_name, MAX_OPEN_PARTS};
+#[cfg(feature = "sqlite")]
+pub use sqlite::write_sqlite;
+use stats::StatsAccumulator;
+pub use stats::{ColumnStats, ValueFrequency};
+use types::TypeTracker;
+pub use types::{ColumnSchema, ColumnType};
+
 /// Represents a data frame with CSV data.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DataFrame {
     pub name: String,
     pub headers: Vec<String>,
     pub header_indices: HashMap<String, usize>,
     pub primary_key: Option<String>,
     pub foreign_keys: Vec<(String, String, String)>,
+    /// Columns of each foreign key constraint spanning several columns, in declaration
+    /// order. Foreign keys not listed here are single columns.
+    #[serde(default)]
+    pub composite_foreign_keys: Vec<Vec<String>>,
+    /// Inferred type of each header, empty until [`DataFrame::infer_types`] runs.
+    #[serde(default)]
+    pub column_types: Vec<ColumnType>,
+    /// Declared SQL type of each header, e.g. `VARCHAR(255)`, empty unless read from a
+    /// schema.
+    #[serde(default)]
+    pub sql_types: Vec<String>,
+    /// Number of rows in the table's file, unknown until `graph count-rows` runs.
+    #[serde(default)]
+    pub row_count: Option<u64>,
+    /// Quoting conventions used when reading and writing this frame's rows.
+    #[serde(skip)]
+    pub dialect: Dialect,
 }
 
 impl DataFrame {
@@ -30,16 +102,29 @@ impl DataFrame {
             header_indices: HashMap::new(),
             primary_key: None,
             foreign_keys: vec![],
+            composite_foreign_keys: Vec::new(),
+            column_types: Vec::new(),
+            sql_types: Vec::new(),
+            row_count: None,
+            dialect: Dialect::default(),
         }
     }
 
+    /// Sets the quoting conventions used to read and write rows.
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
     /// Reads CSV headers from a file.
     pub fn read_headers(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
         let file =
             File::open(path).map_err(|e| format!("Failed to open file '{:?}': {}", path, e))?;
-        let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
+        let mut reader = self.dialect.reader_builder().from_reader(file);
+        self.dialect.resolve_line_ending(path)?;
 
-        self.headers = reader.headers()?.iter().map(|s| s.to_string()).collect();
+        let headers = reader.headers()?.iter().map(|s| s.to_string()).collect();
+        self.headers = self.dialect.unique_headers(headers)?;
         self.header_indices = self
             .headers
             .iter()
@@ -51,12 +136,79 @@ impl DataFrame {
     }
 
     /// Writes CSV headers to a writer.
-    pub fn write_headers<W: Write>(&self, writer: W) -> Result<(), Box<dyn Error>> {
-        let mut csv_writer = Writer::from_writer(writer);
-        csv_writer.write_record(&self.headers)?;
+    pub fn write_headers<W: Write>(&self, mut writer: W) -> Result<(), Box<dyn Error>> {
+        self.write_row(&mut writer, &self.headers)
+    }
+
+    /// Writes one record, quoting the fields that contain a delimiter, a quote or a
+    /// line break as RFC 4180 requires, or every field if the dialect says so.
+    pub fn write_row<W: Write + ?Sized, S: AsRef<str>>(
+        &self,
+        output: &mut W,
+        row: &[S],
+    ) -> Result<(), Box<dyn Error>> {
+        // A lone empty field would otherwise read back as a blank line.
+        if let [field] = row {
+            if field.as_ref().is_empty() {
+                let quote = self.dialect.quote;
+                write!(
+                    output,
+                    "{}{}{}",
+                    quote,
+                    quote,
+                    self.dialect.line_terminator()
+                )?;
+                return Ok(());
+            }
+        }
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                output.write_all(b",")?;
+            }
+            self.dialect.write_field(output, field.as_ref())?;
+        }
+        output.write_all(self.dialect.line_terminator().as_bytes())?;
         Ok(())
     }
 
+    /// Opens a CSV file positioned just past its header record, which may contain
+    /// quoted line breaks.
+    pub fn open_rows(&self, path: &Path) -> Result<BufReader<File>, Box<dyn Error>> {
+        let mut reader = self.dialect.reader_builder().from_path(path)?;
+        reader.headers()?;
+        let data_start = reader.position().byte();
+
+        let mut file = reader.into_inner();
+        file.seek(SeekFrom::Start(data_start))?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Line number of a record read from a headerless stream, counting the header
+    /// that preceded it as line 1.
+    fn line_of(position: Option<&Position>) -> u64 {
+        position.map_or(0, |p| p.line() + 1)
+    }
+
+    /// Applies the ragged row policy to a row read from `record`, matching it against
+    /// this frame's headers. Returns `false` when the row should be skipped.
+    fn fit_row(
+        &self,
+        row: &mut Vec<String>,
+        record: &StringRecord,
+    ) -> Result<bool, Box<dyn Error>> {
+        let line = Self::line_of(record.position());
+        self.dialect.fit_row(row, self.headers.len(), line)
+    }
+
+    /// Returns a reader for headerless CSV rows.
+    fn row_reader<R: Read>(&self, input: R) -> Reader<R> {
+        self.dialect
+            .reader_builder()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(input)
+    }
+
     /// Processes CSV rows with a custom function.
     pub fn process_rows<F>(
         &self,
@@ -66,17 +218,125 @@ impl DataFrame {
     where
         F: FnMut(&[String]) -> Result<(), Box<dyn Error>>,
     {
-        let mut reader = ReaderBuilder::new().has_headers(false).from_reader(input);
+        let mut reader = self.row_reader(input);
 
         for result in reader.records() {
             let record = result?;
-            let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
-            processor(&row)?;
+            let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            if self.fit_row(&mut row, &record)? {
+                processor(&row)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Scans up to `limit` rows to infer each column's type and nullability,
+    /// storing the types on the frame.
+    pub fn infer_types<R: BufRead>(
+        &mut self,
+        input: &mut R,
+        limit: Option<usize>,
+    ) -> Result<Vec<ColumnSchema>, Box<dyn Error>> {
+        let mut trackers = vec![TypeTracker::default(); self.headers.len()];
+        let mut reader = self.row_reader(input);
+
+        for result in reader.records().take(limit.unwrap_or(usize::MAX)) {
+            let record = result?;
+            for (tracker, value) in trackers.iter_mut().zip(record.iter()) {
+                tracker.observe(value);
+            }
+        }
+
+        let schema: Vec<ColumnSchema> = self
+            .headers
+            .iter()
+            .zip(&trackers)
+            .map(|(name, tracker)| tracker.finish(name))
+            .collect();
+        self.column_types = schema.iter().map(|c| c.column_type).collect();
+        Ok(schema)
+    }
+
+    /// Computes per-column summary statistics in a single pass over the rows.
+    pub fn describe<R: BufRead>(&self, input: &mut R) -> Result<Vec<ColumnStats>, Box<dyn Error>> {
+        let mut accumulators = vec![StatsAccumulator::new(); self.headers.len()];
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            for (accumulator, value) in accumulators.iter_mut().zip(row) {
+                accumulator.observe(value);
+            }
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(self
+            .headers
+            .iter()
+            .zip(accumulators)
+            .map(|(name, accumulator)| accumulator.finish(name))
+            .collect())
+    }
+
+    /// Counts the rows in the input without decoding their fields.
+    pub fn count_rows<R: BufRead>(&self, input: &mut R) -> Result<usize, Box<dyn Error>> {
+        let mut reader = self.row_reader(input);
+        let mut record = ByteRecord::new();
+        let mut count = 0;
+        while reader.read_byte_record(&mut record)? {
+            let line = Self::line_of(record.position());
+            if self
+                .dialect
+                .keep_ragged(self.headers.len(), record.len(), line)?
+            {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Counts the rows for each distinct value of a column.
+    pub fn count_by_value<R: BufRead>(
+        &self,
+        input: &mut R,
+        column: &str,
+    ) -> Result<BTreeMap<String, usize>, Box<dyn Error>> {
+        let index = Self::extract_header_index(&self.headers, column)?;
+        let mut counts = BTreeMap::new();
+        self.process_rows(input, |row| {
+            let value = row.get(index).map_or("", |v| v.as_str());
+            *counts.entry(value.to_string()).or_insert(0) += 1;
+            Ok(())
+        })?;
+        Ok(counts)
+    }
+
+    /// Returns the most common values of a column, most frequent first.
+    pub fn value_frequencies<R: BufRead>(
+        &self,
+        input: &mut R,
+        column: &str,
+        top: Option<usize>,
+    ) -> Result<Vec<ValueFrequency>, Box<dyn Error>> {
+        let counts = self.count_by_value(input, column)?;
+        let total: usize = counts.values().sum();
+
+        let mut frequencies: Vec<ValueFrequency> = counts
+            .into_iter()
+            .map(|(value, count)| ValueFrequency {
+                value,
+                count,
+                percent: count as f64 * 100.0 / total as f64,
+            })
+            .collect();
+        // The map is already ordered by value, so a stable sort keeps ties alphabetical.
+        frequencies.sort_by_key(|f| std::cmp::Reverse(f.count));
+        frequencies.truncate(top.unwrap_or(usize::MAX));
+        Ok(frequencies)
+    }
+
     /// Concatenates CSV data.
     pub fn concat_stream<R: BufRead, W: Write>(
         &self,
@@ -85,7 +345,7 @@ impl DataFrame {
     ) -> Result<(), Box<dyn Error>> {
         let timer = Instant::now();
         self.process_rows(input, |row| {
-            writeln!(output, "{}", row.join(","))?;
+            self.write_row(output, row)?;
             Ok(())
         })?;
         let duration = timer.elapsed();
@@ -94,6 +354,54 @@ impl DataFrame {
         Ok(())
     }
 
+    /// Pastes the rows of `other` next to the rows of this frame, line by line.
+    /// Both inputs must have the same number of rows.
+    pub fn hstack_stream<R1: BufRead, R2: BufRead, W: Write>(
+        &self,
+        other: &DataFrame,
+        left: &mut R1,
+        right: &mut R2,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut headers = self.headers.clone();
+        headers.extend(other.headers.iter().cloned());
+        self.write_row(output, &headers)?;
+
+        let timer = Instant::now();
+        let mut left_reader = self.row_reader(left);
+        let mut right_reader = other.row_reader(right);
+        let mut left_rows = left_reader.records();
+        let mut right_rows = right_reader.records();
+        let mut line = 0;
+        loop {
+            line += 1;
+            let (left_record, right_record) = match (left_rows.next(), right_rows.next()) {
+                (None, None) => break,
+                (Some(left_record), Some(right_record)) => (left_record?, right_record?),
+                (Some(_), None) | (None, Some(_)) => {
+                    return Err(format!(
+                        "Row counts differ: '{}' and '{}' diverge at row {}",
+                        self.name, other.name, line
+                    )
+                    .into())
+                }
+            };
+            let mut row: Vec<String> = left_record.iter().map(|s| s.to_string()).collect();
+            let mut right_row: Vec<String> = right_record.iter().map(|s| s.to_string()).collect();
+            // Rows stay paired by position, so a row skipped on either side drops both.
+            if self.fit_row(&mut row, &left_record)?
+                & other.fit_row(&mut right_row, &right_record)?
+            {
+                row.extend(right_row);
+                self.write_row(output, &row)?;
+            }
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+
     /// Drops specified columns from CSV data.
     pub fn drop_stream<R: BufRead, W: Write>(
         &self,
@@ -114,12 +422,15 @@ impl DataFrame {
             .map(|&i| self.headers[i].clone())
             .collect();
 
-        writeln!(output, "{}", new_headers.join(","))?;
+        self.write_row(output, &new_headers)?;
 
         let timer = Instant::now();
         self.process_rows(input, |row| {
-            let new_row: Vec<String> = indices_to_keep.iter().map(|&i| row[i].clone()).collect();
-            writeln!(output, "{}", new_row.join(","))?;
+            let new_row: Vec<&str> = indices_to_keep
+                .iter()
+                .map(|&i| row.get(i).map_or("", |v| v.as_str()))
+                .collect();
+            self.write_row(output, &new_row)?;
             Ok(())
         })?;
         let duration = timer.elapsed();
@@ -150,199 +461,281 @@ impl DataFrame {
         Ok(())
     }
 
-    fn parse_csv_line(line: &str) -> Vec<String> {
-        line.split(',').map(|s| s.trim().to_string()).collect()
-    }
+    /// Moves `columns` to the front in the given order, followed by the remaining
+    /// columns in their original order.
+    pub fn reorder_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        columns: &[String],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut order = Vec::with_capacity(self.headers.len());
+        for column in columns {
+            let index = Self::extract_header_index(&self.headers, column)?;
+            if order.contains(&index) {
+                return Err(format!("Column '{}' listed more than once", column).into());
+            }
+            order.push(index);
+        }
+        let rest: Vec<usize> = (0..self.headers.len())
+            .filter(|i| !order.contains(i))
+            .collect();
+        order.extend(rest);
 
-    fn get_header_index(headers: &Vec<String>, key: &str) -> Result<usize, Box<dyn Error>> {
-        Ok(headers
-            .iter()
-            .position(|column| column == key)
-            .ok_or_else(|| format!("Column '{}' not found in left table", key))?)
-    }
+        let new_headers: Vec<&str> = order.iter().map(|&i| self.headers[i].as_str()).collect();
+        self.write_row(output, &new_headers)?;
 
-    /// Extracts the index of a key from the provided headers
-    fn extract_header_index(headers: &[String], key: &str) -> Result<usize, Box<dyn Error>> {
-        Self::get_header_index(&headers.to_vec(), key)
-    }
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let new_row: Vec<&str> = order
+                .iter()
+                .map(|&i| row.get(i).map_or("", |v| v.as_str()))
+                .collect();
+            self.write_row(output, &new_row)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
 
-    /// Parses and stores the right input data into a map using the join key
-    fn build_right_key_map<R: BufRead>(
-        right_input: &mut R,
-        right_index: usize,
-    ) -> Result<BTreeMap<String, Vec<Vec<String>>>, Box<dyn Error>> {
-        let right_reader = BufReader::new(right_input);
-        let mut right_index_map: BTreeMap<String, Vec<Vec<String>>> = BTreeMap::new();
-
-        for line in right_reader.lines() {
-            let record = Self::parse_csv_line(&line?);
-            if record.len() > right_index {
-                let key = record[right_index].to_string();
-                right_index_map.entry(key).or_default().push(record);
-            }
-        }
-        Ok(right_index_map)
+        Ok(())
     }
 
-    /// Writes the joined headers to the output
-    fn write_joined_headers<W: Write>(
+    /// Appends a column computed from an expression over each row.
+    pub fn derive_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
         output: &mut W,
-        left_headers: &[String],
-        right_headers: &[String],
-        right_key: &str,
+        column: &str,
+        expression: &Expr,
     ) -> Result<(), Box<dyn Error>> {
-        let mut joined_headers = left_headers.to_vec();
-        joined_headers.extend(right_headers.iter().filter(|&h| h != right_key).cloned());
-        writeln!(output, "{}", joined_headers.join(","))?;
+        if self.headers.iter().any(|h| h == column) {
+            return Err(format!("Column '{}' already exists", column).into());
+        }
+        let expression = expression.clone().bind(&self.headers)?;
+
+        let mut new_headers = self.headers.clone();
+        new_headers.push(column.to_string());
+        self.write_row(output, &new_headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let mut new_row = row.to_vec();
+            new_row.push(expression.evaluate(row)?.to_string());
+            self.write_row(output, &new_row)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
         Ok(())
     }
 
-    /// Handles joining logic for each left record
-    fn join_left_record<W: Write>(
-        left_record: Vec<String>,
-        right_rows: Option<&Vec<Vec<String>>>,
-        right_index: usize,
-        right_headers_len: usize,
+    /// Prepends a column numbering the rows from `start`.
+    pub fn index_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
         output: &mut W,
-        join_type: &JoinType,
+        column: &str,
+        start: i64,
     ) -> Result<(), Box<dyn Error>> {
-        if let Some(right_rows) = right_rows {
-            for right_row in right_rows {
-                let mut joined_row = left_record.clone();
-                joined_row.extend(
-                    right_row
-                        .iter()
-                        .enumerate()
-                        .filter(|&(i, _)| i != right_index)
-                        .map(|(_, v)| v.clone()),
-                );
-                writeln!(output, "{}", joined_row.join(","))?;
-            }
-        } else if matches!(join_type, JoinType::Left | JoinType::Full) {
-            let mut joined_row = left_record;
-            joined_row.extend(vec!["".to_string(); right_headers_len - 1]);
-            writeln!(output, "{}", joined_row.join(","))?;
+        if self.headers.iter().any(|h| h == column) {
+            return Err(format!("Column '{}' already exists", column).into());
         }
+        let mut new_headers = vec![column.to_string()];
+        new_headers.extend(self.headers.iter().cloned());
+        self.write_row(output, &new_headers)?;
+
+        let timer = Instant::now();
+        let mut next = start;
+        self.process_rows(input, |row| {
+            let mut new_row = vec![next.to_string()];
+            new_row.extend(row.iter().cloned());
+            self.write_row(output, &new_row)?;
+            next += 1;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
         Ok(())
     }
 
-    /// Handles join logic for the right side when using Right or Full join types
-    fn join_right_unmatched<W: Write>(
-        right_key: &str,
-        right_rows: &Vec<Vec<String>>,
-        processed_left_keys: &HashSet<String>,
-        right_index: usize,
-        left_headers_len: usize,
+    /// Keeps only the rows for which the predicate holds.
+    pub fn filter_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
         output: &mut W,
+        predicate: &Expr,
     ) -> Result<(), Box<dyn Error>> {
-        if !processed_left_keys.contains(right_key) {
-            for right_row in right_rows {
-                let mut joined_row = vec!["".to_string(); left_headers_len];
-                joined_row.extend(
-                    right_row
-                        .iter()
-                        .enumerate()
-                        .filter(|&(i, _)| i != right_index)
-                        .map(|(_, v)| v.clone()),
-                );
-                writeln!(output, "{}", joined_row.join(","))?;
+        let predicate = predicate.clone().bind(&self.headers)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            if predicate.matches(row)? {
+                self.write_row(output, row)?;
             }
-        }
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
         Ok(())
     }
 
-    /// Performs a join operation on two CSV streams.
-    pub fn join_stream<R1: BufRead, R2: BufRead, W: Write>(
+    /// Skips `offset` rows and writes up to `limit` of the rows after them,
+    /// stopping as soon as the limit is reached.
+    pub fn slice_stream<R: BufRead, W: Write>(
         &self,
-        left_input: &mut R1,
-        right_input: &mut R2, // Mutably borrow right_input
+        input: &mut R,
         output: &mut W,
-        left_key: &str,
-        right_key: &str,
-        join_type: &JoinType,
+        offset: usize,
+        limit: Option<usize>,
     ) -> Result<(), Box<dyn Error>> {
-        let timer = Instant::now();
-
-        let left_index = Self::extract_header_index(&self.headers, left_key)?;
-        let mut right_reader = BufReader::new(right_input);
+        self.write_row(output, &self.headers)?;
 
-        let mut right_headers_line = String::new();
-        right_reader.read_line(&mut right_headers_line)?;
-        let right_headers = Self::parse_csv_line(&right_headers_line);
-        let right_index = Self::extract_header_index(&right_headers, right_key)?;
-
-        Self::write_joined_headers(output, &self.headers, &right_headers, right_key)?;
-        let right_index_map = Self::build_right_key_map(&mut right_reader, right_index)?;
-
-        let mut left_reader = BufReader::new(left_input);
-        let mut left_headers_line = String::new();
-        left_reader.read_line(&mut left_headers_line)?; // Skip the header line
-        let mut processed_left_keys = HashSet::new();
-
-        for line in left_reader.lines() {
-            let left_record = Self::parse_csv_line(&line?);
-            if left_record.len() < left_index {
+        let timer = Instant::now();
+        let end = offset.saturating_add(limit.unwrap_or(usize::MAX));
+        let mut reader = self.row_reader(input);
+        let mut kept = 0;
+        for result in reader.records() {
+            if kept >= end {
+                break;
+            }
+            let record = result?;
+            let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+            if !self.fit_row(&mut row, &record)? {
                 continue;
             }
-            let left_key_value = left_record[left_index].to_string();
-            processed_left_keys.insert(left_key_value.clone());
-
-            Self::join_left_record(
-                left_record,
-                right_index_map.get(&left_key_value),
-                right_index,
-                right_headers.len(),
-                output,
-                join_type,
-            )?;
-        }
-
-        if matches!(join_type, JoinType::Right | JoinType::Full) {
-            for (right_key, right_rows) in right_index_map.iter() {
-                Self::join_right_unmatched(
-                    right_key,
-                    right_rows,
-                    &processed_left_keys,
-                    right_index,
-                    self.headers.len(),
-                    output,
-                )?;
+            if kept >= offset {
+                self.write_row(output, &row)?;
             }
+            kept += 1;
         }
-
         let duration = timer.elapsed();
         print_info(&format!("Operation took: {:.2?}\n", duration));
+
         Ok(())
     }
+
+    fn get_header_index(headers: &[String], key: &str) -> Result<usize, Box<dyn Error>> {
+        Ok(headers
+            .iter()
+            .position(|column| column == key)
+            .ok_or_else(|| format!("Column '{}' not found in left table", key))?)
+    }
+
+    /// Extracts the index of a key from the provided headers
+    fn extract_header_index(headers: &[String], key: &str) -> Result<usize, Box<dyn Error>> {
+        Self::get_header_index(headers, key)
+    }
 }
 
 /// Reads and prints CSV data with optional line count and reverse order.
-pub fn read_csv_stream(
+pub fn read_csv_stream<W: Write>(
     path: &Path,
     lines_count: Option<usize>,
     reverse: bool,
+    dialect: &Dialect,
+    output: &mut W,
 ) -> Result<(), Box<dyn Error>> {
+    read_csv_table(path, lines_count, reverse, dialect)?.print(output)?;
+    Ok(())
+}
+
+/// Reads the first `lines_count` rows of the CSV file at `path`, or the last ones in
+/// reverse order when `reverse` is set, into a table titled with the header.
+pub fn read_csv_table(
+    path: &Path,
+    lines_count: Option<usize>,
+    reverse: bool,
+    dialect: &Dialect,
+) -> Result<Table, Box<dyn Error>> {
     let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut lines: Vec<String> = reader.lines().collect::<Result<_, _>>()?;
+    let mut reader = dialect.reader_builder().flexible(true).from_reader(file);
 
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_BOX_CHARS);
-    table.set_titles(lines[0].split(',').into());
-
-    if reverse {
-        lines.reverse();
+    table.set_titles(Row::new(reader.headers()?.iter().map(Cell::new).collect()));
+
+    let count = lines_count.unwrap_or(usize::MAX);
+    let mut rows: VecDeque<StringRecord> = VecDeque::new();
+    for result in reader.records() {
+        let record = result?;
+        if reverse {
+            // Keep only the last `count` rows in memory.
+            if rows.len() == count {
+                rows.pop_front();
+            }
+        } else if rows.len() == count {
+            break;
+        }
+        rows.push_back(record);
     }
 
-    let count = lines_count.unwrap_or(lines.len());
-    for line in lines.into_iter().skip(1).take(count) {
-        table.add_row(line.split(',').into());
+    let rows: Box<dyn Iterator<Item = StringRecord>> = match reverse {
+        true => Box::new(rows.into_iter().rev()),
+        false => Box::new(rows.into_iter()),
+    };
+    for record in rows {
+        table.add_row(Row::new(record.iter().map(Cell::new).collect()));
     }
-    table.printstd();
+    Ok(table)
+}
 
+/// Rewrites the CSV file at `path` to `output` in the given format. `max_rows` caps
+/// the rows shown in HTML output.
+pub fn convert_csv<W: Write + Send>(
+    path: &Path,
+    output: &mut W,
+    format: OutputFormat,
+    dialect: &Dialect,
+    max_rows: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        OutputFormat::Csv => {
+            std::io::copy(&mut File::open(path)?, output)?;
+        }
+        OutputFormat::Json | OutputFormat::Jsonl => {
+            write_json(BufReader::new(File::open(path)?), output, format, dialect)?;
+        }
+        OutputFormat::Latex => {
+            write_latex(BufReader::new(File::open(path)?), output, dialect)?;
+        }
+        OutputFormat::Html => {
+            write_html(BufReader::new(File::open(path)?), output, dialect, max_rows)?;
+        }
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => {
+            write_parquet(path, output, dialect)?;
+        }
+        #[cfg(not(feature = "parquet"))]
+        OutputFormat::Parquet => return Err(PARQUET_DISABLED.into()),
+        #[cfg(feature = "arrow")]
+        OutputFormat::Arrow => {
+            write_arrow(path, output, dialect)?;
+        }
+        #[cfg(not(feature = "arrow"))]
+        OutputFormat::Arrow => return Err(ARROW_DISABLED.into()),
+    }
     Ok(())
 }
 
+/// Error reported when Arrow output is used without the `arrow` feature.
+pub const ARROW_DISABLED: &str = "Arrow support requires building csvg with --features arrow";
+
+/// Error reported when SQLite is used without the `sqlite` feature.
+pub const SQLITE_DISABLED: &str = "SQLite support requires building csvg with --features sqlite";
+
+/// Error reported when an S3 input is used without the `s3` feature.
+pub const S3_DISABLED: &str = "S3 support requires building csvg with --features s3";
+
+/// Error reported when Avro is used without the `avro` feature.
+pub const AVRO_DISABLED: &str = "Avro support requires building csvg with --features avro";
+
+/// Error reported when Parquet is used without the `parquet` feature.
+pub const PARQUET_DISABLED: &str = "Parquet support requires building csvg with --features parquet";
+
 pub fn human_readable_bytes(bytes: u64) -> String {
     let sizes = ["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
@@ -0,0 +1,60 @@
+//! Reading and writing Parquet files by converting them to and from CSV rows.
+use super::arrow::CsvBatches;
+use super::{DataFrame, Dialect};
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use ::parquet::arrow::ArrowWriter;
+use arrow_array::RecordBatchReader;
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+impl DataFrame {
+    /// Converts a Parquet file to CSV, one column per field, writing nulls as empty
+    /// fields. Returns the number of rows.
+    pub fn parquet_to_csv<W: Write>(
+        &self,
+        input: File,
+        output: &mut W,
+    ) -> Result<usize, Box<dyn Error>> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(input)?.build()?;
+        let schema = reader.schema();
+        let headers: Vec<&String> = schema.fields().iter().map(|f| f.name()).collect();
+        self.write_row(output, &headers)?;
+
+        let options = FormatOptions::default();
+        let mut rows = 0;
+        for batch in reader {
+            let batch = batch?;
+            let formatters = batch
+                .columns()
+                .iter()
+                .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            for row in 0..batch.num_rows() {
+                let fields: Vec<String> = formatters
+                    .iter()
+                    .map(|formatter| formatter.value(row).to_string())
+                    .collect();
+                self.write_row(output, &fields)?;
+            }
+            rows += batch.num_rows();
+        }
+        Ok(rows)
+    }
+}
+
+/// Writes the CSV file at `path` as Parquet, with the column types described in
+/// [`CsvBatches`]. Returns the number of rows.
+pub fn write_parquet<W: Write + Send>(
+    path: &Path,
+    output: W,
+    dialect: &Dialect,
+) -> Result<usize, Box<dyn Error>> {
+    let batches = CsvBatches::infer(path, dialect)?;
+    let mut writer = ArrowWriter::try_new(output, batches.schema.clone(), None)?;
+    let rows = batches.for_each(|batch| Ok(writer.write(batch)?))?;
+    writer.close()?;
+    Ok(rows)
+}
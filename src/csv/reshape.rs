@@ -0,0 +1,208 @@
+//! Operations that change the shape of a table between long and wide layouts.
+use super::DataFrame;
+use crate::cli::PivotAggregate;
+use crate::expr::Value;
+use crate::utils::print_info;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+/// Wide rows keyed by their index values, in the order they were first seen.
+#[derive(Default)]
+struct PivotState {
+    keys: Vec<String>,
+    key_positions: HashMap<String, usize>,
+    rows: Vec<(Vec<String>, Vec<Option<String>>)>,
+    row_positions: HashMap<Vec<String>, usize>,
+}
+
+impl PivotState {
+    fn key_position(&mut self, key: &str) -> usize {
+        if let Some(&position) = self.key_positions.get(key) {
+            return position;
+        }
+        self.keys.push(key.to_string());
+        self.key_positions
+            .insert(key.to_string(), self.keys.len() - 1);
+        self.keys.len() - 1
+    }
+
+    fn cell(&mut self, index: Vec<String>, key: &str) -> &mut Option<String> {
+        let column = self.key_position(key);
+        let row = match self.row_positions.get(&index) {
+            Some(&row) => row,
+            None => {
+                self.rows.push((index.clone(), Vec::new()));
+                self.row_positions.insert(index, self.rows.len() - 1);
+                self.rows.len() - 1
+            }
+        };
+        let cells = &mut self.rows[row].1;
+        if cells.len() <= column {
+            cells.resize(column + 1, None);
+        }
+        &mut cells[column]
+    }
+}
+
+/// Folds `value` into an existing pivot cell.
+fn aggregate(
+    cell: &mut Option<String>,
+    value: &str,
+    aggregate: &PivotAggregate,
+) -> Result<(), Box<dyn Error>> {
+    match aggregate {
+        PivotAggregate::First if cell.is_some() => {}
+        PivotAggregate::Sum if value.trim().is_empty() => {}
+        PivotAggregate::First | PivotAggregate::Last => *cell = Some(value.to_string()),
+        PivotAggregate::Count => {
+            let count = cell
+                .as_deref()
+                .map_or(0, |c| c.parse::<usize>().unwrap_or(0));
+            *cell = Some((count + 1).to_string());
+        }
+        PivotAggregate::Sum => {
+            let current = cell.as_deref().map_or(Ok(0.0), str::parse::<f64>)?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Cannot sum non-numeric value '{}'", value))?;
+            *cell = Some(Value::Number(current + value).to_string());
+        }
+    }
+    Ok(())
+}
+
+impl DataFrame {
+    /// Turns a long table into a wide one: one row per distinct `index` tuple and one
+    /// column per distinct value of `key`, holding the aggregated `value`.
+    pub fn pivot_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        index: &[String],
+        key: &str,
+        value: &str,
+        agg: &PivotAggregate,
+    ) -> Result<(), Box<dyn Error>> {
+        let index_positions = index
+            .iter()
+            .map(|column| Self::extract_header_index(&self.headers, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key_position = Self::extract_header_index(&self.headers, key)?;
+        let value_position = Self::extract_header_index(&self.headers, value)?;
+
+        let timer = Instant::now();
+        let mut state = PivotState::default();
+        self.process_rows(input, |row| {
+            let field = |i: usize| row.get(i).cloned().unwrap_or_default();
+            let index_values: Vec<String> = index_positions.iter().map(|&i| field(i)).collect();
+            let cell = state.cell(index_values, &field(key_position));
+            aggregate(cell, &field(value_position), agg)
+        })?;
+
+        let mut headers = index.to_vec();
+        headers.extend(state.keys.iter().cloned());
+        self.write_row(output, &headers)?;
+        for (index_values, cells) in state.rows {
+            let mut row = index_values;
+            row.extend((0..state.keys.len()).map(|i| {
+                cells
+                    .get(i)
+                    .and_then(|cell| cell.clone())
+                    .unwrap_or_default()
+            }));
+            self.write_row(output, &row)?;
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+
+    /// Turns a wide table into a long one, emitting one row per value column with the
+    /// `id_columns` repeated, followed by the column name and its value.
+    pub fn melt_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        id_columns: &[String],
+        value_columns: &[String],
+        var_name: &str,
+        value_name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let id_positions = id_columns
+            .iter()
+            .map(|column| Self::extract_header_index(&self.headers, column))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Without explicit value columns, every non-id column is melted.
+        let value_positions: Vec<usize> = if value_columns.is_empty() {
+            (0..self.headers.len())
+                .filter(|i| !id_positions.contains(i))
+                .collect()
+        } else {
+            value_columns
+                .iter()
+                .map(|column| Self::extract_header_index(&self.headers, column))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut headers = id_columns.to_vec();
+        headers.push(var_name.to_string());
+        headers.push(value_name.to_string());
+        self.write_row(output, &headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let field = |i: usize| row.get(i).map_or("", |v| v.as_str());
+            let ids: Vec<&str> = id_positions.iter().map(|&i| field(i)).collect();
+            for &position in &value_positions {
+                let mut melted = ids.clone();
+                melted.push(&self.headers[position]);
+                melted.push(field(position));
+                self.write_row(output, &melted)?;
+            }
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+
+    /// Emits one row per `separator`-delimited value of `column`, repeating the other
+    /// columns. Values are trimmed, and a row with an empty cell is kept as is.
+    pub fn explode_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        column: &str,
+        separator: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if separator.is_empty() {
+            return Err("Separator must not be empty".into());
+        }
+        let index = Self::extract_header_index(&self.headers, column)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        self.process_rows(input, |row| {
+            let cell = row.get(index).map_or("", |v| v.as_str());
+            if cell.trim().is_empty() {
+                self.write_row(output, row)?;
+                return Ok(());
+            }
+            let mut exploded = row.to_vec();
+            for value in cell.split(separator).map(str::trim) {
+                exploded[index] = value.to_string();
+                self.write_row(output, &exploded)?;
+            }
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//! Reading CSV rows from the end of a file backwards.
+use super::DataFrame;
+use crate::utils::print_info;
+use ::csv::{ByteRecord, Position, StringRecord};
+use std::error::Error;
+use std::io::{Read, Seek, Write};
+use std::time::Instant;
+
+impl DataFrame {
+    /// Writes the rows of `input` in reverse order. A first pass records where each
+    /// record starts, then the records are read back last to first by seeking to
+    /// them, so only their offsets and the current row are held in memory. Rows are
+    /// taken from `input`'s current position, which should be just past the header.
+    /// Ragged rows are checked during the first pass, so an error stops the command
+    /// before any data row is written.
+    pub fn reverse_stream<R: Read + Seek, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<(), Box<dyn Error>> {
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let start = input.stream_position()?;
+        let mut reader = self.row_reader(&mut *input);
+        let mut positions: Vec<Position> = Vec::new();
+        let mut record = ByteRecord::new();
+        while reader.read_byte_record(&mut record)? {
+            let line = Self::line_of(record.position());
+            if !self
+                .dialect
+                .keep_ragged(self.headers.len(), record.len(), line)?
+            {
+                continue;
+            }
+            if let Some(position) = record.position() {
+                let mut position = position.clone();
+                position.set_byte(start + position.byte());
+                positions.push(position);
+            }
+        }
+
+        let mut record = StringRecord::new();
+        for position in positions.iter().rev() {
+            reader.seek(position.clone())?;
+            if reader.read_record(&mut record)? {
+                let mut row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
+                if !self.headers.is_empty() {
+                    row.resize(self.headers.len(), String::new());
+                }
+                self.write_row(output, &row)?;
+            }
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+}
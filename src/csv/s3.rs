@@ -0,0 +1,43 @@
+//! Reading objects from S3, or an S3-compatible store, given `s3://bucket/key` URLs.
+use ::s3::creds::Credentials;
+use ::s3::{Bucket, Region};
+use std::env;
+use std::error::Error;
+use std::io::Write;
+
+/// Writes the object named by an `s3://bucket/key` URL to `output`. Credentials come
+/// from the usual AWS environment variables or profile, the region from `AWS_REGION`
+/// (or `AWS_DEFAULT_REGION`, falling back to `us-east-1`), and `AWS_ENDPOINT_URL`
+/// points at an S3-compatible store instead of AWS.
+pub fn read_s3_object<W: Write + Send>(url: &str, output: &mut W) -> Result<(), Box<dyn Error>> {
+    let (bucket_name, key) = split_s3_url(url)?;
+    let credentials =
+        Credentials::default().map_err(|e| format!("Failed to load AWS credentials: {}", e))?;
+    let region_name = env::var("AWS_REGION")
+        .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+        .unwrap_or_else(|_| "us-east-1".to_string());
+    let bucket = match env::var("AWS_ENDPOINT_URL") {
+        Ok(endpoint) => Bucket::new(
+            bucket_name,
+            Region::Custom {
+                region: region_name,
+                endpoint,
+            },
+            credentials,
+        )?
+        .with_path_style(),
+        Err(_) => Bucket::new(bucket_name, region_name.parse()?, credentials)?,
+    };
+    bucket
+        .get_object_to_writer(key, output)
+        .map_err(|e| format!("Failed to read '{}': {}", url, e))?;
+    Ok(())
+}
+
+/// Splits an `s3://bucket/key` URL into the bucket name and key.
+fn split_s3_url(url: &str) -> Result<(&str, &str), Box<dyn Error>> {
+    url.strip_prefix("s3://")
+        .and_then(|rest| rest.split_once('/'))
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| format!("Invalid S3 URL '{}', expected s3://bucket/key", url).into())
+}
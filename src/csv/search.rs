@@ -0,0 +1,102 @@
+//! Regex search and substitution over CSV fields.
+use super::DataFrame;
+use crate::utils::print_info;
+use regex::Regex;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Instant;
+
+impl DataFrame {
+    /// Returns a predicate testing `pattern` against one column, or any column when
+    /// `column` is `None`.
+    fn row_matcher<'a>(
+        &self,
+        pattern: &'a Regex,
+        column: Option<&str>,
+    ) -> Result<impl Fn(&[String]) -> bool + 'a, Box<dyn Error>> {
+        let index = column
+            .map(|column| Self::extract_header_index(&self.headers, column))
+            .transpose()?;
+        Ok(move |row: &[String]| match index {
+            Some(i) => row.get(i).is_some_and(|value| pattern.is_match(value)),
+            None => row.iter().any(|value| pattern.is_match(value)),
+        })
+    }
+
+    /// Writes the rows in which `pattern` matches, returning how many matched.
+    pub fn grep_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        pattern: &Regex,
+        column: Option<&str>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let matches = self.row_matcher(pattern, column)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut count = 0;
+        self.process_rows(input, |row| {
+            if matches(row) {
+                count += 1;
+                self.write_row(output, row)?;
+            }
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(count)
+    }
+
+    /// Counts the rows in which `pattern` matches.
+    pub fn count_matches<R: BufRead>(
+        &self,
+        input: &mut R,
+        pattern: &Regex,
+        column: Option<&str>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let matches = self.row_matcher(pattern, column)?;
+        let mut count = 0;
+        self.process_rows(input, |row| {
+            if matches(row) {
+                count += 1;
+            }
+            Ok(())
+        })?;
+        Ok(count)
+    }
+
+    /// Applies a regex substitution to every value of `column`. Replacements may refer
+    /// to capture groups (`$1`, `${name}`), and fields are re-quoted as needed so values
+    /// containing commas or quotes keep the CSV structure intact.
+    pub fn replace_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        column: &str,
+        pattern: &Regex,
+        replacement: &str,
+    ) -> Result<usize, Box<dyn Error>> {
+        let index = Self::extract_header_index(&self.headers, column)?;
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut replaced = 0;
+        self.process_rows(input, |row| {
+            let mut row = row.to_vec();
+            if let Some(value) = row.get_mut(index) {
+                if pattern.is_match(value) {
+                    *value = pattern.replace_all(value, replacement).into_owned();
+                    replaced += 1;
+                }
+            }
+            self.write_row(output, &row)?;
+            Ok(())
+        })?;
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(replaced)
+    }
+}
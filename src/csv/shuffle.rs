@@ -0,0 +1,69 @@
+//! Random reordering of CSV rows.
+use super::DataFrame;
+use crate::utils::print_info;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// SplitMix64, a small generator whose output for a given seed never changes, so
+/// seeded shuffles stay reproducible across releases.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..bound` without modulo bias.
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+impl DataFrame {
+    /// Writes the rows in a random order. The same `seed` always produces the same
+    /// order; without one the order differs between runs. All rows are held in memory.
+    pub fn shuffle_stream<R: BufRead, W: Write>(
+        &self,
+        input: &mut R,
+        output: &mut W,
+        seed: Option<u64>,
+    ) -> Result<(), Box<dyn Error>> {
+        let seed = match seed {
+            Some(seed) => seed,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64,
+        };
+        self.write_row(output, &self.headers)?;
+
+        let timer = Instant::now();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        self.process_rows(input, |row| {
+            rows.push(row.to_vec());
+            Ok(())
+        })?;
+
+        // Fisher-Yates
+        let mut rng = SplitMix64(seed);
+        for i in (1..rows.len()).rev() {
+            let j = rng.below(i as u64 + 1) as usize;
+            rows.swap(i, j);
+        }
+        for row in rows {
+            self.write_row(output, &row)?;
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+//! Splitting one CSV stream into several files.
+use super::DataFrame;
+use crate::utils::print_info;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Most files `split_by_column` keeps open at once, well below common limits on open
+/// files.
+pub const MAX_OPEN_PARTS: usize = 256;
+
+/// Turns a column value into a string that is safe to use in a file name.
+pub fn sanitize_file_name(value: &str) -> String {
+    let sanitized: String = value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if sanitized.is_empty() {
+        "empty".to_string()
+    } else {
+        sanitized
+    }
+}
+
+impl DataFrame {
+    /// Creates `dir/prefix_suffix.csv` and writes the header line to it.
+    fn create_part(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        suffix: &str,
+    ) -> Result<(PathBuf, BufWriter<File>), Box<dyn Error>> {
+        let path = dir.join(format!("{}_{}.csv", prefix, suffix));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        self.write_row(&mut writer, &self.headers)?;
+        Ok((path, writer))
+    }
+
+    /// Writes consecutive chunks of `rows` rows to numbered files in `dir`.
+    pub fn split_by_rows<R: BufRead>(
+        &self,
+        input: &mut R,
+        dir: &Path,
+        prefix: &str,
+        rows: usize,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        if rows == 0 {
+            return Err("Rows per file must be greater than zero".into());
+        }
+        fs::create_dir_all(dir)?;
+
+        let timer = Instant::now();
+        let mut paths = Vec::new();
+        let mut current: Option<BufWriter<File>> = None;
+        let mut written = 0;
+        self.process_rows(input, |row| {
+            if written % rows == 0 {
+                let (path, writer) =
+                    self.create_part(dir, prefix, &(paths.len() + 1).to_string())?;
+                paths.push(path);
+                current = Some(writer);
+            }
+            if let Some(writer) = current.as_mut() {
+                self.write_row(writer, row)?;
+            }
+            written += 1;
+            Ok(())
+        })?;
+        if let Some(mut writer) = current {
+            writer.flush()?;
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(paths)
+    }
+
+    /// Writes the rows for each distinct value of `column` to its own file in `dir`. At
+    /// most [`MAX_OPEN_PARTS`] files stay open: the least recently written one is closed
+    /// and reopened for appending when its value comes back. If the split fails, the
+    /// files it created are removed.
+    pub fn split_by_column<R: BufRead>(
+        &self,
+        input: &mut R,
+        dir: &Path,
+        prefix: &str,
+        column: &str,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let index = Self::extract_header_index(&self.headers, column)?;
+        fs::create_dir_all(dir)?;
+
+        let timer = Instant::now();
+        let mut paths = Vec::new();
+        let mut parts: HashMap<String, Part> = HashMap::new();
+        let mut used_names: HashSet<String> = HashSet::new();
+        let mut open = 0;
+        let mut rows_written = 0;
+        let split = self.process_rows(input, |row| {
+            let value = row.get(index).map_or("", |v| v.as_str());
+            rows_written += 1;
+            let is_open = parts.get(value).is_some_and(|part| part.writer.is_some());
+            if !is_open && open == MAX_OPEN_PARTS {
+                let oldest = parts
+                    .values_mut()
+                    .filter(|part| part.writer.is_some())
+                    .min_by_key(|part| part.last_used);
+                if let Some(mut writer) = oldest.and_then(|part| part.writer.take()) {
+                    writer.flush()?;
+                    open -= 1;
+                }
+            }
+            match parts.get_mut(value) {
+                Some(part) if part.writer.is_none() => {
+                    let file = OpenOptions::new().append(true).open(&part.path)?;
+                    part.writer = Some(BufWriter::new(file));
+                    open += 1;
+                }
+                Some(_) => {}
+                None => {
+                    // Distinct values can sanitize to the same name, so number the repeats
+                    // until the name is one no other value took.
+                    let name = sanitize_file_name(value);
+                    let suffix = match used_names.contains(&name) {
+                        false => name,
+                        true => (2..)
+                            .map(|n| format!("{}_{}", name, n))
+                            .find(|candidate| !used_names.contains(candidate))
+                            .unwrap_or_default(),
+                    };
+                    used_names.insert(suffix.clone());
+                    let (path, writer) = self.create_part(dir, prefix, &suffix)?;
+                    paths.push(path.clone());
+                    parts.insert(
+                        value.to_string(),
+                        Part {
+                            path,
+                            writer: Some(writer),
+                            last_used: 0,
+                        },
+                    );
+                    open += 1;
+                }
+            }
+            let part = parts.get_mut(value).unwrap();
+            part.last_used = rows_written;
+            self.write_row(part.writer.as_mut().unwrap(), row)?;
+            Ok(())
+        });
+        let flushed = split.and_then(|()| {
+            parts
+                .values_mut()
+                .filter_map(|part| part.writer.as_mut())
+                .try_for_each(|writer| writer.flush())
+                .map_err(Into::into)
+        });
+        if let Err(e) = flushed {
+            drop(parts);
+            for path in &paths {
+                let _ = fs::remove_file(path);
+            }
+            return Err(e);
+        }
+        let duration = timer.elapsed();
+        print_info(&format!("Operation took: {:.2?}\n", duration));
+
+        Ok(paths)
+    }
+}
+
+/// A file `split_by_column` writes the rows of one value to, open or closed.
+struct Part {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    /// Rows read when one was last written to the file.
+    last_used: usize,
+}
@@ -0,0 +1,127 @@
+//! Importing CSV files into SQLite tables and reading tables back as CSV rows.
+use super::{ColumnType, DataFrame, Dialect};
+use crate::sql::quote_identifier;
+use rusqlite::types::ValueRef;
+use rusqlite::{params_from_iter, Connection, OpenFlags};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+impl DataFrame {
+    /// Writes the rows of `table` in the SQLite database at `db` as CSV, one column per
+    /// table column. Nulls become empty fields. Returns the number of rows.
+    pub fn sqlite_to_csv<W: Write>(
+        &self,
+        db: &Path,
+        table: &str,
+        output: &mut W,
+    ) -> Result<usize, Box<dyn Error>> {
+        let connection = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("Failed to open database '{:?}': {}", db, e))?;
+        let mut statement =
+            connection.prepare(&format!("SELECT * FROM {}", quote_identifier(table)))?;
+        let headers: Vec<String> = statement
+            .column_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        self.write_row(output, &headers)?;
+
+        let mut rows = statement.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let fields = (0..headers.len())
+                .map(|i| {
+                    Ok(match row.get_ref(i)? {
+                        ValueRef::Null => String::new(),
+                        ValueRef::Integer(n) => n.to_string(),
+                        ValueRef::Real(f) => f.to_string(),
+                        ValueRef::Text(text) | ValueRef::Blob(text) => {
+                            String::from_utf8_lossy(text).into_owned()
+                        }
+                    })
+                })
+                .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+            self.write_row(output, &fields)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Creates `table` in the SQLite database at `db`, creating the database if needed,
+/// and fills it with the rows of the CSV file at `path` in one transaction. Int and
+/// float columns become INTEGER and REAL, booleans are stored as 0 and 1, other columns
+/// as TEXT, and empty fields as NULL. An existing table is an error unless `replace`
+/// is set. Returns the number of rows.
+pub fn write_sqlite(
+    path: &Path,
+    db: &Path,
+    table: &str,
+    dialect: &Dialect,
+    replace: bool,
+) -> Result<usize, Box<dyn Error>> {
+    let mut df = DataFrame::new(table.to_string()).with_dialect(dialect.clone());
+    df.read_headers(path)?;
+    let mut input = df.open_rows(path)?;
+    let columns = df.infer_types(&mut input, None)?;
+
+    let mut connection =
+        Connection::open(db).map_err(|e| format!("Failed to open database '{:?}': {}", db, e))?;
+    let transaction = connection.transaction()?;
+    if replace {
+        transaction.execute(
+            &format!("DROP TABLE IF EXISTS {}", quote_identifier(table)),
+            [],
+        )?;
+    }
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let sql_type = match column.column_type {
+                ColumnType::Int | ColumnType::Bool => "INTEGER",
+                ColumnType::Float => "REAL",
+                ColumnType::Date | ColumnType::String => "TEXT",
+            };
+            format!("{} {}", quote_identifier(&column.name), sql_type)
+        })
+        .collect();
+    transaction
+        .execute(
+            &format!(
+                "CREATE TABLE {} ({})",
+                quote_identifier(table),
+                definitions.join(", ")
+            ),
+            [],
+        )
+        .map_err(|e| format!("Failed to create table '{}': {}", table, e))?;
+
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut rows = 0;
+    {
+        let mut insert = transaction.prepare(&format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_identifier(table),
+            placeholders
+        ))?;
+        df.process_rows(&mut df.open_rows(path)?, |row| {
+            let values = columns.iter().zip(row).map(|(column, value)| {
+                match (value.trim().is_empty(), column.column_type) {
+                    (true, _) => None,
+                    (false, ColumnType::Bool) => column
+                        .column_type
+                        .cast(value)
+                        .map(|b| if b == "true" { "1" } else { "0" }.to_string()),
+                    (false, ColumnType::Int | ColumnType::Float) => column.column_type.cast(value),
+                    (false, _) => Some(value.clone()),
+                }
+            });
+            insert.execute(params_from_iter(values))?;
+            rows += 1;
+            Ok(())
+        })?;
+    }
+    transaction.commit()?;
+    Ok(rows)
+}
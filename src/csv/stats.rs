@@ -0,0 +1,106 @@
+//! Streaming summary statistics over CSV columns.
+use std::collections::HashSet;
+
+/// Summary statistics for one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    pub name: String,
+    /// Number of non-empty values.
+    pub count: usize,
+    pub null_count: usize,
+    /// Smallest value, compared numerically when every value is a number.
+    pub min: Option<String>,
+    /// Largest value, compared numerically when every value is a number.
+    pub max: Option<String>,
+    /// Mean of the values, present only when every value is a number.
+    pub mean: Option<f64>,
+    pub distinct: usize,
+}
+
+/// How often a value occurs in a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueFrequency {
+    pub value: String,
+    pub count: usize,
+    /// Share of all rows, from 0 to 100.
+    pub percent: f64,
+}
+
+/// Accumulates statistics for a column one value at a time.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StatsAccumulator {
+    count: usize,
+    null_count: usize,
+    numeric: bool,
+    sum: f64,
+    min_number: Option<(f64, String)>,
+    max_number: Option<(f64, String)>,
+    min_text: Option<String>,
+    max_text: Option<String>,
+    distinct: HashSet<String>,
+}
+
+impl StatsAccumulator {
+    pub(crate) fn new() -> Self {
+        Self {
+            numeric: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn observe(&mut self, value: &str) {
+        if value.trim().is_empty() {
+            self.null_count += 1;
+            return;
+        }
+        self.count += 1;
+
+        if self.numeric {
+            match value.trim().parse::<f64>() {
+                Ok(n) => {
+                    self.sum += n;
+                    if self.min_number.as_ref().is_none_or(|(min, _)| n < *min) {
+                        self.min_number = Some((n, value.to_string()));
+                    }
+                    if self.max_number.as_ref().is_none_or(|(max, _)| n > *max) {
+                        self.max_number = Some((n, value.to_string()));
+                    }
+                }
+                Err(_) => self.numeric = false,
+            }
+        }
+
+        if self.min_text.as_deref().is_none_or(|min| value < min) {
+            self.min_text = Some(value.to_string());
+        }
+        if self.max_text.as_deref().is_none_or(|max| value > max) {
+            self.max_text = Some(value.to_string());
+        }
+        if !self.distinct.contains(value) {
+            self.distinct.insert(value.to_string());
+        }
+    }
+
+    pub(crate) fn finish(self, name: &str) -> ColumnStats {
+        let numeric = self.numeric && self.count > 0;
+        let (min, max, mean) = if numeric {
+            (
+                self.min_number.map(|(_, text)| text),
+                self.max_number.map(|(_, text)| text),
+                Some(self.sum / self.count as f64),
+            )
+        } else {
+            (self.min_text, self.max_text, None)
+        };
+
+        ColumnStats {
+            name: name.to_string(),
+            count: self.count,
+            null_count: self.null_count,
+            min,
+            max,
+            mean,
+            distinct: self.distinct.len(),
+        }
+    }
+}
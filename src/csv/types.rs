@@ -0,0 +1,176 @@
+//! Column type inference for CSV data.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The inferred type of a CSV column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    Int,
+    Float,
+    Date,
+    Bool,
+    String,
+}
+
+impl ColumnType {
+    /// Classifies a single non-empty field.
+    pub fn of_value(value: &str) -> Self {
+        let value = value.trim();
+        if value.parse::<i64>().is_ok() {
+            ColumnType::Int
+        } else if value.parse::<f64>().is_ok() {
+            ColumnType::Float
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            ColumnType::Bool
+        } else if is_date(value) {
+            ColumnType::Date
+        } else {
+            ColumnType::String
+        }
+    }
+
+    /// Returns the narrowest type able to hold values of both types.
+    pub fn merge(self, other: ColumnType) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => {
+                ColumnType::Float
+            }
+            _ => ColumnType::String,
+        }
+    }
+
+    /// Converts a non-empty field to this type's canonical form, or returns `None` when
+    /// the value cannot be represented as this type.
+    pub fn cast(self, value: &str) -> Option<String> {
+        let value = value.trim();
+        match self {
+            ColumnType::Int => match value.parse::<i64>() {
+                Ok(n) => Some(n.to_string()),
+                // Accept integral floats such as `3.0` from spreadsheet exports.
+                Err(_) => value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                    .map(|f| (f as i64).to_string()),
+            },
+            ColumnType::Float => {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|f| f.is_finite())
+                    .map(|f| match f.fract() == 0.0 {
+                        true => format!("{:.1}", f),
+                        false => f.to_string(),
+                    })
+            }
+            ColumnType::Bool => match value.to_ascii_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "1" => Some("true".to_string()),
+                "false" | "f" | "no" | "n" | "0" => Some("false".to_string()),
+                _ => None,
+            },
+            ColumnType::Date => is_date(value).then(|| value.to_string()),
+            ColumnType::String => Some(value.to_string()),
+        }
+    }
+}
+
+impl FromStr for ColumnType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "int" | "integer" => Ok(ColumnType::Int),
+            "float" | "number" => Ok(ColumnType::Float),
+            "date" => Ok(ColumnType::Date),
+            "bool" | "boolean" => Ok(ColumnType::Bool),
+            "string" | "str" | "text" => Ok(ColumnType::String),
+            other => Err(format!(
+                "Unknown type '{}', expected int, float, date, bool or string",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Date => "date",
+            ColumnType::Bool => "bool",
+            ColumnType::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Inferred type and nullability of one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+/// Accumulates the type of a column across the values seen so far.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TypeTracker {
+    column_type: Option<ColumnType>,
+    nullable: bool,
+}
+
+impl TypeTracker {
+    pub(crate) fn observe(&mut self, value: &str) {
+        if value.trim().is_empty() {
+            self.nullable = true;
+            return;
+        }
+        let value_type = ColumnType::of_value(value);
+        self.column_type = Some(match self.column_type {
+            Some(current) => current.merge(value_type),
+            None => value_type,
+        });
+    }
+
+    /// Columns without any non-empty values default to string.
+    pub(crate) fn finish(&self, name: &str) -> ColumnSchema {
+        ColumnSchema {
+            name: name.to_string(),
+            column_type: self.column_type.unwrap_or(ColumnType::String),
+            nullable: self.nullable,
+        }
+    }
+}
+
+/// Accepts ISO 8601 dates (`2024-01-31`), optionally followed by a time (`2024-01-31T12:30:00`).
+fn is_date(value: &str) -> bool {
+    let (date, time) = match value.find(['T', ' ']) {
+        Some(i) => (&value[..i], Some(&value[i + 1..])),
+        None => (value, None),
+    };
+
+    let parts: Vec<&str> = date.split('-').collect();
+    let valid_date = match parts.as_slice() {
+        [year, month, day] => {
+            year.len() == 4
+                && month.len() == 2
+                && day.len() == 2
+                && year.parse::<u32>().is_ok()
+                && month.parse::<u32>().is_ok_and(|m| (1..=12).contains(&m))
+                && day.parse::<u32>().is_ok_and(|d| (1..=31).contains(&d))
+        }
+        _ => false,
+    };
+
+    valid_date
+        && time.is_none_or(|time| {
+            let mut fields = time.splitn(3, ':');
+            let hour = fields.next().and_then(|h| h.parse::<u32>().ok());
+            let minute = fields.next().and_then(|m| m.parse::<u32>().ok());
+            hour.is_some_and(|h| h < 24) && minute.is_some_and(|m| m < 60)
+        })
+}
@@ -0,0 +1,92 @@
+//! Built-in functions callable from expressions.
+use super::Value;
+use std::error::Error;
+
+/// Name, minimum and maximum argument count (`None` for variadic) of each built-in.
+const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("upper", 1, Some(1)),
+    ("lower", 1, Some(1)),
+    ("trim", 1, Some(1)),
+    ("length", 1, Some(1)),
+    ("substr", 2, Some(3)),
+    ("replace", 3, Some(3)),
+    ("contains", 2, Some(2)),
+    ("starts_with", 2, Some(2)),
+    ("ends_with", 2, Some(2)),
+    ("concat", 1, None),
+    ("coalesce", 1, None),
+    ("abs", 1, Some(1)),
+    ("round", 1, Some(2)),
+    ("if", 3, Some(3)),
+];
+
+/// Verifies that `name` is a known function accepting `count` arguments.
+pub(crate) fn check_arity(name: &str, count: usize) -> Result<(), Box<dyn Error>> {
+    let &(_, min, max) = FUNCTIONS
+        .iter()
+        .find(|(f, _, _)| *f == name)
+        .ok_or_else(|| format!("Unknown function '{}'", name))?;
+    if count < min || max.is_some_and(|max| count > max) {
+        return Err(format!("Wrong number of arguments for {}(): got {}", name, count).into());
+    }
+    Ok(())
+}
+
+/// Applies a string function, passing nulls through unchanged.
+fn map_text(value: &Value, f: impl FnOnce(&str) -> Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        other => f(&other.to_string()),
+    }
+}
+
+/// Calls the built-in `name` with already evaluated arguments.
+pub(crate) fn call(name: &str, args: Vec<Value>) -> Result<Value, Box<dyn Error>> {
+    let text = |i: usize| args[i].to_string();
+    Ok(match name {
+        "upper" => map_text(&args[0], |s| Value::Str(s.to_uppercase())),
+        "lower" => map_text(&args[0], |s| Value::Str(s.to_lowercase())),
+        "trim" => map_text(&args[0], |s| Value::Str(s.trim().to_string())),
+        "length" => map_text(&args[0], |s| Value::Number(s.chars().count() as f64)),
+        "substr" => {
+            let start = args[1].to_number()?.unwrap_or(1.0).max(1.0) as usize - 1;
+            let len = match args.get(2) {
+                Some(len) => len.to_number()?.map_or(usize::MAX, |n| n.max(0.0) as usize),
+                None => usize::MAX,
+            };
+            map_text(&args[0], |s| {
+                Value::Str(s.chars().skip(start).take(len).collect())
+            })
+        }
+        "replace" => map_text(&args[0], |s| Value::Str(s.replace(&text(1), &text(2)))),
+        "contains" => map_text(&args[0], |s| Value::Bool(s.contains(&text(1)))),
+        "starts_with" => map_text(&args[0], |s| Value::Bool(s.starts_with(&text(1)))),
+        "ends_with" => map_text(&args[0], |s| Value::Bool(s.ends_with(&text(1)))),
+        "concat" => Value::Str(args.iter().map(|v| v.to_string()).collect()),
+        "coalesce" => args
+            .into_iter()
+            .find(|v| *v != Value::Null)
+            .unwrap_or(Value::Null),
+        "abs" => args[0]
+            .to_number()?
+            .map_or(Value::Null, |n| Value::Number(n.abs())),
+        "round" => {
+            let digits = match args.get(1) {
+                Some(digits) => digits.to_number()?.unwrap_or(0.0),
+                None => 0.0,
+            };
+            let scale = 10f64.powi(digits as i32);
+            args[0]
+                .to_number()?
+                .map_or(Value::Null, |n| Value::Number((n * scale).round() / scale))
+        }
+        "if" => {
+            if args[0].is_truthy() {
+                args[1].clone()
+            } else {
+                args[2].clone()
+            }
+        }
+        other => return Err(format!("Unknown function '{}'", other).into()),
+    })
+}
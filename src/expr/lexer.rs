@@ -0,0 +1,120 @@
+//! Splits expression source text into tokens.
+use super::BinaryOp;
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Number(f64),
+    Str(String),
+    /// A bare word: a column name, function name or keyword.
+    Ident(String),
+    /// A double-quoted column name, never treated as a keyword.
+    QuotedIdent(String),
+    Op(BinaryOp),
+    Not,
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// Reads characters up to the closing `quote`, treating a doubled quote as an escaped one.
+fn read_quoted(chars: &mut Peekable<CharIndices>, quote: char) -> Result<String, Box<dyn Error>> {
+    chars.next();
+    let mut literal = String::new();
+    loop {
+        match chars.next() {
+            Some((_, c)) if c == quote && chars.peek().map(|&(_, c)| c) == Some(quote) => {
+                chars.next();
+                literal.push(quote);
+            }
+            Some((_, c)) if c == quote => return Ok(literal),
+            Some((_, c)) => literal.push(c),
+            None => return Err(format!("Unterminated {} quote", quote).into()),
+        }
+    }
+}
+
+/// Consumes characters while `accept` holds and returns the end offset of the run.
+fn read_while(chars: &mut Peekable<CharIndices>, start: usize, accept: fn(char) -> bool) -> usize {
+    let mut end = start;
+    while let Some(&(i, c)) = chars.peek() {
+        if !accept(c) {
+            break;
+        }
+        end = i + c.len_utf8();
+        chars.next();
+    }
+    end
+}
+
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '\'' => Token::Str(read_quoted(&mut chars, '\'')?),
+            '"' => Token::QuotedIdent(read_quoted(&mut chars, '"')?),
+            c if c.is_ascii_digit() || c == '.' => {
+                let end = read_while(&mut chars, start, |c| c.is_ascii_digit() || c == '.');
+                let text = &input[start..end];
+                Token::Number(
+                    text.parse()
+                        .map_err(|_| format!("Invalid number '{}'", text))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                // Dots are allowed so that table-qualified names like `orders.status` read as one column.
+                let end = read_while(&mut chars, start, |c| {
+                    c.is_alphanumeric() || c == '_' || c == '.'
+                });
+                let word = &input[start..end];
+                match word.to_lowercase().as_str() {
+                    "and" => Token::Op(BinaryOp::And),
+                    "or" => Token::Op(BinaryOp::Or),
+                    "not" => Token::Not,
+                    _ => Token::Ident(word.to_string()),
+                }
+            }
+            _ => {
+                chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let mut two_char = |token| {
+                    chars.next();
+                    token
+                };
+                match (c, next) {
+                    ('=', Some('=')) => two_char(Token::Op(BinaryOp::Eq)),
+                    ('!', Some('=')) => two_char(Token::Op(BinaryOp::NotEq)),
+                    ('<', Some('>')) => two_char(Token::Op(BinaryOp::NotEq)),
+                    ('<', Some('=')) => two_char(Token::Op(BinaryOp::LtEq)),
+                    ('>', Some('=')) => two_char(Token::Op(BinaryOp::GtEq)),
+                    ('&', Some('&')) => two_char(Token::Op(BinaryOp::And)),
+                    ('|', Some('|')) => two_char(Token::Op(BinaryOp::Concat)),
+                    ('=', _) => Token::Op(BinaryOp::Eq),
+                    ('<', _) => Token::Op(BinaryOp::Lt),
+                    ('>', _) => Token::Op(BinaryOp::Gt),
+                    ('!', _) => Token::Not,
+                    ('+', _) => Token::Op(BinaryOp::Add),
+                    ('-', _) => Token::Op(BinaryOp::Sub),
+                    ('*', _) => Token::Op(BinaryOp::Mul),
+                    ('/', _) => Token::Op(BinaryOp::Div),
+                    ('%', _) => Token::Op(BinaryOp::Rem),
+                    ('(', _) => Token::LParen,
+                    (')', _) => Token::RParen,
+                    (',', _) => Token::Comma,
+                    (other, _) => return Err(format!("Unexpected character '{}'", other).into()),
+                }
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
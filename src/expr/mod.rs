@@ -0,0 +1,292 @@
+//! A small expression language shared by the commands that compute or test values per row.
+//!
+//! Expressions reference columns by name (double-quote names containing spaces or
+//! punctuation) and combine them with numbers, single-quoted strings, `true`,
+//! `false` and `null` using:
+//!
+//! - arithmetic: `+ - * / %`
+//! - string concatenation: `||`
+//! - comparisons: `= == != <> < <= > >=`
+//! - logic: `and` (`&&`), `or`, `not` (`!`)
+//! - functions: `upper`, `lower`, `trim`, `length`, `substr`, `replace`, `contains`,
+//!   `starts_with`, `ends_with`, `concat`, `coalesce`, `abs`, `round`, `if`
+//!
+//! Parse an expression once with [`parse`], resolve its column names against a
+//! header row with [`Expr::bind`], then call [`Expr::evaluate`] for every row.
+mod functions;
+mod lexer;
+mod parser;
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+/// A value produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+}
+
+impl Value {
+    /// Wraps a raw CSV field, treating empty fields as null.
+    pub fn from_field(field: &str) -> Self {
+        if field.is_empty() {
+            Value::Null
+        } else {
+            Value::Str(field.to_string())
+        }
+    }
+
+    /// Converts the value to a number, returning `None` for null.
+    pub(crate) fn to_number(&self) -> Result<Option<f64>, Box<dyn Error>> {
+        match self {
+            Value::Null => Ok(None),
+            Value::Bool(b) => Ok(Some(if *b { 1.0 } else { 0.0 })),
+            Value::Number(n) => Ok(Some(*n)),
+            Value::Str(s) => s
+                .trim()
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| format!("Cannot use '{}' as a number", s).into()),
+        }
+    }
+
+    /// Interprets the value as a condition; null, zero, empty and `"false"` are false.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Str(s) => !s.is_empty() && !s.eq_ignore_ascii_case("false"),
+        }
+    }
+
+    /// Orders two non-null values, numerically when both sides are numeric.
+    fn compare(&self, other: &Value) -> Ordering {
+        match (self.to_number(), other.to_number()) {
+            (Ok(Some(l)), Ok(Some(r))) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+            _ => self.to_string().cmp(&other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Binary operators supported by the expression language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    Add,
+    Sub,
+    Concat,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Lt
+            | BinaryOp::LtEq
+            | BinaryOp::Gt
+            | BinaryOp::GtEq => 4,
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Concat => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+
+    fn apply(self, left: Value, right: Value) -> Result<Value, Box<dyn Error>> {
+        match self {
+            BinaryOp::Or => Ok(Value::Bool(left.is_truthy() || right.is_truthy())),
+            BinaryOp::And => Ok(Value::Bool(left.is_truthy() && right.is_truthy())),
+            BinaryOp::Concat => Ok(Value::Str(format!("{}{}", left, right))),
+            // Null only equals null, so `col = ''` and `col = null` both test for an empty field.
+            BinaryOp::Eq | BinaryOp::NotEq if left == Value::Null || right == Value::Null => {
+                Ok(Value::Bool((left == right) == (self == BinaryOp::Eq)))
+            }
+            BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Lt
+            | BinaryOp::LtEq
+            | BinaryOp::Gt
+            | BinaryOp::GtEq => {
+                if left == Value::Null || right == Value::Null {
+                    return Ok(Value::Null);
+                }
+                let ordering = left.compare(&right);
+                Ok(Value::Bool(match self {
+                    BinaryOp::Eq => ordering == Ordering::Equal,
+                    BinaryOp::NotEq => ordering != Ordering::Equal,
+                    BinaryOp::Lt => ordering == Ordering::Less,
+                    BinaryOp::LtEq => ordering != Ordering::Greater,
+                    BinaryOp::Gt => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                }))
+            }
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                let (Some(l), Some(r)) = (left.to_number()?, right.to_number()?) else {
+                    return Ok(Value::Null);
+                };
+                Ok(Value::Number(match self {
+                    BinaryOp::Add => l + r,
+                    BinaryOp::Sub => l - r,
+                    BinaryOp::Mul => l * r,
+                    BinaryOp::Div | BinaryOp::Rem if r == 0.0 => return Ok(Value::Null),
+                    BinaryOp::Div => l / r,
+                    _ => l % r,
+                }))
+            }
+        }
+    }
+}
+
+/// A parsed expression tree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// A column referenced by name, as written in the expression.
+    Column(String),
+    /// A column resolved to its position in the row by [`Expr::bind`].
+    Field(usize),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// A call to a built-in function, with its lowercased name.
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    /// Resolves column names to row positions using the given headers.
+    pub fn bind(self, headers: &[String]) -> Result<Expr, Box<dyn Error>> {
+        Ok(match self {
+            Expr::Column(name) => Expr::Field(
+                headers
+                    .iter()
+                    .position(|h| *h == name)
+                    .ok_or_else(|| format!("Column '{}' not found", name))?,
+            ),
+            Expr::Negate(inner) => Expr::Negate(Box::new(inner.bind(headers)?)),
+            Expr::Not(inner) => Expr::Not(Box::new(inner.bind(headers)?)),
+            Expr::Binary(op, left, right) => Expr::Binary(
+                op,
+                Box::new(left.bind(headers)?),
+                Box::new(right.bind(headers)?),
+            ),
+            Expr::Call(name, args) => Expr::Call(
+                name,
+                args.into_iter()
+                    .map(|arg| arg.bind(headers))
+                    .collect::<Result<_, _>>()?,
+            ),
+            other => other,
+        })
+    }
+
+    /// Names of the columns an unbound expression references, in order of appearance.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Expr::Column(name) => vec![name.as_str()],
+            Expr::Negate(inner) | Expr::Not(inner) => inner.columns(),
+            Expr::Binary(_, left, right) => {
+                let mut columns = left.columns();
+                columns.extend(right.columns());
+                columns
+            }
+            Expr::Call(_, args) => args.iter().flat_map(Expr::columns).collect(),
+            Expr::Literal(_) | Expr::Field(_) => Vec::new(),
+        }
+    }
+
+    /// Renames every column an unbound expression references.
+    pub fn rename_columns(self, rename: &impl Fn(&str) -> String) -> Expr {
+        match self {
+            Expr::Column(name) => Expr::Column(rename(&name)),
+            Expr::Negate(inner) => Expr::Negate(Box::new(inner.rename_columns(rename))),
+            Expr::Not(inner) => Expr::Not(Box::new(inner.rename_columns(rename))),
+            Expr::Binary(op, left, right) => Expr::Binary(
+                op,
+                Box::new(left.rename_columns(rename)),
+                Box::new(right.rename_columns(rename)),
+            ),
+            Expr::Call(name, args) => Expr::Call(
+                name,
+                args.into_iter()
+                    .map(|arg| arg.rename_columns(rename))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Evaluates a bound expression against a single row.
+    pub fn evaluate(&self, row: &[String]) -> Result<Value, Box<dyn Error>> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Column(name) => Err(format!("Column '{}' has not been bound", name).into()),
+            Expr::Field(index) => Ok(row
+                .get(*index)
+                .map_or(Value::Null, |field| Value::from_field(field))),
+            Expr::Negate(inner) => match inner.evaluate(row)?.to_number()? {
+                Some(n) => Ok(Value::Number(-n)),
+                None => Ok(Value::Null),
+            },
+            Expr::Not(inner) => Ok(Value::Bool(!inner.evaluate(row)?.is_truthy())),
+            Expr::Binary(op, left, right) => op.apply(left.evaluate(row)?, right.evaluate(row)?),
+            Expr::Call(name, args) => functions::call(
+                name,
+                args.iter()
+                    .map(|arg| arg.evaluate(row))
+                    .collect::<Result<_, _>>()?,
+            ),
+        }
+    }
+
+    /// Evaluates a bound expression as a row predicate.
+    pub fn matches(&self, row: &[String]) -> Result<bool, Box<dyn Error>> {
+        Ok(self.evaluate(row)?.is_truthy())
+    }
+}
+
+/// Parses an expression such as `price * qty + 1` or `upper(status) = 'PAID'`.
+pub fn parse(input: &str) -> Result<Expr, Box<dyn Error>> {
+    parser::Parser::new(lexer::tokenize(input)?).parse()
+}
+
+/// Parses a column definition such as `total = price * qty` into its name and expression.
+pub fn parse_assignment(input: &str) -> Result<(String, Expr), Box<dyn Error>> {
+    let (name, expression) = input
+        .split_once('=')
+        .ok_or_else(|| format!("Expected '<column> = <expression>', got '{}'", input))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Missing column name before '='".into());
+    }
+    if name.ends_with(['!', '<', '>']) {
+        return Err(format!("Expected '<column> = <expression>', got '{}'", input).into());
+    }
+    Ok((name.to_string(), parse(expression)?))
+}
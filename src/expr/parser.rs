@@ -0,0 +1,123 @@
+//! Builds an [`Expr`] tree from tokens using precedence climbing.
+use super::lexer::Token;
+use super::{functions, BinaryOp, Expr, Value};
+use std::error::Error;
+
+/// Binding strength of `not`: looser than comparisons, tighter than `and`.
+const NOT_PRECEDENCE: u8 = 3;
+
+pub(crate) struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(tokens: Vec<Token>) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    /// Parses the whole token stream as a single expression.
+    pub(crate) fn parse(mut self) -> Result<Expr, Box<dyn Error>> {
+        let expr = self.parse_binary(0)?;
+        match self.peek() {
+            Some(token) => Err(format!("Unexpected token {:?}", token).into()),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Box<dyn Error>> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {:?}, found {:?}", expected, token).into()),
+            None => Err(format!("Expected {:?} before end of expression", expected).into()),
+        }
+    }
+
+    /// Parses a binary expression whose operators bind at least as tightly as `min_precedence`.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, Box<dyn Error>> {
+        let mut left = self.parse_unary()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.next();
+            let right = self.parse_binary(op.precedence() + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Box<dyn Error>> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(Expr::Not(Box::new(self.parse_binary(NOT_PRECEDENCE)?)))
+            }
+            Some(Token::Op(BinaryOp::Sub)) => {
+                self.next();
+                Ok(Expr::Negate(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Op(BinaryOp::Add)) => {
+                self.next();
+                self.parse_unary()
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, Box<dyn Error>> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::from_field(&s))),
+            Some(Token::QuotedIdent(name)) => Ok(Expr::Column(name)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.next();
+                self.parse_call(name)
+            }
+            Some(Token::Ident(name)) => Ok(match name.to_lowercase().as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                _ => Expr::Column(name),
+            }),
+            Some(Token::LParen) => {
+                let inner = self.parse_binary(0)?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(token) => Err(format!("Unexpected token {:?}", token).into()),
+            None => Err("Unexpected end of expression".into()),
+        }
+    }
+
+    /// Parses the argument list of a function call; the opening parenthesis is already consumed.
+    fn parse_call(&mut self, name: String) -> Result<Expr, Box<dyn Error>> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::RParen) {
+            self.next();
+        } else {
+            loop {
+                args.push(self.parse_binary(0)?);
+                match self.next() {
+                    Some(Token::Comma) => continue,
+                    Some(Token::RParen) => break,
+                    _ => return Err(format!("Expected ',' or ')' in call to {}", name).into()),
+                }
+            }
+        }
+
+        let name = name.to_lowercase();
+        functions::check_arity(&name, args.len())?;
+        Ok(Expr::Call(name, args))
+    }
+}
@@ -0,0 +1,45 @@
+//! A textual view of the graph for terminals, e.g. over SSH.
+use crate::csv::DataFrame;
+use petgraph::graph::{NodeIndex, UnGraph};
+
+/// Lists every table by name with its foreign keys underneath, drawn as a tree:
+/// `column → table.column` for keys it holds and `column ← table.column` for keys
+/// referencing it. Tables of `path` are marked with their step along it.
+pub fn write_ascii(g: &UnGraph<DataFrame, (String, String)>, path: &[NodeIndex]) -> String {
+    let mut nodes: Vec<NodeIndex> = g.node_indices().collect();
+    nodes.sort_by(|&a, &b| g[a].name.cmp(&g[b].name));
+
+    let mut text = String::new();
+    for node in nodes {
+        let table = &g[node];
+        text.push_str(&table.name);
+        if let Some(count) = table.row_count {
+            text.push_str(&format!(" ({} rows)", count));
+        }
+        if let Some(step) = path.iter().position(|&n| n == node) {
+            text.push_str(&format!("  [path step {}]", step + 1));
+        }
+        text.push('\n');
+
+        let mut keys: Vec<String> = Vec::new();
+        for edge in g.edge_indices() {
+            let (src, dst) = g.edge_endpoints(edge).unwrap();
+            let (column, dst_column) = &g[edge];
+            if src == node {
+                keys.push(format!("{} → {}.{}", column, g[dst].name, dst_column));
+            }
+            if dst == node && src != node {
+                keys.push(format!("{} ← {}.{}", dst_column, g[src].name, column));
+            }
+        }
+        keys.sort();
+        for (i, key) in keys.iter().enumerate() {
+            let branch = match i + 1 == keys.len() {
+                true => "└──",
+                false => "├──",
+            };
+            text.push_str(&format!("{} {}\n", branch, key));
+        }
+    }
+    text
+}
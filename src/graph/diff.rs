@@ -0,0 +1,179 @@
+//! Comparing two versions of a schema.
+use super::{create_graph, find_table, write_styled_dot_file, DotStyles};
+use crate::{config::GraphvizSettings, csv::DataFrame};
+use petgraph::graph::NodeIndex;
+
+/// Color of tables, columns and foreign keys only in the new schema.
+const ADDED_COLOR: &str = "green3";
+
+/// Color of tables, columns and foreign keys only in the old schema.
+const REMOVED_COLOR: &str = "red";
+
+/// A foreign key as `(table, column, referenced table, referenced column)`.
+pub type ForeignKey = (String, String, String, String);
+
+/// Tables, columns and foreign keys that differ between two versions of a schema.
+/// Columns are listed as `(table, column)` for tables in both versions only.
+#[derive(Debug, Default, PartialEq)]
+pub struct SchemaDiff {
+    pub added_tables: Vec<String>,
+    pub removed_tables: Vec<String>,
+    pub added_columns: Vec<(String, String)>,
+    pub removed_columns: Vec<(String, String)>,
+    pub added_foreign_keys: Vec<ForeignKey>,
+    pub removed_foreign_keys: Vec<ForeignKey>,
+}
+
+impl SchemaDiff {
+    /// Whether both versions have the same tables, columns and foreign keys.
+    pub fn is_empty(&self) -> bool {
+        *self == SchemaDiff::default()
+    }
+}
+
+/// Compares the tables of an old and a new version of a schema.
+pub fn diff_schemas(old: &[DataFrame], new: &[DataFrame]) -> SchemaDiff {
+    let find = |tables: &[DataFrame], name: &str| tables.iter().find(|t| t.name == name).cloned();
+    let mut diff = SchemaDiff::default();
+
+    for table in new {
+        match find(old, &table.name) {
+            None => diff.added_tables.push(table.name.clone()),
+            Some(old_table) => {
+                diff.added_columns
+                    .extend(missing_columns(table, &old_table.headers));
+                diff.removed_columns
+                    .extend(missing_columns(&old_table, &table.headers));
+            }
+        }
+    }
+    diff.removed_tables = old
+        .iter()
+        .filter(|table| find(new, &table.name).is_none())
+        .map(|table| table.name.clone())
+        .collect();
+
+    let old_keys = foreign_keys(old);
+    let new_keys = foreign_keys(new);
+    diff.added_foreign_keys = new_keys
+        .iter()
+        .filter(|key| !old_keys.contains(key))
+        .cloned()
+        .collect();
+    diff.removed_foreign_keys = old_keys
+        .into_iter()
+        .filter(|key| !new_keys.contains(key))
+        .collect();
+    diff
+}
+
+/// Columns of `table` that are not among `headers`, as `(table, column)`.
+fn missing_columns(table: &DataFrame, headers: &[String]) -> Vec<(String, String)> {
+    table
+        .headers
+        .iter()
+        .filter(|&h| !headers.iter().any(|other| other.eq_ignore_ascii_case(h)))
+        .map(|h| (table.name.clone(), h.clone()))
+        .collect()
+}
+
+/// All foreign keys of `tables`.
+fn foreign_keys(tables: &[DataFrame]) -> Vec<ForeignKey> {
+    tables
+        .iter()
+        .flat_map(|table| {
+            table.foreign_keys.iter().map(|(column, dst, dst_column)| {
+                (
+                    table.name.clone(),
+                    column.clone(),
+                    dst.clone(),
+                    dst_column.clone(),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Generates DOT format content for the new schema with what `diff` removed added
+/// back, drawing additions in green and removals in red.
+pub fn write_diff_dot_file(
+    old: &[DataFrame],
+    new: &[DataFrame],
+    diff: &SchemaDiff,
+    settings: &GraphvizSettings,
+) -> String {
+    let mut tables = new.to_vec();
+    for table in old {
+        match tables.iter_mut().find(|t| t.name == table.name) {
+            None => tables.push(table.clone()),
+            Some(merged) => {
+                for (name, column) in &diff.removed_columns {
+                    if *name != table.name {
+                        continue;
+                    }
+                    // Types stay aligned with the headers only while every column has one.
+                    let old_type = table
+                        .headers
+                        .iter()
+                        .position(|h| h == column)
+                        .and_then(|i| table.sql_types.get(i));
+                    match old_type {
+                        Some(sql_type) if merged.sql_types.len() == merged.headers.len() => {
+                            merged.sql_types.push(sql_type.clone())
+                        }
+                        _ => merged.sql_types.clear(),
+                    }
+                    merged.headers.push(column.clone());
+                }
+                for (name, column, dst, dst_column) in &diff.removed_foreign_keys {
+                    if *name == table.name {
+                        merged
+                            .foreign_keys
+                            .push((column.clone(), dst.clone(), dst_column.clone()));
+                    }
+                }
+            }
+        }
+    }
+    let g = create_graph(tables);
+    let node = |name: &str| find_table(g.node_weights(), name).map(NodeIndex::new);
+    let style = |color: &str| format!("color=\"{}\", fontcolor=\"{}\", penwidth=2", color, color);
+
+    let mut styles = DotStyles::default();
+    for (names, color) in [
+        (&diff.added_tables, ADDED_COLOR),
+        (&diff.removed_tables, REMOVED_COLOR),
+    ] {
+        for index in names.iter().filter_map(|name| node(name)) {
+            styles.nodes.insert(index, style(color));
+        }
+    }
+    for (columns, color) in [
+        (&diff.added_columns, ADDED_COLOR),
+        (&diff.removed_columns, REMOVED_COLOR),
+    ] {
+        for (name, column) in columns {
+            if let Some(index) = node(name) {
+                styles
+                    .columns
+                    .insert((index, column.clone()), color.to_string());
+            }
+        }
+    }
+    for edge in g.edge_indices() {
+        let (src, dst) = g.edge_endpoints(edge).unwrap();
+        let (column, dst_column) = &g[edge];
+        let is_edge = |(name, key_column, dst_name, key_dst_column): &&ForeignKey| {
+            *name == g[src].name
+                && key_column == column
+                && key_dst_column == dst_column
+                && node(dst_name) == Some(dst)
+        };
+        if diff.added_foreign_keys.iter().any(|key| is_edge(&key)) {
+            styles.edges.insert(edge, style(ADDED_COLOR));
+        } else if diff.removed_foreign_keys.iter().any(|key| is_edge(&key)) {
+            styles.edges.insert(edge, style(REMOVED_COLOR));
+        }
+    }
+    write_styled_dot_file(&g, settings, &styles)
+}
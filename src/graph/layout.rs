@@ -0,0 +1,313 @@
+//! A built-in layered layout drawn as SVG, for machines without Graphviz.
+use super::{escape_html, FOREIGN_KEY_COLOR};
+use crate::{config::GraphvizSettings, csv::DataFrame};
+use petgraph::graph::{NodeIndex, UnGraph};
+use std::collections::HashSet;
+
+/// Estimated width of one character at the label font size.
+const CHAR_WIDTH: f64 = 7.0;
+/// Height of the title and of each column line of a table.
+const LINE_HEIGHT: f64 = 18.0;
+/// Space around the text inside a table.
+const PADDING: f64 = 8.0;
+/// Horizontal space between tables of the same layer.
+const NODE_GAP: f64 = 40.0;
+/// Vertical space between layers, leaving room for foreign key labels.
+const LAYER_GAP: f64 = 70.0;
+/// Color of the tables and foreign keys of a highlighted path.
+const HIGHLIGHT_COLOR: &str = "blue";
+
+/// Position and size of a table's box.
+#[derive(Clone, Copy, Default)]
+struct Placement {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    layer: usize,
+}
+
+/// Draws the graph as SVG with a layered layout: referenced tables sit above the tables
+/// referencing them, and tables are ordered within a layer to keep foreign keys short.
+/// The tables of `path` and the foreign keys between consecutive ones are drawn in blue.
+pub fn write_layered_svg(
+    g: &UnGraph<DataFrame, (String, String)>,
+    settings: &GraphvizSettings,
+    path: &[NodeIndex],
+) -> String {
+    let lines: Vec<Vec<String>> = g
+        .node_indices()
+        .map(|node| column_lines(&g[node], settings))
+        .collect();
+    let mut placements: Vec<Placement> = g
+        .node_indices()
+        .map(|node| {
+            let title = g[node].name.len();
+            let longest = lines[node.index()].iter().map(String::len).max();
+            let chars = longest.unwrap_or(0).max(title);
+            Placement {
+                width: chars as f64 * CHAR_WIDTH + 2.0 * PADDING,
+                height: (lines[node.index()].len() + 1) as f64 * LINE_HEIGHT + PADDING,
+                ..Placement::default()
+            }
+        })
+        .collect();
+
+    let layers = order_layers(g, assign_layers(g));
+    let layer_widths: Vec<f64> = layers
+        .iter()
+        .map(|layer| {
+            let widths: f64 = layer.iter().map(|n| placements[n.index()].width).sum();
+            widths + NODE_GAP * layer.len().saturating_sub(1) as f64
+        })
+        .collect();
+    let width = layer_widths.iter().cloned().fold(0.0, f64::max) + 2.0 * NODE_GAP;
+    let mut y = NODE_GAP;
+    for (i, layer) in layers.iter().enumerate() {
+        let mut x = (width - layer_widths[i]) / 2.0;
+        let mut layer_height: f64 = 0.0;
+        for node in layer {
+            let placement = &mut placements[node.index()];
+            placement.x = x;
+            placement.y = y;
+            placement.layer = i;
+            x += placement.width + NODE_GAP;
+            layer_height = layer_height.max(placement.height);
+        }
+        y += layer_height + LAYER_GAP;
+    }
+    let height = y - LAYER_GAP + NODE_GAP;
+
+    let hops: HashSet<(NodeIndex, NodeIndex)> = path
+        .windows(2)
+        .flat_map(|hop| [(hop[0], hop[1]), (hop[1], hop[0])])
+        .collect();
+    let look = &settings.style;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" \
+         viewBox=\"0 0 {:.0} {:.0}\" font-family=\"{}, sans-serif\" font-size=\"12\">\n",
+        width,
+        height,
+        width,
+        height,
+        escape_html(&look.font_name)
+    );
+    svg.push_str(&format!(
+        "  <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        look.background_color
+    ));
+    for edge in g.edge_indices() {
+        let (src, dst) = g.edge_endpoints(edge).unwrap();
+        let (column, dst_column) = &g[edge];
+        let color = match hops.contains(&(src, dst)) {
+            true => HIGHLIGHT_COLOR,
+            false => look.edge_color.as_str(),
+        };
+        let (x1, y1, x2, y2) = edge_ends(&placements[src.index()], &placements[dst.index()]);
+        if src == dst {
+            svg.push_str(&format!(
+                "  <path d=\"M {:.1} {:.1} C {:.1} {:.1}, {:.1} {:.1}, {:.1} {:.1}\" \
+                 fill=\"none\" stroke=\"{}\"/>\n",
+                x1,
+                y1,
+                x1 + NODE_GAP,
+                y1,
+                x1 + NODE_GAP,
+                y2,
+                x2,
+                y2,
+                color
+            ));
+        } else {
+            svg.push_str(&format!(
+                "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\"/>\n",
+                x1, y1, x2, y2, color
+            ));
+        }
+        let (label_x, label_y) = match src == dst {
+            true => (x1 + NODE_GAP, (y1 + y2) / 2.0),
+            false => ((x1 + x2) / 2.0, (y1 + y2) / 2.0),
+        };
+        svg.push_str(&format!(
+            "  <text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" fill=\"{}\">({}, {})</text>\n",
+            label_x,
+            label_y,
+            color,
+            escape_html(column),
+            escape_html(dst_column)
+        ));
+    }
+    for node in g.node_indices() {
+        let table = &g[node];
+        let placement = &placements[node.index()];
+        let color = match path.contains(&node) {
+            true => HIGHLIGHT_COLOR,
+            false => "black",
+        };
+        svg.push_str(&format!("  <g id=\"{}\">\n", escape_html(&table.name)));
+        svg.push_str(&format!(
+            "    <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" \
+             fill=\"white\" stroke=\"{}\"/>\n",
+            placement.x, placement.y, placement.width, placement.height, color
+        ));
+        svg.push_str(&format!(
+            "    <text x=\"{:.1}\" y=\"{:.1}\" font-weight=\"bold\" fill=\"{}\">{}</text>\n",
+            placement.x + PADDING,
+            placement.y + LINE_HEIGHT - 4.0,
+            look.title_color,
+            escape_html(&table.name)
+        ));
+        svg.push_str(&format!(
+            "    <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\"/>\n",
+            placement.x,
+            placement.y + LINE_HEIGHT,
+            placement.x + placement.width,
+            placement.y + LINE_HEIGHT,
+            color
+        ));
+        for (i, (header, line)) in table.headers.iter().zip(&lines[node.index()]).enumerate() {
+            svg.push_str(&format!(
+                "    <text x=\"{:.1}\" y=\"{:.1}\"{}>{}</text>\n",
+                placement.x + PADDING,
+                placement.y + (i + 2) as f64 * LINE_HEIGHT - 4.0,
+                key_attributes(table, header),
+                escape_html(line)
+            ));
+        }
+        svg.push_str("  </g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Text of each column line of a table, with its SQL type when shown and an arrow after
+/// foreign key columns.
+fn column_lines(table: &DataFrame, settings: &GraphvizSettings) -> Vec<String> {
+    table
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            let line = match table.sql_types.get(i) {
+                Some(sql_type) if settings.show_column_types => {
+                    format!("{}: {}", header, sql_type)
+                }
+                _ => header.clone(),
+            };
+            match is_foreign_key(table, header) {
+                true => format!("{} \u{2192}", line),
+                false => line,
+            }
+        })
+        .collect()
+}
+
+/// SVG text attributes marking a primary key bold and underlined, and a foreign key in
+/// italics and color, like the Graphviz labels.
+fn key_attributes(table: &DataFrame, header: &str) -> String {
+    let mut attributes = String::new();
+    if table
+        .primary_key
+        .as_deref()
+        .is_some_and(|key| key.eq_ignore_ascii_case(header))
+    {
+        attributes.push_str(" font-weight=\"bold\" text-decoration=\"underline\"");
+    }
+    if is_foreign_key(table, header) {
+        attributes.push_str(&format!(
+            " font-style=\"italic\" fill=\"{}\"",
+            FOREIGN_KEY_COLOR
+        ));
+    }
+    attributes
+}
+
+/// Whether `header` is a foreign key column of `table`.
+fn is_foreign_key(table: &DataFrame, header: &str) -> bool {
+    table
+        .foreign_keys
+        .iter()
+        .any(|(fk, _, _)| fk.eq_ignore_ascii_case(header))
+}
+
+/// Puts every table one layer below the lowest table it references. Within a foreign
+/// key cycle, layers stop growing once they reach the number of tables.
+fn assign_layers(g: &UnGraph<DataFrame, (String, String)>) -> Vec<usize> {
+    let max_layer = g.node_count().saturating_sub(1);
+    let mut layers = vec![0; g.node_count()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for edge in g.edge_indices() {
+            let (src, dst) = g.edge_endpoints(edge).unwrap();
+            let below = (layers[dst.index()] + 1).min(max_layer);
+            if src != dst && layers[src.index()] < below {
+                layers[src.index()] = below;
+                changed = true;
+            }
+        }
+    }
+    layers
+}
+
+/// Groups the tables by layer, dropping empty layers, and orders each layer by the mean
+/// position of its neighbors in the layer above, then by name.
+fn order_layers(
+    g: &UnGraph<DataFrame, (String, String)>,
+    layers: Vec<usize>,
+) -> Vec<Vec<NodeIndex>> {
+    let mut grouped: Vec<Vec<NodeIndex>> = vec![Vec::new(); g.node_count()];
+    for node in g.node_indices() {
+        grouped[layers[node.index()]].push(node);
+    }
+    grouped.retain(|layer| !layer.is_empty());
+
+    let mut positions = vec![0.0; g.node_count()];
+    for i in 0..grouped.len() {
+        let (above, rest) = grouped.split_at_mut(i);
+        let layer = &mut rest[0];
+        let above: HashSet<NodeIndex> = above.last().into_iter().flatten().copied().collect();
+        let barycenter = |node: NodeIndex| {
+            let neighbors: Vec<f64> = g
+                .neighbors(node)
+                .filter(|n| above.contains(n))
+                .map(|n| positions[n.index()])
+                .collect();
+            match neighbors.is_empty() {
+                true => f64::MAX,
+                false => neighbors.iter().sum::<f64>() / neighbors.len() as f64,
+            }
+        };
+        layer.sort_by(|&a, &b| {
+            barycenter(a)
+                .total_cmp(&barycenter(b))
+                .then_with(|| g[a].name.cmp(&g[b].name))
+        });
+        for (position, node) in layer.iter().enumerate() {
+            positions[node.index()] = position as f64;
+        }
+    }
+    grouped
+}
+
+/// End points of a foreign key's line: between facing sides of tables in different
+/// layers, or between the sides of tables in the same layer.
+fn edge_ends(src: &Placement, dst: &Placement) -> (f64, f64, f64, f64) {
+    let center = |p: &Placement| (p.x + p.width / 2.0, p.y + p.height / 2.0);
+    let ((src_x, src_y), (dst_x, dst_y)) = (center(src), center(dst));
+    match src.layer.cmp(&dst.layer) {
+        std::cmp::Ordering::Greater => (src_x, src.y, dst_x, dst.y + dst.height),
+        std::cmp::Ordering::Less => (src_x, src.y + src.height, dst_x, dst.y),
+        std::cmp::Ordering::Equal if src.x < dst.x => (src.x + src.width, src_y, dst.x, dst_y),
+        std::cmp::Ordering::Equal if src.x > dst.x => (src.x, src_y, dst.x + dst.width, dst_y),
+        std::cmp::Ordering::Equal => {
+            let right = src.x + src.width;
+            (
+                right,
+                src.y + LINE_HEIGHT / 2.0,
+                right,
+                src.y + src.height - PADDING,
+            )
+        }
+    }
+}
@@ -1,8 +1,29 @@
 //! Functions for creating a graph from tables, running the `dot` command, and opening files.
-use crate::{config, csv::DataFrame, sql};
-use petgraph::graph::UnGraph;
+mod ascii;
+mod diff;
+mod layout;
+mod query;
+
+pub use ascii::write_ascii;
+pub use diff::{diff_schemas, write_diff_dot_file, ForeignKey, SchemaDiff};
+pub use layout::write_layered_svg;
+pub use query::{match_pattern, parse_pattern, PatternStep};
+
+use crate::{
+    config::{self, Config, GraphvizSettings},
+    csv::{ColumnType, DataFrame},
+    sql,
+};
+use petgraph::data::FromElements;
+use petgraph::graph::{EdgeIndex, NodeIndex, UnGraph};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error, path::PathBuf, process::Command};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    error::Error,
+    path::Path,
+    process::Command,
+};
 
 #[derive(Serialize, Deserialize)]
 pub struct SerializableGraph {
@@ -46,6 +67,147 @@ impl From<&UnGraph<DataFrame, (String, String)>> for SerializableGraph {
     }
 }
 
+/// Version of the [`GraphExport`] layout, raised whenever a field changes meaning or
+/// goes away.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Documented layout written by `graph export --format json`, unlike
+/// [`SerializableGraph`] which may change with the cache.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct GraphExport {
+    pub version: u32,
+    pub tables: Vec<TableExport>,
+    pub foreign_keys: Vec<ForeignKeyExport>,
+}
+
+/// A table and its columns, in header order.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct TableExport {
+    pub name: String,
+    pub columns: Vec<ColumnExport>,
+    pub primary_key: Option<String>,
+}
+
+/// A column, with its type when it has been inferred from the data and its declared
+/// type when it was read from a schema.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ColumnExport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: Option<ColumnType>,
+    pub sql_type: Option<String>,
+}
+
+/// An edge of the graph: `table.column` references `references_table.references_column`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ForeignKeyExport {
+    pub table: String,
+    pub column: String,
+    pub references_table: String,
+    pub references_column: String,
+}
+
+impl From<&UnGraph<DataFrame, (String, String)>> for GraphExport {
+    fn from(graph: &UnGraph<DataFrame, (String, String)>) -> Self {
+        let tables = graph
+            .node_weights()
+            .map(|table| TableExport {
+                name: table.name.clone(),
+                columns: table
+                    .headers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, header)| ColumnExport {
+                        name: header.clone(),
+                        column_type: table.column_types.get(i).copied(),
+                        sql_type: table.sql_types.get(i).cloned(),
+                    })
+                    .collect(),
+                primary_key: table.primary_key.clone(),
+            })
+            .collect();
+        // `create_graph` points each edge from the table holding the key.
+        let foreign_keys = graph
+            .edge_indices()
+            .map(|edge| {
+                let (src, dst) = graph.edge_endpoints(edge).unwrap();
+                let (column, references_column) = graph[edge].clone();
+                ForeignKeyExport {
+                    table: graph[src].name.clone(),
+                    column,
+                    references_table: graph[dst].name.clone(),
+                    references_column,
+                }
+            })
+            .collect();
+
+        GraphExport {
+            version: EXPORT_VERSION,
+            tables,
+            foreign_keys,
+        }
+    }
+}
+
+/// Finds the position of the table `name` refers to: the table named exactly so, or else
+/// one whose name matches without its schema when either name has none, so that
+/// `invoices` finds `billing.invoices`.
+pub fn find_table<'a>(
+    tables: impl IntoIterator<Item = &'a DataFrame>,
+    name: &str,
+) -> Option<usize> {
+    let tables: Vec<&DataFrame> = tables.into_iter().collect();
+    tables.iter().position(|t| t.name == name).or_else(|| {
+        tables.iter().position(|t| {
+            (schema_of(&t.name).is_none() || schema_of(name).is_none())
+                && unqualified(&t.name) == unqualified(name)
+        })
+    })
+}
+
+/// Compiles a table name in which `*` stands for any characters into a regex matching
+/// lowercased names.
+pub(crate) fn name_pattern(name: &str) -> Result<Regex, regex::Error> {
+    let name = regex::escape(&name.to_lowercase()).replace(r"\*", ".*");
+    Regex::new(&format!("^{}$", name))
+}
+
+/// Removes the tables matching any of `patterns`, and their foreign keys, from the
+/// graph. A pattern may contain `*` wildcards and matches a table's name with or
+/// without its schema.
+pub fn exclude_tables(
+    g: UnGraph<DataFrame, (String, String)>,
+    patterns: &[String],
+) -> Result<UnGraph<DataFrame, (String, String)>, Box<dyn Error>> {
+    if patterns.is_empty() {
+        return Ok(g);
+    }
+    let patterns = patterns
+        .iter()
+        .map(|pattern| name_pattern(pattern))
+        .collect::<Result<Vec<Regex>, regex::Error>>()?;
+    let is_excluded = |table: &DataFrame| {
+        let name = table.name.to_lowercase();
+        patterns
+            .iter()
+            .any(|pattern| pattern.is_match(&name) || pattern.is_match(unqualified(&name)))
+    };
+    Ok(g.filter_map(
+        |_, table| (!is_excluded(table)).then(|| table.clone()),
+        |_, key| Some(key.clone()),
+    ))
+}
+
+/// Schema part of a qualified table name such as `billing.invoices`.
+pub fn schema_of(name: &str) -> Option<&str> {
+    name.rsplit_once('.').map(|(schema, _)| schema)
+}
+
+/// Table name without its schema.
+fn unqualified(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, table)| table)
+}
+
 /// Creates an undirected graph from a vector of `DataFrame` instances.
 pub fn create_graph(nodes: Vec<DataFrame>) -> UnGraph<DataFrame, (String, String)> {
     let mut g = UnGraph::<DataFrame, (String, String)>::new_undirected();
@@ -60,12 +222,12 @@ pub fn create_graph(nodes: Vec<DataFrame>) -> UnGraph<DataFrame, (String, String
         let src_table = &g[src_index];
 
         for (src_column, dst_table_name, dst_column) in src_table.foreign_keys.clone() {
-            if let Some((dst_index, _)) = g
-                .node_indices()
-                .map(|idx| (idx, &g[idx]))
-                .find(|(_, table)| table.name == *dst_table_name)
-            {
-                g.add_edge(src_index, dst_index, (src_column, dst_column));
+            if let Some(dst_index) = find_table(g.node_weights(), &dst_table_name) {
+                g.add_edge(
+                    src_index,
+                    NodeIndex::new(dst_index),
+                    (src_column, dst_column),
+                );
             }
         }
     }
@@ -73,23 +235,50 @@ pub fn create_graph(nodes: Vec<DataFrame>) -> UnGraph<DataFrame, (String, String
     g
 }
 
+/// The minimum spanning tree of the graph: every table, linked by the fewest foreign
+/// keys that keep connected tables connected.
+pub fn minimum_spanning_tree(
+    g: &UnGraph<DataFrame, (String, String)>,
+) -> UnGraph<DataFrame, (String, String)> {
+    petgraph::Graph::from_elements(petgraph::algo::min_spanning_tree(g))
+}
+
+/// Lists the foreign keys of the graph, one `table.column -> table.column` per line.
+pub fn write_edge_list(g: &UnGraph<DataFrame, (String, String)>) -> String {
+    g.edge_indices()
+        .filter_map(|edge| {
+            let (src, dst) = g.edge_endpoints(edge)?;
+            let (column, dst_column) = &g[edge];
+            Some(format!(
+                "{}.{} -> {}.{}\n",
+                g[src].name, column, g[dst].name, dst_column
+            ))
+        })
+        .collect()
+}
+
+/// Builds the graph from the SQL schema files found by [`config::find_sql_schemas`]
+/// and caches it along with the hash of the schemas.
 pub fn generate_graph(
-    config_dir: &PathBuf,
+    config: &Config,
+    config_dir: &Path,
 ) -> Result<UnGraph<DataFrame, (String, String)>, Box<dyn Error>> {
-    let schema_path =
-        config::find_sql_schema().ok_or("No SQL schema found in the current directory")?;
-    let schema_content = std::fs::read_to_string(&schema_path)?;
-    let result = sql::parse_sql(&schema_content)?;
+    let schemas = config::read_sql_schemas(config)?;
+    if schemas.is_empty() {
+        return Err("No SQL schema found in the current directory".into());
+    }
+    let result = sql::parse_sql_schemas(&schemas)?;
     let g = create_graph(result);
     config::write_graph_cache(&g, config_dir)?;
+    config::write_schema_hash(&config::schemas_hash(&schemas), config_dir)?;
     Ok(g)
 }
 
 /// Opens a file using the default application based on the operating system.
-pub fn open_dot_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+pub fn open_dot_file(file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     if cfg!(target_os = "windows") {
         Command::new("cmd")
-            .args(&["/C", "start", file_path.to_str().unwrap()])
+            .args(["/C", "start", file_path.to_str().unwrap()])
             .status()?;
     } else if cfg!(target_os = "macos") {
         Command::new("open").arg(file_path).status()?;
@@ -102,40 +291,157 @@ pub fn open_dot_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+/// Color of foreign key columns in table labels.
+const FOREIGN_KEY_COLOR: &str = "darkgreen";
+
+/// Style given to the tables and foreign keys of a highlighted path.
+const HIGHLIGHT_STYLE: &str = "color=\"blue\", fontcolor=\"blue\", penwidth=3";
+
+/// Extra DOT attributes for some tables, foreign keys and columns of a rendered graph.
+#[derive(Debug, Default)]
+pub struct DotStyles {
+    /// Attributes appended to a table's node, such as `color="blue"`.
+    pub nodes: HashMap<NodeIndex, String>,
+    /// Attributes appended to a foreign key's edge.
+    pub edges: HashMap<EdgeIndex, String>,
+    /// Font color of a table's column in its label.
+    pub columns: HashMap<(NodeIndex, String), String>,
+}
+
 /// Generates DOT format content for an undirected graph.
-pub fn write_dot_file(g: &UnGraph<DataFrame, (String, String)>) -> String {
+pub fn write_dot_file(
+    g: &UnGraph<DataFrame, (String, String)>,
+    settings: &GraphvizSettings,
+) -> String {
+    write_styled_dot_file(g, settings, &DotStyles::default())
+}
+
+/// Generates DOT format content for an undirected graph, drawing the tables of `path`
+/// and the foreign keys between consecutive ones in color.
+pub fn write_highlighted_dot_file(
+    g: &UnGraph<DataFrame, (String, String)>,
+    settings: &GraphvizSettings,
+    path: &[NodeIndex],
+) -> String {
+    let hops: HashSet<(NodeIndex, NodeIndex)> = path
+        .windows(2)
+        .flat_map(|hop| [(hop[0], hop[1]), (hop[1], hop[0])])
+        .collect();
+    let styles = DotStyles {
+        nodes: path
+            .iter()
+            .map(|&node| (node, HIGHLIGHT_STYLE.to_string()))
+            .collect(),
+        edges: g
+            .edge_indices()
+            .filter(|&edge| hops.contains(&g.edge_endpoints(edge).unwrap()))
+            .map(|edge| (edge, HIGHLIGHT_STYLE.to_string()))
+            .collect(),
+        ..DotStyles::default()
+    };
+    write_styled_dot_file(g, settings, &styles)
+}
+
+/// Generates DOT format content for an undirected graph with `styles` applied.
+pub fn write_styled_dot_file(
+    g: &UnGraph<DataFrame, (String, String)>,
+    settings: &GraphvizSettings,
+    styles: &DotStyles,
+) -> String {
+    let style = |attributes: Option<&String>| match attributes {
+        Some(attributes) => format!(", {}", attributes),
+        None => String::new(),
+    };
+
+    let look = &settings.style;
     let dot_content = {
         let mut dot = String::new();
         dot.push_str("graph G {\n");
-        dot.push_str("  node [shape=record, fontname=\"Arial\"];\n");
-        dot.push_str("  edge [fontsize=12];\n");
-        dot.push_str("  nodesep=1.0;\n");
+        dot.push_str(&format!(
+            "  node [shape={}, fontname=\"{}\"];\n",
+            look.node_shape, look.font_name
+        ));
+        dot.push_str(&format!(
+            "  edge [fontsize={}, fontname=\"{}\", color=\"{}\"];\n",
+            look.edge_font_size, look.font_name, look.edge_color
+        ));
+        dot.push_str(&format!("  bgcolor=\"{}\";\n", look.background_color));
+        dot.push_str(&format!("  fontname=\"{}\";\n", look.font_name));
+        dot.push_str(&format!("  nodesep={};\n", look.nodesep));
+        dot.push_str(&format!("  ranksep={};\n", look.ranksep));
         dot.push_str("  edgesep=0.75;\n");
-        dot.push_str("  rankdir=TB;\n");
+        dot.push_str(&format!("  rankdir={};\n", look.rankdir));
+        // Tables of each schema, drawn in a labeled box of their own.
+        let mut clusters: BTreeMap<&str, String> = BTreeMap::new();
         for node in g.node_indices() {
             let table = &g[node];
-            let columns = table
+            let columns: Vec<String> = table
                 .headers
                 .iter()
-                .map(|col| col.clone())
-                .collect::<Vec<_>>()
-                .join("|");
-            dot.push_str(&format!(
-                "  {} [label=<{{<b><font point-size='16' color='red'>{}</font></b>|{}}}>];\n",
+                .enumerate()
+                .map(|(i, header)| {
+                    let column = match table.sql_types.get(i) {
+                        Some(sql_type) if settings.show_column_types => {
+                            format!("{}: {}", header, escape_html(sql_type))
+                        }
+                        _ => header.clone(),
+                    };
+                    let column = mark_key_column(table, header, column);
+                    match styles.columns.get(&(node, header.clone())) {
+                        Some(color) => format!("<font color='{}'>{}</font>", color, column),
+                        None => column,
+                    }
+                })
+                .collect();
+            let columns = columns.join("|");
+            let rows = match table.row_count {
+                Some(count) => format!("<br/><font point-size='10'>{} rows</font>", count),
+                None => String::new(),
+            };
+            let (target, indent) = match schema_of(&table.name) {
+                Some(schema) => (clusters.entry(schema).or_default(), "    "),
+                None => (&mut dot, "  "),
+            };
+            // `id` and `URL` make each table an anchor in SVG output.
+            target.push_str(&format!(
+                "{}{} [label=<{{<b><font point-size='{}' color='{}'>{}</font></b>{}|{}}}>, \
+                 id=\"{}\", URL=\"#{}\", tooltip=\"{}\"{}];\n",
+                indent,
                 node.index(),
+                look.title_font_size,
+                look.title_color,
+                table.name,
+                rows,
+                columns,
+                table.name,
+                table.name,
                 table.name,
-                columns
+                style(styles.nodes.get(&node))
             ));
         }
+        for (i, (schema, nodes)) in clusters.iter().enumerate() {
+            dot.push_str(&format!("  subgraph cluster_{} {{\n", i));
+            dot.push_str(&format!("    label=\"{}\";\n", schema));
+            dot.push_str("    style=rounded;\n");
+            dot.push_str(nodes);
+            dot.push_str("  }\n");
+        }
         for edge in g.edge_indices() {
             let (src, dst) = g.edge_endpoints(edge).unwrap();
             let (label1, label2) = g.edge_weight(edge).unwrap();
+            // Row counts on either side of the key, the referencing table first.
+            let cardinality = match (g[src].row_count, g[dst].row_count) {
+                (Some(src_rows), Some(dst_rows)) => format!("\\n{} : {} rows", src_rows, dst_rows),
+                _ => String::new(),
+            };
             dot.push_str(&format!(
-                "  {} -- {} [label=\"({}, {})\"];\n",
+                "  {} -- {} [label=\"({}, {}){}\"{}];\n",
                 src.index(),
                 dst.index(),
                 label1,
-                label2
+                label2,
+                cardinality,
+                style(styles.edges.get(&edge))
             ));
         }
         dot.push_str("}\n");
@@ -143,3 +449,28 @@ pub fn write_dot_file(g: &UnGraph<DataFrame, (String, String)>) -> String {
     };
     dot_content
 }
+
+/// Draws a primary key column bold and underlined, and a foreign key column in italics
+/// and color with an arrow towards the table it references. Keys are matched without
+/// regard to case, since the schema parser lowercases them.
+fn mark_key_column(table: &DataFrame, header: &str, column: String) -> String {
+    let is_header = |key: &str| key.eq_ignore_ascii_case(header);
+    let column = match table.primary_key.as_deref().is_some_and(is_header) {
+        true => format!("<b><u>{}</u></b>", column),
+        false => column,
+    };
+    match table.foreign_keys.iter().any(|(fk, _, _)| is_header(fk)) {
+        true => format!(
+            "<i><font color='{}'>{} &#8594;</font></i>",
+            FOREIGN_KEY_COLOR, column
+        ),
+        false => column,
+    }
+}
+
+/// Escapes text for use inside an HTML-like DOT label.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,101 @@
+//! Matching chains of tables against patterns such as `users -> * -> invoices`.
+use super::name_pattern;
+use crate::csv::DataFrame;
+use petgraph::graph::{NodeIndex, UnGraph};
+use regex::Regex;
+use std::error::Error;
+
+/// One step of a pattern, between `->` arrows.
+#[derive(Debug)]
+pub enum PatternStep {
+    /// A single table whose name matches, where `*` stands for any characters.
+    Table(Regex),
+    /// Any number of tables, including none, written `**`.
+    AnyPath,
+}
+
+/// Parses a pattern of steps separated by `->`, each a table name that may contain `*`
+/// wildcards, or `**` for any number of tables in between. The first and last steps
+/// must be tables.
+pub fn parse_pattern(pattern: &str) -> Result<Vec<PatternStep>, Box<dyn Error>> {
+    let steps = pattern
+        .split("->")
+        .map(|step| match step.trim() {
+            "" => Err(format!("Empty step in pattern '{}'", pattern).into()),
+            "**" => Ok(PatternStep::AnyPath),
+            name => Ok(PatternStep::Table(name_pattern(name)?)),
+        })
+        .collect::<Result<Vec<PatternStep>, Box<dyn Error>>>()?;
+    let ends = [steps.first(), steps.last()];
+    if ends
+        .iter()
+        .any(|step| matches!(step, Some(PatternStep::AnyPath)))
+    {
+        return Err("A pattern must start and end with a table, not `**`".into());
+    }
+    Ok(steps)
+}
+
+/// Finds every chain of tables joined by foreign keys, in either direction, that
+/// matches `steps` and visits no table twice. Chains are sorted by length, then by
+/// table names.
+pub fn match_pattern(
+    g: &UnGraph<DataFrame, (String, String)>,
+    steps: &[PatternStep],
+) -> Vec<Vec<NodeIndex>> {
+    let mut matches = Vec::new();
+    if let Some(PatternStep::Table(first)) = steps.first() {
+        for start in g.node_indices() {
+            if first.is_match(&g[start].name.to_lowercase()) {
+                extend_match(g, steps, 1, &mut vec![start], &mut matches);
+            }
+        }
+    }
+    matches.sort_by_key(|chain| {
+        (
+            chain.len(),
+            chain.iter().map(|&n| g[n].name.clone()).collect::<Vec<_>>(),
+        )
+    });
+    matches.dedup();
+    matches
+}
+
+/// Extends `chain` with tables matching the steps from `step` on, collecting every
+/// complete match.
+fn extend_match(
+    g: &UnGraph<DataFrame, (String, String)>,
+    steps: &[PatternStep],
+    step: usize,
+    chain: &mut Vec<NodeIndex>,
+    matches: &mut Vec<Vec<NodeIndex>>,
+) {
+    let Some(current) = steps.get(step) else {
+        matches.push(chain.clone());
+        return;
+    };
+    let last = *chain.last().unwrap();
+    let mut next: Vec<NodeIndex> = g.neighbors(last).filter(|n| !chain.contains(n)).collect();
+    next.sort();
+    next.dedup();
+
+    match current {
+        PatternStep::Table(name) => {
+            for node in next {
+                if name.is_match(&g[node].name.to_lowercase()) {
+                    chain.push(node);
+                    extend_match(g, steps, step + 1, chain, matches);
+                    chain.pop();
+                }
+            }
+        }
+        PatternStep::AnyPath => {
+            extend_match(g, steps, step + 1, chain, matches);
+            for node in next {
+                chain.push(node);
+                extend_match(g, steps, step, chain, matches);
+                chain.pop();
+            }
+        }
+    }
+}
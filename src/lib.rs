@@ -2,6 +2,7 @@ pub mod cli;
 pub mod commands;
 pub mod config;
 pub mod csv;
+pub mod expr;
 pub mod graph;
 pub mod sql;
 pub mod utils;
@@ -1,6 +1,6 @@
 use crate::{config, csv::DataFrame, graph, sql};
 use sqlparser::{
-    ast::{AlterTableOperation, ColumnOption, Statement, TableConstraint},
+    ast::{AlterTableOperation, ColumnOption, ObjectName, Statement, TableConstraint},
     dialect::PostgreSqlDialect,
     parser::Parser,
 };
@@ -9,19 +9,125 @@ use std::{
     path::{Path, PathBuf},
 };
 
+#[cfg(feature = "postgres")]
+mod postgres;
+
+#[cfg(feature = "postgres")]
+pub use postgres::{copy_to_postgres, introspect_postgres};
+
+/// Error reported when PostgreSQL is used without the `postgres` feature.
+pub const POSTGRES_DISABLED: &str =
+    "PostgreSQL support requires building csvg with --features postgres";
+
+/// Quotes a table or column name for use in SQL.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a table name that may be qualified by its schema, as in `billing.invoices`,
+/// quoting each part on its own.
+pub fn quote_qualified_name(name: &str) -> String {
+    name.split('.')
+        .map(quote_identifier)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 /// Parses SQL content and extracts table definitions.
 pub fn parse_sql(contents: &str) -> Result<Vec<DataFrame>, Box<dyn Error>> {
     let dialect = PostgreSqlDialect {};
-    let ast = Parser::parse_sql(&dialect, &contents)?;
-    let mut tables = ast
-        .clone()
-        .into_iter()
-        .filter_map(parse_statement)
-        .collect();
+    let ast = Parser::parse_sql(&dialect, contents)?;
+    Ok(parse_statements(&ast))
+}
+
+/// Parses several schema files, given as `(path, content)` pairs, into one list of
+/// tables. `ALTER TABLE` statements in any file may reference tables created in another.
+pub fn parse_sql_schemas(schemas: &[(PathBuf, String)]) -> Result<Vec<DataFrame>, Box<dyn Error>> {
+    let dialect = PostgreSqlDialect {};
+    let mut ast = Vec::new();
+    for (path, content) in schemas {
+        let statements = Parser::parse_sql(&dialect, content)
+            .map_err(|e| format!("{}: {}", config::display_relative_path(path), e))?;
+        ast.extend(statements);
+    }
+    Ok(parse_statements(&ast))
+}
+
+/// A foreign key column of a database catalog as `(table, column, referenced table,
+/// referenced column, constraint name)`.
+pub type CatalogForeignKey = (String, String, String, String, String);
+
+/// Builds tables from CREATE TABLE statements, then adds foreign keys from ALTER TABLE.
+fn parse_statements(ast: &[Statement]) -> Vec<DataFrame> {
+    let mut tables: Vec<DataFrame> = ast.iter().cloned().filter_map(parse_statement).collect();
+    parse_alter_table(&mut tables, ast);
+    tables
+}
+
+/// Builds table definitions from rows of a database catalog: `(table, column)` pairs in
+/// column order, `(table, column)` primary key columns, and `(table, column, ref_table,
+/// ref_column, constraint)` foreign key columns in constraint order. Names are
+/// lowercased to match [`parse_sql`].
+pub fn tables_from_catalog(
+    columns: &[(String, String)],
+    primary_keys: &[(String, String)],
+    foreign_keys: &[CatalogForeignKey],
+) -> Vec<DataFrame> {
+    let mut tables: Vec<DataFrame> = Vec::new();
+    for (table_name, column) in columns {
+        let table_name = table_name.to_lowercase();
+        let index = match tables.iter().position(|t| t.name == table_name) {
+            Some(index) => index,
+            None => {
+                tables.push(DataFrame::new(table_name));
+                tables.len() - 1
+            }
+        };
+        tables[index].headers.push(column.to_owned());
+    }
 
-    parse_alter_table(&mut tables, &ast);
+    for (table_name, column) in primary_keys {
+        if let Some(table) = tables
+            .iter_mut()
+            .find(|t| t.name == table_name.to_lowercase())
+        {
+            table
+                .primary_key
+                .get_or_insert_with(|| column.to_lowercase());
+        }
+    }
 
-    Ok(tables)
+    let mut constraints: Vec<(&str, &str, Vec<String>)> = Vec::new();
+    for (table_name, column, ref_table, ref_column, constraint) in foreign_keys {
+        if let Some(table) = tables
+            .iter_mut()
+            .find(|t| t.name == table_name.to_lowercase())
+        {
+            table.foreign_keys.push((
+                column.to_lowercase(),
+                ref_table.to_lowercase(),
+                ref_column.to_lowercase(),
+            ));
+        }
+        match constraints
+            .iter_mut()
+            .find(|(name, other, _)| name == table_name && other == constraint)
+        {
+            Some((_, _, columns)) => columns.push(column.to_lowercase()),
+            None => constraints.push((table_name, constraint, vec![column.to_lowercase()])),
+        }
+    }
+    for (table_name, _, columns) in constraints {
+        if let Some(table) = tables
+            .iter_mut()
+            .find(|t| t.name == table_name.to_lowercase())
+        {
+            if columns.len() > 1 {
+                table.composite_foreign_keys.push(columns);
+            }
+        }
+    }
+    tables
 }
 
 fn parse_statement(statement: Statement) -> Option<DataFrame> {
@@ -32,7 +138,7 @@ fn parse_statement(statement: Statement) -> Option<DataFrame> {
 }
 
 fn parse_create_table(create_table: &sqlparser::ast::CreateTable) -> DataFrame {
-    let mut table = DataFrame::new(create_table.name.to_string().to_lowercase());
+    let mut table = DataFrame::new(qualified_name(&create_table.name));
     parse_columns(&mut table, &create_table.columns);
     parse_constraints(&mut table, &create_table.constraints);
     table
@@ -41,6 +147,7 @@ fn parse_create_table(create_table: &sqlparser::ast::CreateTable) -> DataFrame {
 fn parse_columns(table: &mut DataFrame, columns: &[sqlparser::ast::ColumnDef]) {
     for (i, column) in columns.iter().enumerate() {
         table.headers.insert(i, column.name.value.to_owned());
+        table.sql_types.insert(i, column.data_type.to_string());
         for definition in &column.options {
             if let ColumnOption::Unique {
                 is_primary: true, ..
@@ -66,59 +173,70 @@ fn parse_constraint(table: &mut DataFrame, constraint: &TableConstraint) {
         ..
     } = constraint
     {
-        let ident = foreign_table.0.last().unwrap();
-        let fks = [ident.clone()];
+        let dst_table = qualified_name(foreign_table);
 
         table
             .foreign_keys
-            .extend(columns.iter().zip(&fks).zip(referred_columns.iter()).map(
-                |((src_column, dst_table), dst_column)| {
-                    (
-                        src_column.value.to_lowercase().to_owned(),
-                        dst_table.value.to_lowercase().to_owned(),
-                        dst_column.value.to_lowercase().to_owned(),
-                    )
-                },
-            ));
+            .extend(
+                columns
+                    .iter()
+                    .zip(referred_columns.iter())
+                    .map(|(src_column, dst_column)| {
+                        (
+                            src_column.value.to_lowercase().to_owned(),
+                            dst_table.clone(),
+                            dst_column.value.to_lowercase().to_owned(),
+                        )
+                    }),
+            );
+        if columns.len() > 1 {
+            table.composite_foreign_keys.push(
+                columns
+                    .iter()
+                    .map(|column| column.value.to_lowercase())
+                    .collect(),
+            );
+        }
     }
 }
 
-fn parse_alter_table(tables: &mut Vec<DataFrame>, ast: &Vec<Statement>) {
+/// Lowercased table name, qualified with its schema when the statement gives one, as
+/// in `billing.invoices`.
+fn qualified_name(name: &ObjectName) -> String {
+    name.0
+        .iter()
+        .map(|ident| ident.value.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn parse_alter_table(tables: &mut [DataFrame], ast: &[Statement]) {
     for statement in ast {
-        match &statement {
-            Statement::AlterTable {
-                name, operations, ..
-            } => {
-                if let Some(table_index) = tables
-                    .iter()
-                    .position(|t| t.name == name.0.last().unwrap().value.to_lowercase())
-                {
-                    for op in operations {
-                        match op {
-                            AlterTableOperation::AddConstraint(constraint) => {
-                                let table = &mut tables[table_index];
-                                parse_constraint(table, constraint);
-                            }
-                            _ => {}
-                        }
+        if let Statement::AlterTable {
+            name, operations, ..
+        } = &statement
+        {
+            if let Some(table_index) = graph::find_table(tables.iter(), &qualified_name(name)) {
+                for op in operations {
+                    if let AlterTableOperation::AddConstraint(constraint) = op {
+                        let table = &mut tables[table_index];
+                        parse_constraint(table, constraint);
                     }
                 }
             }
-            _ => {}
         }
     }
 }
 
-pub fn process_sql_schema(
-    schema_path: &Path,
-    config_dir: &PathBuf,
+pub fn process_sql_schemas(
+    schemas: &[(PathBuf, String)],
+    config_dir: &Path,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let schema_content = std::fs::read_to_string(schema_path)
-        .map_err(|e| format!("Failed to read schema file: {}", e))?;
     let result =
-        sql::parse_sql(&schema_content).map_err(|e| format!("Failed to parse SQL: {}", e))?;
+        sql::parse_sql_schemas(schemas).map_err(|e| format!("Failed to parse SQL: {}", e))?;
     let g = graph::create_graph(result);
     config::write_graph_cache(&g, config_dir)
+        .and_then(|_| config::write_schema_hash(&config::schemas_hash(schemas), config_dir))
         .map_err(|e| format!("Failed to write graph cache: {}", e))?;
     println!(
         "Graph data cached in {}",
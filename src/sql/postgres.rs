@@ -0,0 +1,134 @@
+//! Reading table definitions from a live PostgreSQL database's `information_schema`, and
+//! copying CSV rows into its tables.
+use super::{quote_identifier, quote_qualified_name, tables_from_catalog};
+use crate::csv::{ColumnType, DataFrame, Dialect};
+use postgres::{Client, NoTls};
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+
+const COLUMNS_QUERY: &str = "
+    SELECT c.table_name::text, c.column_name::text
+    FROM information_schema.columns c
+    JOIN information_schema.tables t
+      ON t.table_schema = c.table_schema AND t.table_name = c.table_name
+    WHERE c.table_schema = $1 AND t.table_type = 'BASE TABLE'
+    ORDER BY c.table_name, c.ordinal_position";
+
+const PRIMARY_KEYS_QUERY: &str = "
+    SELECT kcu.table_name::text, kcu.column_name::text
+    FROM information_schema.table_constraints tc
+    JOIN information_schema.key_column_usage kcu
+      ON kcu.constraint_schema = tc.constraint_schema
+     AND kcu.constraint_name = tc.constraint_name
+    WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = $1
+    ORDER BY kcu.table_name, kcu.ordinal_position";
+
+const FOREIGN_KEYS_QUERY: &str = "
+    SELECT kcu.table_name::text, kcu.column_name::text,
+           ref.table_name::text, ref.column_name::text, kcu.constraint_name::text
+    FROM information_schema.referential_constraints rc
+    JOIN information_schema.key_column_usage kcu
+      ON kcu.constraint_schema = rc.constraint_schema
+     AND kcu.constraint_name = rc.constraint_name
+    JOIN information_schema.key_column_usage ref
+      ON ref.constraint_schema = rc.unique_constraint_schema
+     AND ref.constraint_name = rc.unique_constraint_name
+     AND ref.ordinal_position = kcu.position_in_unique_constraint
+    WHERE kcu.table_schema = $1
+    ORDER BY kcu.table_name, kcu.constraint_name, kcu.ordinal_position";
+
+/// Connects to the database at `url` (without TLS) and reads the tables, primary keys
+/// and foreign keys of `schema`, giving the same tables [`super::parse_sql`] would for
+/// a dump of it.
+pub fn introspect_postgres(url: &str, schema: &str) -> Result<Vec<DataFrame>, Box<dyn Error>> {
+    let mut client = Client::connect(url, NoTls)
+        .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
+
+    let columns = client
+        .query(COLUMNS_QUERY, &[&schema])?
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        return Err(format!("No tables found in schema '{}'", schema).into());
+    }
+    let primary_keys = client
+        .query(PRIMARY_KEYS_QUERY, &[&schema])?
+        .iter()
+        .map(|row| (row.get(0), row.get(1)))
+        .collect::<Vec<_>>();
+    let foreign_keys = client
+        .query(FOREIGN_KEYS_QUERY, &[&schema])?
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3), row.get(4)))
+        .collect::<Vec<_>>();
+
+    Ok(tables_from_catalog(&columns, &primary_keys, &foreign_keys))
+}
+
+/// Streams the rows of the CSV file at `path` into `table`, optionally qualified by its
+/// schema as `schema.table`, of the database at `url` with `COPY ... FROM STDIN`, in one
+/// transaction. A missing table is created first, with int, float and boolean columns as
+/// BIGINT, DOUBLE PRECISION and BOOLEAN and other columns as TEXT; an existing one is
+/// appended to by column name. Empty fields become NULL. Returns the number of rows
+/// copied.
+pub fn copy_to_postgres(
+    path: &Path,
+    url: &str,
+    table: &str,
+    dialect: &Dialect,
+) -> Result<u64, Box<dyn Error>> {
+    let mut df = DataFrame::new(table.to_string()).with_dialect(dialect.clone());
+    df.read_headers(path)?;
+    let columns = df.infer_types(&mut df.open_rows(path)?, None)?;
+
+    let mut client = Client::connect(url, NoTls)
+        .map_err(|e| format!("Failed to connect to PostgreSQL: {}", e))?;
+    let mut transaction = client.transaction()?;
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let sql_type = match column.column_type {
+                ColumnType::Int => "BIGINT",
+                ColumnType::Float => "DOUBLE PRECISION",
+                ColumnType::Bool => "BOOLEAN",
+                ColumnType::Date | ColumnType::String => "TEXT",
+            };
+            format!("{} {}", quote_identifier(&column.name), sql_type)
+        })
+        .collect();
+    transaction
+        .batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            quote_qualified_name(table),
+            definitions.join(", ")
+        ))
+        .map_err(|e| format!("Failed to create table '{}': {}", table, e))?;
+
+    let names: Vec<String> = columns.iter().map(|c| quote_identifier(&c.name)).collect();
+    let mut writer = transaction.copy_in(&format!(
+        "COPY {} ({}) FROM STDIN (FORMAT csv)",
+        quote_qualified_name(table),
+        names.join(", ")
+    ))?;
+    // Rows are rewritten in PostgreSQL's CSV dialect, with values in the form it parses.
+    let copy_df = DataFrame::new(table.to_string());
+    df.process_rows(&mut df.open_rows(path)?, |row| {
+        let values: Vec<String> = columns
+            .iter()
+            .zip(row)
+            .map(|(column, value)| match column.column_type {
+                ColumnType::Int | ColumnType::Float | ColumnType::Bool => {
+                    column.column_type.cast(value).unwrap_or_default()
+                }
+                ColumnType::Date | ColumnType::String => value.clone(),
+            })
+            .collect();
+        copy_df.write_row(&mut writer, &values)
+    })?;
+    writer.flush()?;
+    let rows = writer.finish()?;
+    transaction.commit()?;
+    Ok(rows)
+}
@@ -3,8 +3,9 @@ use std::path::PathBuf;
 use tempfile::TempDir;
 
 use csvg::config::{
-    create_config_folder, graph_cache_exists, read_config, read_graph_cache, redirect_output,
-    write_config, write_graph_cache, Config, GraphvizSettings,
+    create_config_folder, find_sql_schemas, graph_cache_exists, graph_cache_is_stale, read_config,
+    read_graph_cache, redirect_output, remove_schema_hash, schema_hash, schemas_hash, write_config,
+    write_graph_cache, write_schema_hash, Config, GraphvizSettings,
 };
 use csvg::csv::DataFrame;
 
@@ -20,15 +21,19 @@ fn test_write_and_read_config() {
         graphviz_settings: GraphvizSettings {
             engine: "neato".to_string(),
             format: "svg".to_string(),
+            ..GraphvizSettings::default()
         },
         csv_output_path: PathBuf::from("/test/csv"),
+        postgres_url: Some("postgres://localhost/test".to_string()),
+        ..Config::default()
     };
 
     write_config(&config, &config_path).unwrap();
-    let read_config = read_config(&temp_dir.path()).unwrap();
+    let read_config = read_config(temp_dir.path()).unwrap();
 
     assert_eq!(config.output_file, read_config.output_file);
     assert_eq!(config.output_path, read_config.output_path);
+    assert_eq!(config.postgres_url, read_config.postgres_url);
     assert_eq!(config.source_path, read_config.source_path);
     assert_eq!(
         config.graphviz_settings.engine,
@@ -41,6 +46,20 @@ fn test_write_and_read_config() {
     assert_eq!(config.csv_output_path, read_config.csv_output_path);
 }
 
+#[test]
+fn test_graphviz_settings_defaults() {
+    let settings: GraphvizSettings =
+        serde_json::from_str(r#"{"engine": "dot", "format": "png"}"#).unwrap();
+    assert!(settings.show_column_types);
+    assert!(settings.open_rendered);
+    assert_eq!(settings.style.rankdir, "TB");
+
+    let settings: GraphvizSettings =
+        serde_json::from_str(r#"{"engine": "dot", "format": "svg", "open_rendered": false}"#)
+            .unwrap();
+    assert!(!settings.open_rendered);
+}
+
 #[test]
 fn test_write_and_read_graph_cache() {
     let temp_dir = TempDir::new().unwrap();
@@ -57,6 +76,57 @@ fn test_write_and_read_graph_cache() {
     assert_eq!(read_graph.edge_count(), graph.edge_count());
 }
 
+#[test]
+fn test_graph_cache_is_stale() {
+    let temp_dir = TempDir::new().unwrap();
+    let schema = "CREATE TABLE users (id INT PRIMARY KEY);";
+    let hash = schema_hash(schema);
+
+    assert_eq!(hash, schema_hash(schema));
+    assert_ne!(hash, schema_hash(""));
+    assert_eq!(
+        schemas_hash(&[(PathBuf::from("schema.sql"), schema.to_string())]),
+        hash,
+        "a single file hashes as its content"
+    );
+    assert!(
+        !graph_cache_is_stale(&hash, temp_dir.path()),
+        "a cache without a hash is never stale"
+    );
+
+    write_schema_hash(&hash, temp_dir.path()).unwrap();
+    assert!(!graph_cache_is_stale(&hash, temp_dir.path()));
+    assert!(graph_cache_is_stale(
+        &schema_hash("CREATE TABLE users (id BIGINT PRIMARY KEY);"),
+        temp_dir.path()
+    ));
+
+    remove_schema_hash(temp_dir.path()).unwrap();
+    remove_schema_hash(temp_dir.path()).unwrap();
+    assert!(!graph_cache_is_stale("", temp_dir.path()));
+}
+
+#[test]
+fn test_find_sql_schemas() {
+    let temp_dir = TempDir::new().unwrap();
+    let schema_dir = temp_dir.path().join("schema");
+    std::fs::create_dir(&schema_dir).unwrap();
+    for name in ["b.sql", "a.sql", "notes.txt"] {
+        std::fs::write(schema_dir.join(name), "").unwrap();
+    }
+    let extra = temp_dir.path().join("extra.sql");
+    std::fs::write(&extra, "").unwrap();
+
+    let config = Config {
+        schema_paths: vec![schema_dir.clone(), extra.clone()],
+        ..Config::default()
+    };
+    assert_eq!(
+        find_sql_schemas(&config),
+        vec![schema_dir.join("a.sql"), schema_dir.join("b.sql"), extra]
+    );
+}
+
 #[test]
 fn test_redirect_output() {
     let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,111 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// Runs `csvg csv <args>` in `dir` with `stdin` as standard input, returning what it
+/// printed to stdout and stderr.
+fn csvg_csv(dir: &Path, args: &[&str], stdin: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_csvg"))
+        .arg("csv")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn test_output_replaced_only_on_success() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("users.csv"), "id,name\n1,ann\n2,bob\n").unwrap();
+    std::fs::write(dir.path().join("ragged.csv"), "id,name\n1,ann\n2,bob,x\n").unwrap();
+    let target = dir.path().join("csv/out.csv");
+    std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+    std::fs::write(&target, "previous\n").unwrap();
+
+    let (_, stderr) = csvg_csv(
+        dir.path(),
+        &["select", "--output", "out.csv", "ragged", "id"],
+        "",
+    );
+    assert!(stderr.contains("expected 2 fields"), "{}", stderr);
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "previous\n");
+    assert_eq!(
+        std::fs::read_dir(target.parent().unwrap()).unwrap().count(),
+        1
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    let (_, stderr) = csvg_csv(
+        dir.path(),
+        &["select", "--output", "out.csv", "users", "id"],
+        "",
+    );
+    assert_eq!(stderr, "");
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "id\n1\n2\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    csvg_csv(
+        dir.path(),
+        &["select", "--output", "out.html", "users", "name"],
+        "",
+    );
+    let html = std::fs::read_to_string(dir.path().join("csv/out.html")).unwrap();
+    assert!(html.contains("<td>ann</td>"), "{}", html);
+}
+
+#[test]
+fn test_stdin_input() {
+    let dir = TempDir::new().unwrap();
+
+    let (stdout, stderr) = csvg_csv(dir.path(), &["select", "-", "v"], "id,v\n1,x\n2,y\n");
+    assert_eq!(stderr, "");
+    assert_eq!(stdout, "v\nx\ny\n");
+}
+
+#[test]
+fn test_stdin_used_once() {
+    let dir = TempDir::new().unwrap();
+
+    let (_, stderr) = csvg_csv(dir.path(), &["join", "-", "-", "id", "id"], "id\n1\n");
+    assert_eq!(
+        stderr,
+        "Error: Standard input can only be used as one input\n"
+    );
+}
+
+#[test]
+fn test_count_per_value_quotes_values() {
+    let dir = TempDir::new().unwrap();
+
+    let (stdout, stderr) = csvg_csv(
+        dir.path(),
+        &["count", "--per-value", "name", "-"],
+        "id,name\n1,\"Smith, J\"\n2,\"Smith, J\"\n3,Ann\n",
+    );
+    assert_eq!(stderr, "");
+    assert_eq!(stdout, "name,count\nAnn,1\n\"Smith, J\",2\n");
+}
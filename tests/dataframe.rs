@@ -1,12 +1,22 @@
+use regex::Regex;
 use std::{
     error::Error,
-    io::{BufWriter, Cursor, Write},
+    io::{BufWriter, Cursor, Read, Write},
+    path::Path,
 };
-use tempfile::NamedTempFile;
+use tempfile::{NamedTempFile, TempDir};
 
 use csvg::{
-    cli::JoinType,
-    csv::{human_readable_bytes, DataFrame},
+    cli::{
+        CastOnError, DedupeKeep, DuplicateHeaders, JoinStrategy, JoinType, LineEnding,
+        OutputFormat, PivotAggregate, RaggedPolicy,
+    },
+    csv::{
+        human_readable_bytes, levenshtein, parse_cast_spec, reformat_date, sanitize_file_name,
+        snake_case, write_html, write_json, write_latex, ColumnType, Compression, DataFrame,
+        Dialect, FuzzyThreshold, JoinOptions, RangeCondition, TextCase, MAX_OPEN_PARTS,
+    },
+    expr,
 };
 
 #[test]
@@ -131,6 +141,869 @@ fn test_select_stream() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_derive_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["price".to_string(), "qty".to_string()];
+    let mut input = Cursor::new(
+        "2.5,4
+3,
+",
+    );
+    let mut output = Vec::new();
+
+    df.derive_stream(
+        &mut input,
+        &mut output,
+        "total",
+        &expr::parse("price * qty")?,
+    )?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "price,qty,total\n2.5,4,10\n3,,\n"
+    );
+    assert!(df
+        .derive_stream(
+            &mut Cursor::new(""),
+            &mut Vec::new(),
+            "qty",
+            &expr::parse("1")?
+        )
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_filter_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["status".to_string(), "total".to_string()];
+    let mut input = Cursor::new("paid,120\nopen,300\npaid,80\n");
+    let mut output = Vec::new();
+
+    df.filter_stream(
+        &mut input,
+        &mut output,
+        &expr::parse("status = 'paid' and total > 100")?,
+    )?;
+
+    assert_eq!(String::from_utf8(output)?, "status,total\npaid,120\n");
+    Ok(())
+}
+
+#[test]
+fn test_infer_types() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = ["id", "price", "created", "active", "name", "empty"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let mut input = Cursor::new(
+        "1,2,2024-01-31,true,Alice,\n2,2.5,2024-02-01T10:30:00,false,,\n3,,2024-02-02,TRUE,Bob,\n",
+    );
+
+    let schema = df.infer_types(&mut input, None)?;
+
+    let types: Vec<ColumnType> = schema.iter().map(|c| c.column_type).collect();
+    assert_eq!(
+        types,
+        vec![
+            ColumnType::Int,
+            ColumnType::Float,
+            ColumnType::Date,
+            ColumnType::Bool,
+            ColumnType::String,
+            ColumnType::String,
+        ]
+    );
+    let nullable: Vec<bool> = schema.iter().map(|c| c.nullable).collect();
+    assert_eq!(nullable, vec![false, true, false, false, true, true]);
+    assert_eq!(df.column_types, types);
+    Ok(())
+}
+
+#[test]
+fn test_infer_types_with_sample_limit() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["code".to_string()];
+    let mut input = Cursor::new("1\n2\nA3\n");
+
+    let schema = df.infer_types(&mut input, Some(2))?;
+
+    assert_eq!(schema[0].column_type, ColumnType::Int);
+    assert_eq!(ColumnType::of_value("2024-13-01"), ColumnType::String);
+    assert_eq!(ColumnType::Int.merge(ColumnType::Float), ColumnType::Float);
+    assert_eq!(ColumnType::Int.merge(ColumnType::Date), ColumnType::String);
+    Ok(())
+}
+
+#[test]
+fn test_describe() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["amount".to_string(), "name".to_string()];
+    let mut input = Cursor::new("10,bob\n8.5,alice\n,bob\n2.5,\n");
+
+    let stats = df.describe(&mut input)?;
+
+    let amount = &stats[0];
+    assert_eq!(amount.count, 3);
+    assert_eq!(amount.null_count, 1);
+    assert_eq!(amount.min.as_deref(), Some("2.5"));
+    assert_eq!(amount.max.as_deref(), Some("10"));
+    assert_eq!(amount.mean, Some(7.0));
+    assert_eq!(amount.distinct, 3);
+
+    let name = &stats[1];
+    assert_eq!(name.count, 3);
+    assert_eq!(name.null_count, 1);
+    assert_eq!(name.min.as_deref(), Some("alice"));
+    assert_eq!(name.max.as_deref(), Some("bob"));
+    assert_eq!(name.mean, None);
+    assert_eq!(name.distinct, 2);
+    Ok(())
+}
+
+#[test]
+fn test_count_rows() -> Result<(), Box<dyn Error>> {
+    let df = DataFrame::new("test".to_string());
+    let mut input = Cursor::new("1,a\n2,\"multi\nline\"\n3,c\n");
+    assert_eq!(df.count_rows(&mut input)?, 3);
+    assert_eq!(df.count_rows(&mut Cursor::new(""))?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_count_by_value() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "status".to_string()];
+    let mut input = Cursor::new("1,paid\n2,open\n3,paid\n4,\n");
+
+    let counts = df.count_by_value(&mut input, "status")?;
+
+    let counts: Vec<(&str, usize)> = counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    assert_eq!(counts, vec![("", 1), ("open", 1), ("paid", 2)]);
+    assert!(df.count_by_value(&mut Cursor::new(""), "missing").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_value_frequencies() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["status".to_string()];
+    let input = "paid\nopen\npaid\nvoid\nopen\npaid\nopen\nnew\n";
+
+    let frequencies = df.value_frequencies(&mut Cursor::new(input), "status", Some(3))?;
+
+    let summary: Vec<(&str, usize)> = frequencies
+        .iter()
+        .map(|f| (f.value.as_str(), f.count))
+        .collect();
+    assert_eq!(summary, vec![("open", 3), ("paid", 3), ("new", 1)]);
+    assert_eq!(frequencies[0].percent, 37.5);
+
+    let all = df.value_frequencies(&mut Cursor::new(input), "status", None)?;
+    assert_eq!(all.len(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_split_by_rows() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string()];
+    let mut input = Cursor::new("1\n2\n3\n4\n5\n");
+
+    let paths = df.split_by_rows(&mut input, dir.path(), "part", 2)?;
+
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[0], dir.path().join("part_1.csv"));
+    assert_eq!(std::fs::read_to_string(&paths[0])?, "id\n1\n2\n");
+    assert_eq!(std::fs::read_to_string(&paths[2])?, "id\n5\n");
+    Ok(())
+}
+
+#[test]
+fn test_split_by_column() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "region".to_string()];
+    let mut input = Cursor::new("1,eu/west\n2,us\n3,eu/west\n4,\n5,eu west\n");
+
+    let paths = df.split_by_column(&mut input, dir.path(), "orders", "region")?;
+
+    let names: Vec<String> = paths
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "orders_eu_west.csv",
+            "orders_us.csv",
+            "orders_empty.csv",
+            "orders_eu_west_2.csv"
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(&paths[0])?,
+        "id,region\n1,eu/west\n3,eu/west\n"
+    );
+    assert_eq!(sanitize_file_name("../etc"), "___etc");
+    Ok(())
+}
+
+#[test]
+fn test_split_by_column_colliding_names() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "s".to_string()];
+    let mut input = Cursor::new("1,a b\n2,a_b\n3,a_b_2\n");
+
+    let paths = df.split_by_column(&mut input, dir.path(), "s", "s")?;
+
+    let contents: Vec<String> = paths
+        .iter()
+        .map(std::fs::read_to_string)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(paths[2], dir.path().join("s_a_b_2_2.csv"));
+    assert_eq!(
+        contents,
+        vec!["id,s\n1,a b\n", "id,s\n2,a_b\n", "id,s\n3,a_b_2\n"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_split_by_column_reopens_closed_parts() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "value".to_string()];
+    let values = MAX_OPEN_PARTS + 10;
+    let rows: String = (0..values * 2)
+        .map(|i| format!("{},v{}\n", i, i % values))
+        .collect();
+
+    let paths = df.split_by_column(&mut Cursor::new(rows), dir.path(), "p", "value")?;
+
+    assert_eq!(paths.len(), values);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("p_v0.csv"))?,
+        format!("id,value\n0,v0\n{},v0\n", values)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_split_by_column_failure_removes_parts() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "value".to_string()];
+    let mut input = Cursor::new("1,a\n2,b\n3,c,extra\n");
+
+    assert!(df
+        .split_by_column(&mut input, dir.path(), "p", "value")
+        .is_err());
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+    Ok(())
+}
+
+fn pivot(input: &str, agg: PivotAggregate) -> Result<String, Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["day".to_string(), "metric".to_string(), "value".to_string()];
+    let mut output = Vec::new();
+    df.pivot_stream(
+        &mut Cursor::new(input.to_string()),
+        &mut output,
+        &["day".to_string()],
+        "metric",
+        "value",
+        &agg,
+    )?;
+    Ok(String::from_utf8(output)?)
+}
+
+#[test]
+fn test_pivot_stream() -> Result<(), Box<dyn Error>> {
+    let input = "mon,temp,20\nmon,rain,3\ntue,temp,22\nwed,rain,1\n";
+    assert_eq!(
+        pivot(input, PivotAggregate::First)?,
+        "day,temp,rain\nmon,20,3\ntue,22,\nwed,,1\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_pivot_stream_aggregates() -> Result<(), Box<dyn Error>> {
+    let input = "mon,temp,20\nmon,temp,21.5\ntue,temp,22\ntue,temp,\n";
+    assert_eq!(
+        pivot(input, PivotAggregate::First)?,
+        "day,temp\nmon,20\ntue,22\n"
+    );
+    assert_eq!(
+        pivot(input, PivotAggregate::Last)?,
+        "day,temp\nmon,21.5\ntue,\n"
+    );
+    assert_eq!(
+        pivot(input, PivotAggregate::Sum)?,
+        "day,temp\nmon,41.5\ntue,22\n"
+    );
+    assert_eq!(
+        pivot(input, PivotAggregate::Count)?,
+        "day,temp\nmon,2\ntue,2\n"
+    );
+    assert!(pivot("mon,temp,warm\n", PivotAggregate::Sum).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_melt_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["day".to_string(), "temp".to_string(), "rain".to_string()];
+    let mut output = Vec::new();
+
+    df.melt_stream(
+        &mut Cursor::new("mon,20,3\ntue,22,\n"),
+        &mut output,
+        &["day".to_string()],
+        &[],
+        "metric",
+        "value",
+    )?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "day,metric,value\nmon,temp,20\nmon,rain,3\ntue,temp,22\ntue,rain,\n"
+    );
+
+    let mut output = Vec::new();
+    df.melt_stream(
+        &mut Cursor::new("mon,20,3\n"),
+        &mut output,
+        &["day".to_string()],
+        &["rain".to_string()],
+        "variable",
+        "value",
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "day,variable,value\nmon,rain,3\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_snake_case() {
+    assert_eq!(snake_case(" Order ID "), "order_id");
+    assert_eq!(snake_case("orderId"), "order_id");
+    assert_eq!(snake_case("unit-price (USD)"), "unit_price_usd");
+    assert_eq!(snake_case("already_snake"), "already_snake");
+}
+
+#[test]
+fn test_clean_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec![" First  Name".to_string(), "cityName ".to_string()];
+
+    let mut output = Vec::new();
+    df.clean_stream(
+        &mut Cursor::new("\"  Ada   Lovelace \",London\n"),
+        &mut output,
+        false,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "First Name,cityName\nAda Lovelace,London\n"
+    );
+
+    let mut output = Vec::new();
+    df.clean_stream(&mut Cursor::new(""), &mut output, true)?;
+    assert_eq!(String::from_utf8(output)?, "first_name,city_name\n");
+
+    df.headers = vec!["Name".to_string(), "name ".to_string()];
+    assert!(df
+        .clean_stream(&mut Cursor::new(""), &mut Vec::new(), true)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_slice_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string()];
+    let rows = "1\n2\n3\n4\n5\n";
+
+    let mut output = Vec::new();
+    df.slice_stream(&mut Cursor::new(rows), &mut output, 1, Some(2))?;
+    assert_eq!(String::from_utf8(output)?, "id\n2\n3\n");
+
+    let mut output = Vec::new();
+    df.slice_stream(&mut Cursor::new(rows), &mut output, 3, None)?;
+    assert_eq!(String::from_utf8(output)?, "id\n4\n5\n");
+
+    let mut output = Vec::new();
+    df.slice_stream(&mut Cursor::new(rows), &mut output, 10, Some(2))?;
+    assert_eq!(String::from_utf8(output)?, "id\n");
+    Ok(())
+}
+
+#[test]
+fn test_grep_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["name".to_string(), "email".to_string()];
+    let rows = "alice,alice@example.com\nbob,bob@test.org\ncarol,alice.c@test.org\n";
+    let pattern = Regex::new("^alice")?;
+
+    let mut output = Vec::new();
+    let matches = df.grep_stream(&mut Cursor::new(rows), &mut output, &pattern, None)?;
+    assert_eq!(matches, 2);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "name,email\nalice,alice@example.com\ncarol,alice.c@test.org\n"
+    );
+
+    let count = df.count_matches(&mut Cursor::new(rows), &pattern, Some("name"))?;
+    assert_eq!(count, 1);
+    assert!(df
+        .count_matches(&mut Cursor::new(rows), &pattern, Some("missing"))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_replace_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["name".to_string(), "phone".to_string()];
+    let rows = "\"Smith, John\",555-1234\nAda,none\n";
+    let pattern = Regex::new(r"(\d{3})-(\d{4})")?;
+
+    let mut output = Vec::new();
+    let replaced = df.replace_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        "phone",
+        &pattern,
+        "($1) $2",
+    )?;
+    assert_eq!(replaced, 1);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "name,phone\n\"Smith, John\",(555) 1234\nAda,none\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_reorder_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+
+    let mut output = Vec::new();
+    df.reorder_stream(
+        &mut Cursor::new("1,2,3\n4,5,6\n"),
+        &mut output,
+        &["c".to_string(), "a".to_string()],
+    )?;
+    assert_eq!(String::from_utf8(output)?, "c,a,b\n3,1,2\n6,4,5\n");
+
+    let duplicate = ["a".to_string(), "a".to_string()];
+    assert!(df
+        .reorder_stream(&mut Cursor::new(""), &mut Vec::new(), &duplicate)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_hstack_stream() -> Result<(), Box<dyn Error>> {
+    let mut left = DataFrame::new("left".to_string());
+    left.headers = vec!["id".to_string()];
+    let mut right = DataFrame::new("right".to_string());
+    right.headers = vec!["name".to_string(), "age".to_string()];
+
+    let mut output = Vec::new();
+    left.hstack_stream(
+        &right,
+        &mut Cursor::new("1\n2\n"),
+        &mut Cursor::new("ada,36\nalan,41\n"),
+        &mut output,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,age\n1,ada,36\n2,alan,41\n"
+    );
+
+    let result = left.hstack_stream(
+        &right,
+        &mut Cursor::new("1\n2\n"),
+        &mut Cursor::new("ada,36\n"),
+        &mut Vec::new(),
+    );
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_index_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["name".to_string()];
+
+    let mut output = Vec::new();
+    df.index_stream(&mut Cursor::new("ada\nalan\n"), &mut output, "row_id", 0)?;
+    assert_eq!(String::from_utf8(output)?, "row_id,name\n0,ada\n1,alan\n");
+
+    assert!(df
+        .index_stream(&mut Cursor::new(""), &mut Vec::new(), "name", 1)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_reverse_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "name".to_string()];
+
+    let mut output = Vec::new();
+    df.reverse_stream(&mut Cursor::new("1,a\r\n2,b\n\n3,c"), &mut output)?;
+    assert_eq!(String::from_utf8(output)?, "id,name\n3,c\n2,b\n1,a\n");
+
+    // Quoted fields keep their line breaks and commas.
+    let mut output = Vec::new();
+    df.reverse_stream(
+        &mut Cursor::new("1,\"Smith, J\"\n2,\"two\nlines\"\n3,c\n"),
+        &mut output,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name\n3,c\n2,\"two\nlines\"\n1,\"Smith, J\"\n"
+    );
+
+    // Spans many records and starts after a header line.
+    let rows: Vec<String> = (0..20_000).map(|i| format!("{},row{}", i, i)).collect();
+    let mut input = Cursor::new(format!("id,name\n{}\n", rows.join("\n")));
+    input.set_position("id,name\n".len() as u64);
+    let mut output = Vec::new();
+    df.reverse_stream(&mut input, &mut output)?;
+
+    let output = String::from_utf8(output)?;
+    let mut lines: Vec<&str> = output.lines().skip(1).collect();
+    lines.reverse();
+    assert_eq!(lines, rows);
+    Ok(())
+}
+
+#[test]
+fn test_shuffle_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string()];
+    let rows: String = (0..50).map(|i| format!("{}\n", i)).collect();
+    let shuffle = |seed| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.shuffle_stream(&mut Cursor::new(&rows), &mut output, Some(seed))?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    let first = shuffle(42)?;
+    assert_eq!(first, shuffle(42)?);
+    assert_ne!(first, shuffle(7)?);
+    assert!(first.starts_with("id\n"));
+
+    let mut ids: Vec<usize> = first.lines().skip(1).map(|l| l.parse().unwrap()).collect();
+    assert_ne!(ids, (0..50).collect::<Vec<_>>());
+    ids.sort();
+    assert_eq!(ids, (0..50).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_merge_sorted_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("merged".to_string());
+    df.headers = vec!["id".to_string(), "source".to_string()];
+
+    let mut inputs = vec![
+        Cursor::new("1,a\n5,a\n10,a\n"),
+        Cursor::new("2,b\n5,b\n"),
+        Cursor::new(""),
+        Cursor::new("3,c\n"),
+    ];
+    let mut output = Vec::new();
+    df.merge_sorted_stream(&mut inputs, &mut output, "id")?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,source\n1,a\n2,b\n3,c\n5,a\n5,b\n10,a\n"
+    );
+
+    let mut unsorted = vec![Cursor::new("2,a\n1,a\n")];
+    assert!(df
+        .merge_sorted_stream(&mut unsorted, &mut Vec::new(), "id")
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_dedupe_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "status".to_string()];
+    let rows = "1,new\n2,new\n1,paid\n2,new\n3,new\n1,shipped\n";
+    let key = ["id".to_string()];
+
+    let mut output = Vec::new();
+    let removed = df.dedupe_stream(&mut Cursor::new(rows), &mut output, &[], &DedupeKeep::First)?;
+    assert_eq!(removed, 1);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,status\n1,new\n2,new\n1,paid\n3,new\n1,shipped\n"
+    );
+
+    let mut output = Vec::new();
+    df.dedupe_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        &key,
+        &DedupeKeep::First,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,status\n1,new\n2,new\n3,new\n"
+    );
+
+    let mut output = Vec::new();
+    let removed = df.dedupe_stream(&mut Cursor::new(rows), &mut output, &key, &DedupeKeep::Last)?;
+    assert_eq!(removed, 3);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,status\n2,new\n3,new\n1,shipped\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_column_type_cast() -> Result<(), Box<dyn Error>> {
+    assert_eq!(ColumnType::Int.cast(" 42 "), Some("42".to_string()));
+    assert_eq!(ColumnType::Int.cast("3.0"), Some("3".to_string()));
+    assert_eq!(ColumnType::Int.cast("3.5"), None);
+    assert_eq!(ColumnType::Float.cast("2"), Some("2.0".to_string()));
+    assert_eq!(ColumnType::Bool.cast("Yes"), Some("true".to_string()));
+    assert_eq!(ColumnType::Date.cast("31/01/2024"), None);
+    assert_eq!(
+        parse_cast_spec("a:b:int")?,
+        ("a:b".to_string(), ColumnType::Int)
+    );
+    assert!(parse_cast_spec("price").is_err());
+    assert!(parse_cast_spec("price:money").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cast_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "price".to_string()];
+    let rows = "1,2.5\n2,n/a\n3,\n";
+    let casts = vec![
+        ("id".to_string(), ColumnType::Int),
+        ("price".to_string(), ColumnType::Float),
+    ];
+
+    let mut output = Vec::new();
+    let failures = df.cast_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        &casts,
+        &CastOnError::Null,
+    )?;
+    assert_eq!(String::from_utf8(output)?, "id,price\n1,2.5\n2,\n3,\n");
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].line, 3);
+    assert_eq!(failures[0].value, "n/a");
+
+    let mut output = Vec::new();
+    df.cast_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        &casts,
+        &CastOnError::Skip,
+    )?;
+    assert_eq!(String::from_utf8(output)?, "id,price\n1,2.5\n3,\n");
+
+    let result = df.cast_stream(
+        &mut Cursor::new(rows),
+        &mut Vec::new(),
+        &casts,
+        &CastOnError::Fail,
+    );
+    assert!(result.unwrap_err().to_string().contains("Line 3"));
+
+    // Lines are counted in the file, so a quoted line break moves later rows down.
+    df.headers = vec!["id".to_string(), "note".to_string()];
+    let failures = df.cast_stream(
+        &mut Cursor::new("1,\"two\nlines\"\nx,\n"),
+        &mut Vec::new(),
+        &[("id".to_string(), ColumnType::Int)],
+        &CastOnError::Null,
+    )?;
+    assert_eq!(failures[0].line, 4);
+    Ok(())
+}
+
+#[test]
+fn test_reformat_date() {
+    assert_eq!(
+        reformat_date("31/01/2024", "%d/%m/%Y", "%Y-%m-%d"),
+        Some("2024-01-31".to_string())
+    );
+    assert_eq!(
+        reformat_date("2024-01-31 14:05", "%Y-%m-%d %H:%M", "%d.%m.%Y %Hh"),
+        Some("31.01.2024 14h".to_string())
+    );
+    assert_eq!(
+        reformat_date("2024-01-31", "%Y-%m-%d", "%Y-%m-%dT%H:%M:%S"),
+        Some("2024-01-31T00:00:00".to_string())
+    );
+    assert_eq!(reformat_date("2024-13-01", "%Y-%m-%d", "%Y"), None);
+    assert_eq!(reformat_date("2024-01-31", "%Y-%m-%d", "%z"), None);
+}
+
+#[test]
+fn test_dateformat_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "created_at".to_string()];
+    let rows = "1,31/01/2024\n2,\n3,yesterday\n";
+
+    let mut output = Vec::new();
+    let failures = df.dateformat_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        "created_at",
+        "%d/%m/%Y",
+        "%Y-%m-%d",
+        &CastOnError::Skip,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,created_at\n1,2024-01-31\n2,\n"
+    );
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].line, 4);
+
+    let invalid_format = df.dateformat_stream(
+        &mut Cursor::new(rows),
+        &mut Vec::new(),
+        "created_at",
+        "%d/%m/%Q",
+        "%Y",
+        &CastOnError::Null,
+    );
+    assert!(invalid_format.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_explode_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "tags".to_string(), "owner".to_string()];
+
+    let mut output = Vec::new();
+    df.explode_stream(
+        &mut Cursor::new("1,red; blue,ada\n2,,alan\n3,green,grace\n"),
+        &mut output,
+        "tags",
+        ";",
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,tags,owner\n1,red,ada\n1,blue,ada\n2,,alan\n3,green,grace\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_transform_stream() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["name".to_string(), "email".to_string()];
+    let rows = "Ada,Ada@Example.COM\n";
+
+    let mut output = Vec::new();
+    df.transform_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        &["email".to_string()],
+        TextCase::Lower,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "name,email\nAda,ada@example.com\n"
+    );
+
+    let mut output = Vec::new();
+    let columns = ["name".to_string(), "email".to_string()];
+    df.transform_stream(
+        &mut Cursor::new(rows),
+        &mut output,
+        &columns,
+        TextCase::Upper,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "name,email\nADA,ADA@EXAMPLE.COM\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ragged_row_policies() -> Result<(), Box<dyn Error>> {
+    let input = "1,Ada\n2\n3,Bob,extra\n";
+    let run = |on_ragged: RaggedPolicy| -> Result<String, Box<dyn Error>> {
+        let mut df = DataFrame::new("test".to_string()).with_dialect(Dialect {
+            on_ragged,
+            ..Dialect::default()
+        });
+        df.headers = vec!["id".to_string(), "name".to_string()];
+        let mut output = Vec::new();
+        df.select_stream(
+            &mut Cursor::new(input),
+            &mut output,
+            &["id".to_string(), "name".to_string()],
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    let err = run(RaggedPolicy::Error).unwrap_err();
+    assert!(err.to_string().contains("Line 3"));
+    assert_eq!(run(RaggedPolicy::Pad)?, "id,name\n1,Ada\n2,\n3,Bob\n");
+    assert_eq!(run(RaggedPolicy::Truncate)?, "id,name\n1,Ada\n3,Bob\n");
+    assert_eq!(run(RaggedPolicy::Skip)?, "id,name\n1,Ada\n");
+
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "name".to_string()];
+    let err = df
+        .reverse_stream(&mut Cursor::new("1,a,extra\n"), &mut Vec::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("Line 2"));
+
+    df.dialect.on_ragged = RaggedPolicy::Pad;
+    let mut output = Vec::new();
+    df.reverse_stream(&mut Cursor::new(input), &mut output)?;
+    assert_eq!(String::from_utf8(output)?, "id,name\n3,Bob\n2,\n1,Ada\n");
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_headers() -> Result<(), Box<dyn Error>> {
+    let mut file = NamedTempFile::new()?;
+    writeln!(file, "id,name,name,name_2\n1,a,b,c")?;
+
+    let mut df = DataFrame::new("test".to_string());
+    df.read_headers(file.path())?;
+    assert_eq!(df.headers, vec!["id", "name", "name_3", "name_2"]);
+    assert_eq!(df.header_indices.get("name_3"), Some(&2));
+
+    let mut df = DataFrame::new("test".to_string()).with_dialect(Dialect {
+        on_duplicate_headers: DuplicateHeaders::Error,
+        ..Dialect::default()
+    });
+    let err = df.read_headers(file.path()).unwrap_err();
+    assert!(err.to_string().contains("Duplicate header 'name'"));
+    Ok(())
+}
+
 #[test]
 fn test_join_stream() -> Result<(), Box<dyn Error>> {
     let mut df = DataFrame::new("test".to_string());
@@ -140,25 +1013,728 @@ fn test_join_stream() -> Result<(), Box<dyn Error>> {
     let mut output = Vec::new();
 
     df.join_stream(
-        &mut left_input,
-        &mut right_input,
+        &mut left_input,
+        &mut right_input,
+        &mut output,
+        &[("id", "id")],
+        &JoinType::Inner,
+        &JoinOptions::default(),
+    )?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,age\n1,Alice,30\n2,Bob,25\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_join_on_composite_key() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["year".to_string(), "month".to_string(), "sales".to_string()];
+    let mut left_input = Cursor::new("year,month,sales\n2024,1,10\n2024,2,20\n2025,1,30\n");
+    let mut right_input = Cursor::new("y,m,target\n2024,2,25\n2025,1,28\n2025,2,40\n");
+    let mut output = Vec::new();
+
+    df.join_stream(
+        &mut left_input,
+        &mut right_input,
+        &mut output,
+        &[("year", "y"), ("month", "m")],
+        &JoinType::Full,
+        &JoinOptions::default(),
+    )?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "year,month,sales,target\n2024,1,10,\n2024,2,20,25\n2025,1,30,28\n,,,40\n"
+    );
+    Ok(())
+}
+
+fn setup_dataframe() -> DataFrame {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["id".to_string(), "name".to_string()];
+    df
+}
+
+#[test]
+fn test_join_numeric_keys() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let join = |options: &JoinOptions| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("id,name\n001,Alice\n2.50,Bob\nx,Carol\n"),
+            &mut Cursor::new("id,age\n1,30\n2.5,25\nx,41\n"),
+            &mut output,
+            &[("id", "id")],
+            &JoinType::Inner,
+            options,
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(join(&JoinOptions::default())?, "id,name,age\nx,Carol,41\n");
+    assert_eq!(
+        join(&JoinOptions {
+            numeric_keys: true,
+            ..JoinOptions::default()
+        })?,
+        "id,name,age\n001,Alice,30\n2.50,Bob,25\nx,Carol,41\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_join_ignore_case() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let mut output = Vec::new();
+    df.join_stream(
+        &mut Cursor::new("id,name\nABC123,Alice\nxyz,Bob\n"),
+        &mut Cursor::new("id,age\nabc123,30\nXyZ,25\nother,41\n"),
+        &mut output,
+        &[("id", "id")],
+        &JoinType::Full,
+        &JoinOptions {
+            ignore_case: true,
+            ..JoinOptions::default()
+        },
+    )?;
+
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,age\nABC123,Alice,30\nxyz,Bob,25\n,,41\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_join_column_collisions() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let join = |options: &JoinOptions| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("id,name\n1,Alice\n"),
+            &mut Cursor::new("id,name,age\n1,Ally,30\n"),
+            &mut output,
+            &[("id", "id")],
+            &JoinType::Inner,
+            options,
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        join(&JoinOptions::default())?,
+        "id,name,name_2,age\n1,Alice,Ally,30\n"
+    );
+    assert_eq!(
+        join(&JoinOptions {
+            left_suffix: "_left".to_string(),
+            right_suffix: "_right".to_string(),
+            ..JoinOptions::default()
+        })?,
+        "id,name_left,name_right,age\n1,Alice,Ally,30\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_spill_join_matches_hash_join() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let left: String = (0..50).fold("id,name\n".to_string(), |acc, i| {
+        acc + &format!("{},n{}\n", i % 20, i)
+    });
+    let right: String = (5..30).fold("id,age\n".to_string(), |acc, i| {
+        acc + &format!("{},{}\n", i, i * 2)
+    });
+    let join = |options: &JoinOptions| -> Result<Vec<String>, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new(left.as_str()),
+            &mut Cursor::new(right.as_str()),
+            &mut output,
+            &[("id", "id")],
+            &JoinType::Full,
+            options,
+        )?;
+        let mut lines: Vec<String> = String::from_utf8(output)?
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        lines[1..].sort();
+        Ok(lines)
+    };
+
+    let spilled = join(&JoinOptions {
+        strategy: JoinStrategy::Spill,
+        partitions: 4,
+        ..JoinOptions::default()
+    })?;
+    assert_eq!(spilled, join(&JoinOptions::default())?);
+    assert_eq!(spilled[0], "id,name,age");
+    assert_eq!(spilled.len(), 1 + 50 + 10);
+    Ok(())
+}
+
+#[test]
+fn test_merge_join_sorted_inputs() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let merge = JoinOptions {
+        strategy: JoinStrategy::Merge,
+        ..JoinOptions::default()
+    };
+    let mut output = Vec::new();
+    df.join_stream(
+        &mut Cursor::new("id,name\n1,Alice\n2,Bob\n2,Bea\n4,Dan\n10,Ed\n"),
+        &mut Cursor::new("id,age\n2,25\n2,26\n3,35\n10,50\n"),
+        &mut output,
+        &[("id", "id")],
+        &JoinType::Full,
+        &merge,
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,age\n1,Alice,\n2,Bob,25\n2,Bob,26\n2,Bea,25\n2,Bea,26\n,,35\n4,Dan,\n10,Ed,50\n"
+    );
+
+    let err = df
+        .join_stream(
+            &mut Cursor::new("id,name\n2,Bob\n1,Alice\n"),
+            &mut Cursor::new("id,age\n1,30\n"),
+            &mut Vec::new(),
+            &[("id", "id")],
+            &JoinType::Inner,
+            &merge,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("left input is not sorted"));
+
+    let err = df
+        .join_stream(
+            &mut Cursor::new("id,name\n2,Bob\n,Nobody\n"),
+            &mut Cursor::new("id,age\n1,30\n"),
+            &mut Vec::new(),
+            &[("id", "id")],
+            &JoinType::Inner,
+            &merge,
+        )
+        .unwrap_err();
+    assert!(err.to_string().ends_with("'' follows '2'"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn test_join_select_columns() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let join = |select: &[&str], skip_missing_select: bool| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("id,name\n1,Alice\n2,Bob\n"),
+            &mut Cursor::new("id,age,city\n1,30,Oslo\n3,41,Rome\n"),
+            &mut output,
+            &[("id", "id")],
+            &JoinType::Full,
+            &JoinOptions {
+                select: select.iter().map(|s| s.to_string()).collect(),
+                skip_missing_select,
+                ..JoinOptions::default()
+            },
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        join(&["city", "name"], false)?,
+        "city,name\nOslo,Alice\n,Bob\nRome,\n"
+    );
+    assert!(join(&["missing"], false).is_err());
+    assert_eq!(
+        join(&["missing", "age", "name"], true)?,
+        "age,name\n30,Alice\n,Bob\n41,\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_asof_join() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("readings".to_string());
+    df.headers = vec![
+        "sensor".to_string(),
+        "time".to_string(),
+        "value".to_string(),
+    ];
+    let join = |tolerance: Option<f64>| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new(
+                "sensor,time,value\n\
+                 a,2024-01-01 00:00:05,1\n\
+                 b,2024-01-01 00:00:06,2\n\
+                 a,2024-01-01 00:01:00,3\n\
+                 c,2024-01-01 00:02:00,4\n",
+            ),
+            &mut Cursor::new(
+                "sensor,time,status\n\
+                 a,2024-01-01 00:00:00,ok\n\
+                 b,2024-01-01 00:00:04,hot\n\
+                 a,2024-01-01 00:00:07,late\n",
+            ),
+            &mut output,
+            &[("sensor", "sensor"), ("time", "time")],
+            &JoinType::Asof,
+            &JoinOptions {
+                tolerance,
+                ..JoinOptions::default()
+            },
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        join(None)?,
+        "sensor,time,value,status\n\
+         a,2024-01-01 00:00:05,1,ok\n\
+         b,2024-01-01 00:00:06,2,hot\n\
+         a,2024-01-01 00:01:00,3,late\n\
+         c,2024-01-01 00:02:00,4,\n"
+    );
+    assert_eq!(
+        join(Some(10.0))?,
+        "sensor,time,value,status\n\
+         a,2024-01-01 00:00:05,1,ok\n\
+         b,2024-01-01 00:00:06,2,hot\n\
+         a,2024-01-01 00:01:00,3,\n\
+         c,2024-01-01 00:02:00,4,\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_fuzzy_join() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("customers".to_string());
+    df.headers = vec!["name".to_string(), "city".to_string()];
+    let join = |fuzzy: FuzzyThreshold| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("name,city\nJon Smith,Oslo\nAnna Berg,Rome\nZed,Lima\n"),
+            &mut Cursor::new("name,plan\nJohn Smith,gold\nAnna Berg,free\n"),
+            &mut output,
+            &[("name", "name")],
+            &JoinType::Left,
+            &JoinOptions {
+                fuzzy: Some(fuzzy),
+                ..JoinOptions::default()
+            },
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        join(FuzzyThreshold::Distance(1))?,
+        "name,city,plan,match_score\n\
+         Jon Smith,Oslo,gold,0.900\n\
+         Anna Berg,Rome,free,1.000\n\
+         Zed,Lima,,\n"
+    );
+    assert_eq!(
+        join(FuzzyThreshold::Similarity(0.95))?,
+        "name,city,plan,match_score\n\
+         Jon Smith,Oslo,,\n\
+         Anna Berg,Rome,free,1.000\n\
+         Zed,Lima,,\n"
+    );
+
+    // The best match is ranked by the measure of the threshold: `ab` takes fewer
+    // edits, but `abcdxyz` has more in common with `abcd`.
+    let closest = |fuzzy: FuzzyThreshold| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("name,city\nabcd,Oslo\n"),
+            &mut Cursor::new("name,plan\nab,free\nabcdxyz,gold\n"),
+            &mut output,
+            &[("name", "name")],
+            &JoinType::Inner,
+            &JoinOptions {
+                fuzzy: Some(fuzzy),
+                ..JoinOptions::default()
+            },
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+    assert_eq!(
+        closest(FuzzyThreshold::Distance(3))?,
+        "name,city,plan,match_score\nabcd,Oslo,free,0.500\n"
+    );
+    assert_eq!(
+        closest(FuzzyThreshold::Similarity(0.5))?,
+        "name,city,plan,match_score\nabcd,Oslo,gold,0.571\n"
+    );
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    Ok(())
+}
+
+#[test]
+fn test_join_null_keys() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let join = |options: &JoinOptions| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new("id,name\n,Bob\n1,Alice\n"),
+            &mut Cursor::new("id,age\n,25\n,26\n1,30\n"),
+            &mut output,
+            &[("id", "id")],
+            &JoinType::Full,
+            options,
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        join(&JoinOptions::default())?,
+        "id,name,age\n,Bob,\n1,Alice,30\n,,25\n,,26\n"
+    );
+    assert_eq!(
+        join(&JoinOptions {
+            strategy: JoinStrategy::Merge,
+            ..JoinOptions::default()
+        })?,
+        "id,name,age\n,Bob,\n,,25\n,,26\n1,Alice,30\n"
+    );
+    assert_eq!(
+        join(&JoinOptions {
+            null_keys_match: true,
+            ..JoinOptions::default()
+        })?,
+        "id,name,age\n,Bob,25\n,Bob,26\n1,Alice,30\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_self_join_with_aliases() -> Result<(), Box<dyn Error>> {
+    let mut file = NamedTempFile::new()?;
+    writeln!(file, "id,name,manager\n1,Ada,\n2,Bob,1\n3,Cy,1")?;
+    let mut df = DataFrame::new("staff".to_string());
+    df.read_headers(file.path())?;
+
+    let mut output = Vec::new();
+    df.join_stream(
+        &mut std::io::BufReader::new(std::fs::File::open(file.path())?),
+        &mut std::io::BufReader::new(std::fs::File::open(file.path())?),
         &mut output,
-        "id",
-        "id",
+        &[("manager", "id")],
         &JoinType::Inner,
+        &JoinOptions {
+            left_alias: Some("e".to_string()),
+            right_alias: Some("m".to_string()),
+            select: vec!["e.name".to_string(), "m.name".to_string()],
+            ..JoinOptions::default()
+        },
     )?;
 
     assert_eq!(
         String::from_utf8(output)?,
-        "id,name,age\n1,Alice,30\n2,Bob,25\n"
+        "e.name,m.name\nBob,Ada\nCy,Ada\n"
     );
     Ok(())
 }
 
-fn setup_dataframe() -> DataFrame {
-    let mut df = DataFrame::new("test".to_string());
-    df.headers = vec!["id".to_string(), "name".to_string()];
-    df
+#[test]
+fn test_range_join() -> Result<(), Box<dyn Error>> {
+    let left = "id,ts\n1,5\n1,12\n2,7\n3,\n";
+    let mut file = NamedTempFile::new()?;
+    write!(file, "{}", left)?;
+    let mut df = DataFrame::new("events".to_string());
+    df.read_headers(file.path())?;
+    let right = "id,start,end,label\n1,0,10,a\n1,5,20,b\n2,8,9,c\n4,0,1,d\n";
+    let range = RangeCondition {
+        column: "ts".to_string(),
+        start: "start".to_string(),
+        end: "end".to_string(),
+    };
+
+    let run = |keys: &[(&str, &str)], join_type: JoinType| -> Result<String, Box<dyn Error>> {
+        let mut output = Vec::new();
+        df.join_stream(
+            &mut Cursor::new(left),
+            &mut Cursor::new(right),
+            &mut output,
+            keys,
+            &join_type,
+            &JoinOptions {
+                range: Some(range.clone()),
+                ..JoinOptions::default()
+            },
+        )?;
+        Ok(String::from_utf8(output)?)
+    };
+
+    assert_eq!(
+        run(&[("id", "id")], JoinType::Inner)?,
+        "id,ts,start,end,label\n1,5,0,10,a\n1,5,5,20,b\n1,12,5,20,b\n"
+    );
+    assert_eq!(
+        run(&[("id", "id")], JoinType::Full)?,
+        "id,ts,start,end,label\n1,5,0,10,a\n1,5,5,20,b\n1,12,5,20,b\n2,7,,,\n3,,,,\n\
+         ,,8,9,c\n,,0,1,d\n"
+    );
+    assert_eq!(
+        run(&[], JoinType::Inner)?,
+        "id,ts,id_2,start,end,label\n1,5,1,0,10,a\n1,5,1,5,20,b\n1,12,1,5,20,b\n\
+         2,7,1,0,10,a\n2,7,1,5,20,b\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compression_round_trip() -> Result<(), Box<dyn Error>> {
+    let csv = b"id,name\n1,Alice\n2,Bob\n";
+    for (file_name, compression) in [
+        ("a.csv.gz", Compression::Gzip),
+        ("a.csv.zst", Compression::Zstd),
+    ] {
+        assert_eq!(
+            Compression::from_path(Path::new(file_name)),
+            Some(compression)
+        );
+        let compressed = compression.compress(&mut &csv[..], Vec::new())?;
+        assert_ne!(compressed, csv);
+
+        let mut decompressed = Vec::new();
+        compression
+            .decoder(&compressed[..])?
+            .read_to_end(&mut decompressed)?;
+        assert_eq!(decompressed, csv);
+    }
+    assert_eq!(Compression::from_path(Path::new("a.csv")), None);
+    Ok(())
+}
+
+#[test]
+fn test_write_json() -> Result<(), Box<dyn Error>> {
+    let csv = "id,name\n1,\"Smith, \"\"Al\"\"\"\n2,Bob\n";
+    let dialect = Dialect::default();
+
+    let mut output = Vec::new();
+    write_json(csv.as_bytes(), &mut output, OutputFormat::Json, &dialect)?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "[\n{\"id\":\"1\",\"name\":\"Smith, \\\"Al\\\"\"},\n{\"id\":\"2\",\"name\":\"Bob\"}\n]\n"
+    );
+
+    let mut output = Vec::new();
+    write_json(csv.as_bytes(), &mut output, OutputFormat::Jsonl, &dialect)?;
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[1]["name"], "Bob");
+
+    let mut output = Vec::new();
+    write_json(
+        "id,name\n".as_bytes(),
+        &mut output,
+        OutputFormat::Json,
+        &dialect,
+    )?;
+    assert_eq!(String::from_utf8(output)?, "[\n]\n");
+    Ok(())
+}
+
+#[test]
+fn test_write_latex() -> Result<(), Box<dyn Error>> {
+    let csv = "id,name,score\n1,R&D_50%,2.5\n2,\"a\\b {x}\",\n";
+    let mut output = Vec::new();
+    write_latex(csv.as_bytes(), &mut output, &Dialect::default())?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "\\begin{tabular}{rlr}\n\\toprule\nid & name & score \\\\\n\\midrule\n\
+         1 & R\\&D\\_50\\% & 2.5 \\\\\n\
+         2 & a\\textbackslash{}b \\{x\\} &  \\\\\n\
+         \\bottomrule\n\\end{tabular}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_write_html() -> Result<(), Box<dyn Error>> {
+    let csv = "id,name\n1,<b>Al & Co</b>\n2,Bob\n3,Cy\n";
+    let dialect = Dialect::default();
+
+    let mut output = Vec::new();
+    write_html(csv.as_bytes(), &mut output, &dialect, None)?;
+    let html = String::from_utf8(output)?;
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<th>id</th>\n<th>name</th>"));
+    assert!(html.contains("<td class=\"num\">1</td>\n<td>&lt;b&gt;Al &amp; Co&lt;/b&gt;</td>"));
+    assert!(html.contains("<td>Cy</td>"));
+    assert!(!html.contains("Showing"));
+
+    let mut output = Vec::new();
+    write_html(csv.as_bytes(), &mut output, &dialect, Some(2))?;
+    let html = String::from_utf8(output)?;
+    assert!(html.contains("<td>Bob</td>"));
+    assert!(!html.contains("<td>Cy</td>"));
+    assert!(html.contains("Showing 2 of 3 rows."));
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_to_csv() -> Result<(), Box<dyn Error>> {
+    let df = DataFrame::new("api".to_string());
+    let mut input = Cursor::new(
+        "{\"id\":1,\"name\":\"Ann, B\",\"tags\":[\"a\"]}\n\n{\"id\":2,\"active\":true,\"name\":null}\n",
+    );
+    let mut output = Vec::new();
+    let rows = df.jsonl_to_csv(&mut input, &mut output)?;
+    assert_eq!(rows, 2);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,tags,active\n1,\"Ann, B\",\"[\"\"a\"\"]\",\n2,,,true\n"
+    );
+
+    let mut input = Cursor::new("[1, 2]\n");
+    assert!(df.jsonl_to_csv(&mut input, &mut Vec::new()).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_round_trip() -> Result<(), Box<dyn Error>> {
+    let mut csv_file = NamedTempFile::new()?;
+    write!(
+        csv_file,
+        "id,score,active,name\n1,2.5,true,\"Ann, B\"\n2,,FALSE,\n"
+    )?;
+    let parquet_file = NamedTempFile::new()?;
+    let rows =
+        csvg::csv::write_parquet(csv_file.path(), parquet_file.reopen()?, &Dialect::default())?;
+    assert_eq!(rows, 2);
+
+    let df = DataFrame::new("parquet".to_string());
+    let mut output = Vec::new();
+    df.parquet_to_csv(parquet_file.reopen()?, &mut output)?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,score,active,name\n1,2.5,true,\"Ann, B\"\n2,,false,\n"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "avro")]
+#[test]
+fn test_avro_to_csv() -> Result<(), Box<dyn Error>> {
+    use apache_avro::types::{Record, Value};
+    use apache_avro::{Schema, Writer};
+
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "order", "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "note", "type": ["null", "string"]},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "tags", "type": {"type": "array", "items": "string"}}
+        ]}"#,
+    )?;
+    let mut writer = Writer::new(&schema, Vec::new())?;
+    for (id, note, day, tags) in [(1, Some("a, b"), 19_000, vec!["x"]), (2, None, 0, vec![])] {
+        let mut record = Record::new(&schema).ok_or("not a record schema")?;
+        record.put("id", id as i64);
+        record.put(
+            "note",
+            Value::Union(
+                note.is_some() as u32,
+                Box::new(note.map_or(Value::Null, |n| Value::String(n.to_string()))),
+            ),
+        );
+        record.put("day", Value::Date(day));
+        record.put(
+            "tags",
+            Value::Array(
+                tags.into_iter()
+                    .map(|t| Value::String(t.to_string()))
+                    .collect(),
+            ),
+        );
+        writer.append_value(record)?;
+    }
+    let bytes = writer.into_inner()?;
+
+    let df = DataFrame::new("orders".to_string());
+    let mut output = Vec::new();
+    let rows = df.avro_to_csv(bytes.as_slice(), &mut output)?;
+    assert_eq!(rows, 2);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,note,day,tags\n1,\"a, b\",2022-01-08,\"[\"\"x\"\"]\"\n2,,1970-01-01,[]\n"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_write_arrow_stream() -> Result<(), Box<dyn Error>> {
+    use arrow_schema::DataType;
+
+    let mut csv_file = NamedTempFile::new()?;
+    write!(
+        csv_file,
+        "id,score,active,name\n1,2.5,true,Ann\n2,,false,\n"
+    )?;
+    let mut output = Vec::new();
+    let rows = csvg::csv::write_arrow(csv_file.path(), &mut output, &Dialect::default())?;
+    assert_eq!(rows, 2);
+
+    let reader = arrow_ipc::reader::StreamReader::try_new(Cursor::new(output), None)?;
+    let types: Vec<DataType> = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.data_type().clone())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            DataType::Int64,
+            DataType::Float64,
+            DataType::Boolean,
+            DataType::Utf8
+        ]
+    );
+    let batches = reader.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+    assert_eq!(batches[0].column(1).null_count(), 1);
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_sqlite_round_trip() -> Result<(), Box<dyn Error>> {
+    let dir = TempDir::new()?;
+    let db = dir.path().join("results.db");
+    let mut csv_file = NamedTempFile::new()?;
+    write!(
+        csv_file,
+        "id,score,active,name\n1,2.5,true,\"Ann, B\"\n2,,false,\n"
+    )?;
+
+    let rows = csvg::csv::write_sqlite(csv_file.path(), &db, "people", &Dialect::default(), false)?;
+    assert_eq!(rows, 2);
+    assert!(
+        csvg::csv::write_sqlite(csv_file.path(), &db, "people", &Dialect::default(), false)
+            .is_err()
+    );
+    csvg::csv::write_sqlite(csv_file.path(), &db, "people", &Dialect::default(), true)?;
+
+    let df = DataFrame::new("people".to_string());
+    let mut output = Vec::new();
+    assert_eq!(df.sqlite_to_csv(&db, "people", &mut output)?, 2);
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,score,active,name\n1,2.5,1,\"Ann, B\"\n2,,0,\n"
+    );
+    Ok(())
 }
 
 #[test]
@@ -171,9 +1747,9 @@ fn test_inner_join() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Inner,
+        &JoinOptions::default(),
     )?;
     assert_eq!(
         String::from_utf8(output)?,
@@ -192,9 +1768,9 @@ fn test_left_outer_join() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Left,
+        &JoinOptions::default(),
     )?;
     assert_eq!(
         String::from_utf8(output)?,
@@ -213,14 +1789,31 @@ fn test_right_outer_join() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Right,
+        &JoinOptions::default(),
     )?;
     assert_eq!(
         String::from_utf8(output)?,
         "id,name,age\n1,Alice,30\n2,Bob,25\n,,35\n"
     );
+
+    let mut output = Vec::new();
+    df.join_stream(
+        &mut Cursor::new("id,name\n1,Alice\n2,Bob\n3,Charlie"),
+        &mut Cursor::new("id,age\n1,30\n2,25\n4,35"),
+        &mut output,
+        &[("id", "id")],
+        &JoinType::Right,
+        &JoinOptions {
+            keep_right_keys: true,
+            ..JoinOptions::default()
+        },
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,age\n1,Alice,30\n2,Bob,25\n4,,35\n"
+    );
     Ok(())
 }
 
@@ -234,9 +1827,9 @@ fn test_full_outer_join() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Full,
+        &JoinOptions::default(),
     )?;
     assert_eq!(
         String::from_utf8(output)?,
@@ -255,9 +1848,9 @@ fn test_join_with_multiple_matches() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Inner,
+        &JoinOptions::default(),
     )?;
     assert_eq!(
         String::from_utf8(output)?,
@@ -276,14 +1869,134 @@ fn test_join_with_empty_inputs() -> Result<(), Box<dyn Error>> {
         &mut left_input,
         &mut right_input,
         &mut output,
-        "id",
-        "id",
+        &[("id", "id")],
         &JoinType::Full,
+        &JoinOptions::default(),
     )?;
     assert_eq!(String::from_utf8(output)?, "id,name,age\n");
     Ok(())
 }
 
+#[test]
+fn test_join_with_quoted_fields() -> Result<(), Box<dyn Error>> {
+    let df = setup_dataframe();
+    let mut left_input = Cursor::new("id,name\n1,\"Smith, John\"\n2,\"Say \"\"hi\"\"\"\n");
+    let mut right_input = Cursor::new("id,address\n1,\"1 Main St\nSpringfield\"\n2,Elm\n");
+    let mut output = Vec::new();
+    df.join_stream(
+        &mut left_input,
+        &mut right_input,
+        &mut output,
+        &[("id", "id")],
+        &JoinType::Inner,
+        &JoinOptions::default(),
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "id,name,address\n1,\"Smith, John\",\"1 Main St\nSpringfield\"\n2,\"Say \"\"hi\"\"\",Elm\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_write_row_quoting() -> Result<(), Box<dyn Error>> {
+    let df = DataFrame::new("test".to_string());
+    let mut output = Vec::new();
+    df.write_row(
+        &mut output,
+        &["plain", "a,b", "say \"hi\"", "two\nlines", ""],
+    )?;
+    df.write_row(&mut output, &[""])?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\n\"\"\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_custom_dialect() -> Result<(), Box<dyn Error>> {
+    let dialect = Dialect {
+        quote: '\'',
+        escape: Some('\\'),
+        ..Dialect::default()
+    };
+    let mut df = DataFrame::new("test".to_string()).with_dialect(dialect.clone());
+    df.headers = vec!["id".to_string(), "name".to_string()];
+
+    let mut output = Vec::new();
+    df.select_stream(
+        &mut Cursor::new("1,'O\\'Brien, Pat'\n2,\"Ada\"\n"),
+        &mut output,
+        &["name".to_string()],
+    )?;
+    assert_eq!(
+        String::from_utf8(output)?,
+        "name\n'O\\'Brien, Pat'\n\"Ada\"\n"
+    );
+
+    let df = df.with_dialect(Dialect {
+        always_quote: true,
+        ..Dialect::default()
+    });
+    let mut output = Vec::new();
+    df.write_row(&mut output, &["1", "say \"hi\""])?;
+    assert_eq!(String::from_utf8(output)?, "\"1\",\"say \"\"hi\"\"\"\n");
+
+    assert!(Dialect {
+        quote: ',',
+        ..Dialect::default()
+    }
+    .validate()
+    .is_err());
+    assert!(dialect.validate().is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_line_endings() -> Result<(), Box<dyn Error>> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(b"id,name\r\n1,Ada\r\n2,Alan\n")?;
+
+    let mut df = DataFrame::new("test".to_string()).with_dialect(Dialect {
+        line_ending: LineEnding::Preserve,
+        ..Dialect::default()
+    });
+    df.read_headers(file.path())?;
+    let mut output = Vec::new();
+    df.select_stream(
+        &mut df.open_rows(file.path())?,
+        &mut output,
+        &["name".to_string()],
+    )?;
+    assert_eq!(String::from_utf8(output)?, "name\r\nAda\r\nAlan\r\n");
+
+    let mut df = DataFrame::new("test".to_string());
+    df.read_headers(file.path())?;
+    let mut output = Vec::new();
+    df.select_stream(
+        &mut df.open_rows(file.path())?,
+        &mut output,
+        &["name".to_string()],
+    )?;
+    assert_eq!(String::from_utf8(output)?, "name\nAda\nAlan\n");
+    Ok(())
+}
+
+#[test]
+fn test_drop_stream_with_quoted_fields() -> Result<(), Box<dyn Error>> {
+    let mut df = DataFrame::new("test".to_string());
+    df.headers = vec!["name".to_string(), "city".to_string()];
+    let mut output = Vec::new();
+    df.drop_stream(
+        &mut Cursor::new("\"Smith, John\",\"New York, NY\"\n"),
+        &mut output,
+        &["city".to_string()],
+    )?;
+    assert_eq!(String::from_utf8(output)?, "name\n\"Smith, John\"\n");
+    Ok(())
+}
+
 #[test]
 fn test_human_readable_bytes() {
     assert_eq!(human_readable_bytes(500), "500.00 B");
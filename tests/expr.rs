@@ -0,0 +1,137 @@
+use csvg::expr::{self, Expr, Value};
+use std::error::Error;
+
+fn headers() -> Vec<String> {
+    vec!["price".to_string(), "qty".to_string(), "name".to_string()]
+}
+
+fn row(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn eval(input: &str, values: &[&str]) -> Result<Value, Box<dyn Error>> {
+    expr::parse(input)?.bind(&headers())?.evaluate(&row(values))
+}
+
+#[test]
+fn test_arithmetic_precedence() -> Result<(), Box<dyn Error>> {
+    assert_eq!(eval("1 + 2 * 3", &[])?, Value::Number(7.0));
+    assert_eq!(eval("(1 + 2) * 3", &[])?, Value::Number(9.0));
+    assert_eq!(eval("10 - 4 - 3", &[])?, Value::Number(3.0));
+    assert_eq!(eval("-2 * 3 % 4", &[])?, Value::Number(-2.0));
+    Ok(())
+}
+
+#[test]
+fn test_column_references() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+        eval("price * qty", &["2.5", "4", "widget"])?,
+        Value::Number(10.0)
+    );
+    assert_eq!(
+        eval("name", &["2.5", "4", "widget"])?,
+        Value::Str("widget".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_null_propagation() -> Result<(), Box<dyn Error>> {
+    assert_eq!(eval("price * qty", &["", "4", "widget"])?, Value::Null);
+    assert_eq!(eval("qty / 0", &["1", "4", "widget"])?, Value::Null);
+    Ok(())
+}
+
+#[test]
+fn test_comparisons() -> Result<(), Box<dyn Error>> {
+    let values = &["10", "9", "widget"];
+    assert_eq!(eval("price > qty", values)?, Value::Bool(true));
+    assert_eq!(eval("price = 10.0", values)?, Value::Bool(true));
+    assert_eq!(eval("name <> 'gadget'", values)?, Value::Bool(true));
+    assert_eq!(eval("name < 'x' and qty >= 9", values)?, Value::Bool(true));
+    assert_eq!(
+        eval("not price > 1 or qty = 1", values)?,
+        Value::Bool(false)
+    );
+    assert_eq!(eval("price = ''", &["", "1", "x"])?, Value::Bool(true));
+    assert_eq!(eval("price > 1", &["", "1", "x"])?, Value::Null);
+    Ok(())
+}
+
+#[test]
+fn test_functions() -> Result<(), Box<dyn Error>> {
+    let values = &["2.71828", "", " Widget "];
+    assert_eq!(
+        eval("upper(trim(name))", values)?,
+        Value::Str("WIDGET".into())
+    );
+    assert_eq!(eval("length(name)", values)?, Value::Number(8.0));
+    assert_eq!(
+        eval("substr(trim(name), 2, 3)", values)?,
+        Value::Str("idg".into())
+    );
+    assert_eq!(eval("round(price, 2)", values)?, Value::Number(2.72));
+    assert_eq!(
+        eval("coalesce(qty, 'none')", values)?,
+        Value::Str("none".into())
+    );
+    assert_eq!(eval("contains(name, 'dg')", values)?, Value::Bool(true));
+    assert_eq!(
+        eval("'#' || trim(name)", values)?,
+        Value::Str("#Widget".into())
+    );
+    assert_eq!(eval("if(qty = null, 0, 1)", values)?, Value::Number(0.0));
+    assert!(expr::parse("nope(1)").is_err());
+    assert!(expr::parse("upper(1, 2)").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_quoted_and_qualified_columns() -> Result<(), Box<dyn Error>> {
+    let headers = vec!["unit price".to_string(), "orders.status".to_string()];
+    let row = row(&["2", "paid"]);
+    let expression = expr::parse("\"unit price\" * 2")?.bind(&headers)?;
+    assert_eq!(expression.evaluate(&row)?, Value::Number(4.0));
+    let expression = expr::parse("orders.status = 'paid'")?.bind(&headers)?;
+    assert!(expression.matches(&row)?);
+    Ok(())
+}
+
+#[test]
+fn test_columns_and_renaming() -> Result<(), Box<dyn Error>> {
+    let expression = expr::parse("orders.total > 10 and upper(orders.status) = 'PAID'")?;
+    assert_eq!(expression.columns(), vec!["orders.total", "orders.status"]);
+    let renamed = expression.rename_columns(&|name| name.trim_start_matches("orders.").to_string());
+    assert_eq!(renamed.columns(), vec!["total", "status"]);
+    assert!(renamed.bind(&headers()).is_err());
+    assert!(expr::parse("1 + 2")?.columns().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_errors() {
+    assert!(expr::parse("1 +").is_err());
+    assert!(expr::parse("(1 + 2").is_err());
+    assert!(expr::parse("'open").is_err());
+    assert!(expr::parse("missing").unwrap().bind(&headers()).is_err());
+    assert!(eval("name * 2", &["1", "2", "widget"]).is_err());
+}
+
+#[test]
+fn test_parse_assignment() -> Result<(), Box<dyn Error>> {
+    let (name, expression) = expr::parse_assignment("total = price * qty")?;
+    assert_eq!(name, "total");
+    assert!(matches!(expression, Expr::Binary(..)));
+    assert!(expr::parse_assignment("price * qty").is_err());
+    assert!(expr::parse_assignment(" = 1").is_err());
+    assert!(expr::parse_assignment("a != b").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_value_display() {
+    assert_eq!(Value::Number(12.0).to_string(), "12");
+    assert_eq!(Value::Number(0.5).to_string(), "0.5");
+    assert_eq!(Value::Null.to_string(), "");
+    assert_eq!(Value::Bool(true).to_string(), "true");
+}
@@ -1,11 +1,21 @@
+use clap::Parser;
 use csvg::{
+    cli::{Cli, Commands, GraphJoinArgs, GraphSubcommands, PathWeight},
     commands::graph::{
-        find_join_columns, find_node, find_shortest_path, update_dataframe_after_join,
+        assign_conditions, build_report, compare_headers, find_columns_to_keep, find_components,
+        find_explicit_path, find_fk_cycles, find_hop_keys, find_hubs, find_join_columns,
+        find_join_order, find_join_tree, find_neighbors, find_node, find_orphans, find_path_via,
+        find_paths, find_references, find_shortest_path, find_weighted_shortest_path,
+        has_independent_branches, join_parents, join_tables_along_path, order_joins_by_size,
+        plan_join, remap_headers, update_dataframe_after_join,
     },
-    csv::DataFrame,
+    config::Config,
+    csv::{DataFrame, JoinOptions},
 };
-use petgraph::graph::UnGraph;
+use petgraph::graph::{NodeIndex, UnGraph};
 use std::collections::HashMap;
+use std::path::Path;
+use tempfile::TempDir;
 
 // Helper function to create a mock graph
 fn create_mock_graph() -> UnGraph<DataFrame, (String, String)> {
@@ -16,6 +26,7 @@ fn create_mock_graph() -> UnGraph<DataFrame, (String, String)> {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![],
+        ..Default::default()
     };
     let df2 = DataFrame {
         name: "table2".to_string(),
@@ -23,6 +34,7 @@ fn create_mock_graph() -> UnGraph<DataFrame, (String, String)> {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![("id".to_string(), "table1".to_string(), "id".to_string())],
+        ..Default::default()
     };
     let df3 = DataFrame {
         name: "table3".to_string(),
@@ -30,6 +42,7 @@ fn create_mock_graph() -> UnGraph<DataFrame, (String, String)> {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![("id".to_string(), "table2".to_string(), "id".to_string())],
+        ..Default::default()
     };
     let n1 = g.add_node(df1);
     let n2 = g.add_node(df2);
@@ -60,6 +73,480 @@ fn test_find_shortest_path() {
     assert_eq!(g[path[2]].name, "table3");
 }
 
+#[test]
+fn test_find_shortest_path_prefers_fewer_rows() {
+    let table = |name: &str, keys: &[&str], rows: u64| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        row_count: Some(rows),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[], 100),
+        table("orders", &["customers", "products"], 1_000_000),
+        table("wishlists", &["customers", "products"], 500),
+        table("products", &[], 50),
+    ]);
+    let start = find_node(&g, "customers").unwrap();
+    let end = find_node(&g, "products").unwrap();
+
+    let path = find_shortest_path(&g, start, end).unwrap();
+    let names: Vec<&str> = path.iter().map(|&n| g[n].name.as_str()).collect();
+    assert_eq!(names, vec!["customers", "wishlists", "products"]);
+}
+
+#[test]
+fn test_find_weighted_shortest_path() {
+    let table = |name: &str, keys: &[&str], rows: u64| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        row_count: Some(rows),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[], 100),
+        table("sales", &["customers", "products"], 1_000_000),
+        table("regions", &["customers"], 10),
+        table("stores", &["regions", "products"], 20),
+        table("products", &[], 50),
+    ]);
+    let start = find_node(&g, "customers").unwrap();
+    let end = find_node(&g, "products").unwrap();
+    let names = |path: Vec<NodeIndex>| -> Vec<String> {
+        path.into_iter().map(|n| g[n].name.clone()).collect()
+    };
+
+    assert_eq!(
+        names(find_weighted_shortest_path(&g, start, end, PathWeight::Joins).unwrap()),
+        vec!["customers", "sales", "products"]
+    );
+    assert_eq!(
+        names(find_weighted_shortest_path(&g, start, end, PathWeight::Rows).unwrap()),
+        vec!["customers", "regions", "stores", "products"]
+    );
+}
+
+#[test]
+fn test_find_components() {
+    let mut g = create_mock_graph();
+    let orphan = g.add_node(DataFrame {
+        name: "audit_log".to_string(),
+        headers: vec!["id".to_string()],
+        ..Default::default()
+    });
+
+    let components = find_components(&g);
+    assert_eq!(
+        components,
+        vec![vec!["table1", "table2", "table3"], vec!["audit_log"],]
+    );
+
+    let start = find_node(&g, "table1").unwrap();
+    let err = find_shortest_path(&g, start, orphan).unwrap_err();
+    assert!(err.to_string().contains("different connected components"));
+}
+
+#[test]
+fn test_find_fk_cycles() {
+    let table = |name: &str, keys: &[(&str, &str)]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|(col, dst)| (col.to_string(), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("orders", &[("invoice_id", "invoices")]),
+        table("invoices", &[("payment_id", "payments")]),
+        table("payments", &[("order_id", "orders")]),
+        table("categories", &[("parent_id", "categories")]),
+        table("refunds", &[("order_id", "orders")]),
+    ]);
+
+    assert_eq!(
+        find_fk_cycles(&g),
+        vec![vec!["categories"], vec!["invoices", "payments", "orders"],]
+    );
+    assert!(find_fk_cycles(&create_mock_graph()).is_empty());
+}
+
+#[test]
+fn test_find_paths() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("orders", &["customers"]),
+        table("items", &["orders", "products"]),
+        table("products", &[]),
+        table("wishlists", &["customers", "products"]),
+    ]);
+    let start = find_node(&g, "customers").unwrap();
+    let end = find_node(&g, "products").unwrap();
+    let names = |paths: Vec<Vec<NodeIndex>>| -> Vec<Vec<String>> {
+        paths
+            .into_iter()
+            .map(|path| path.into_iter().map(|n| g[n].name.clone()).collect())
+            .collect()
+    };
+
+    assert_eq!(
+        names(find_paths(&g, start, end, 5, None)),
+        vec![
+            vec!["customers", "wishlists", "products"],
+            vec!["customers", "orders", "items", "products"],
+        ]
+    );
+    assert_eq!(names(find_paths(&g, start, end, 1, None)).len(), 1);
+    assert_eq!(names(find_paths(&g, start, end, 5, Some(2))).len(), 1);
+}
+
+#[test]
+fn test_find_join_tree() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("products", &[]),
+        table("stores", &[]),
+        table("sales", &["customers", "products", "stores"]),
+        table("reviews", &["customers"]),
+        table("ratings", &["reviews", "products"]),
+        table("audit", &[]),
+    ]);
+    let node = |name: &str| find_node(&g, name).unwrap();
+    let joins = |tables: &[&str]| -> Option<Vec<String>> {
+        let nodes: Vec<NodeIndex> = tables.iter().map(|&t| node(t)).collect();
+        find_join_tree(&g, &nodes).map(|edges| {
+            edges
+                .into_iter()
+                .map(|edge| {
+                    let (src, dst) = g.edge_endpoints(edge).unwrap();
+                    format!("{}-{}", g[src].name, g[dst].name)
+                })
+                .collect()
+        })
+    };
+
+    assert_eq!(
+        joins(&["customers", "products", "stores"]),
+        Some(vec![
+            "sales-customers".to_string(),
+            "sales-products".to_string(),
+            "sales-stores".to_string(),
+        ]),
+        "the tables join through sales rather than pairwise paths"
+    );
+    assert_eq!(
+        joins(&["reviews", "products"]),
+        Some(vec![
+            "ratings-reviews".to_string(),
+            "ratings-products".to_string()
+        ])
+    );
+    assert_eq!(joins(&["sales", "sales"]), Some(vec![]));
+    assert_eq!(joins(&["customers", "audit"]), None);
+}
+
+#[test]
+fn test_build_report() {
+    let table = |name: &str, columns: usize, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: (0..columns).map(|i| format!("c{}", i)).collect(),
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("users", 2, &[]),
+        table("posts", 3, &["users"]),
+        table("comments", 4, &["posts", "users"]),
+        table("tags", 1, &[]),
+    ]);
+
+    let report = build_report(&g, 2);
+    assert_eq!(report.tables, 4);
+    assert_eq!(report.foreign_keys, 3);
+    // users-posts, users-comments and posts-comments are one join apart.
+    assert_eq!(report.average_path_length, Some(1.0));
+    let ranked = |tables: &[csvg::commands::graph::RankedTable]| -> Vec<(String, usize)> {
+        tables.iter().map(|t| (t.table.clone(), t.count)).collect()
+    };
+    assert_eq!(
+        ranked(&report.most_referenced),
+        vec![("users".to_string(), 2), ("posts".to_string(), 1)]
+    );
+    assert_eq!(
+        report.longest_chains,
+        vec![vec!["comments", "posts", "users"]],
+        "chains that are the tail of a longer one are left out"
+    );
+    assert_eq!(
+        ranked(&report.widest_tables),
+        vec![("comments".to_string(), 4), ("posts".to_string(), 3)]
+    );
+}
+
+#[test]
+fn test_find_columns_to_keep() {
+    let table = |name: &str, headers: &[&str], keys: &[(&str, &str)]| DataFrame {
+        name: name.to_string(),
+        headers: headers.iter().map(|h| h.to_string()).collect(),
+        foreign_keys: keys
+            .iter()
+            .map(|(column, dst)| (column.to_string(), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let users = table(
+        "users",
+        &["id", "name", "region_id"],
+        &[("region_id", "regions")],
+    );
+    let orders = table(
+        "orders",
+        &["id", "user_id", "total"],
+        &[("user_id", "users")],
+    );
+    let items = table("items", &["order_id", "sku"], &[("order_id", "orders")]);
+    let regions = table("regions", &["id", "label"], &[]);
+    let select = vec!["name".to_string(), "sku".to_string()];
+
+    assert_eq!(
+        find_columns_to_keep(&users, &orders, &[&items, &regions], &select),
+        vec!["name", "sku", "region_id", "id_orders"],
+        "the selected columns, then keys of later joins, named after their table"
+    );
+    assert_eq!(
+        find_columns_to_keep(&items, &orders, &[&regions], &select),
+        vec!["name", "sku"]
+    );
+    let joined = table("users", &["id", "name", "id_orders", "total"], &[]);
+    assert_eq!(
+        find_columns_to_keep(&joined, &regions, &[&items], &select),
+        vec!["name", "sku", "id_orders"],
+        "keys of earlier tables keep the name they were given"
+    );
+    assert_eq!(
+        find_columns_to_keep(&users, &orders, &[&regions], &["id_regions".to_string()]),
+        vec!["id_regions", "region_id", "id"],
+        "a column a selected one of a later table is suffixed for is kept"
+    );
+}
+
+#[test]
+fn test_find_path_via() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("orders", &["customers"]),
+        table("items", &["orders", "products"]),
+        table("products", &[]),
+        table("wishlists", &["customers", "products"]),
+    ]);
+    let node = |name| find_node(&g, name).unwrap();
+    let names = |path: Vec<NodeIndex>| -> Vec<String> {
+        path.into_iter().map(|n| g[n].name.clone()).collect()
+    };
+
+    let direct = find_path_via(
+        &g,
+        node("customers"),
+        &[],
+        node("products"),
+        PathWeight::Joins,
+    )
+    .unwrap();
+    assert_eq!(names(direct), vec!["customers", "wishlists", "products"]);
+
+    let via_orders = find_path_via(
+        &g,
+        node("customers"),
+        &[node("orders")],
+        node("products"),
+        PathWeight::Joins,
+    )
+    .unwrap();
+    assert_eq!(
+        names(via_orders),
+        vec!["customers", "orders", "items", "products"]
+    );
+
+    let err = find_path_via(
+        &g,
+        node("orders"),
+        &[node("customers")],
+        node("items"),
+        PathWeight::Joins,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("more than once"));
+}
+
+#[test]
+fn test_find_orphans() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("orders", &["customers"]),
+        table("categories", &["categories"]),
+        table("audit_log", &[]),
+        table("refunds", &["payments"]),
+    ]);
+
+    assert_eq!(find_orphans(&g), vec!["audit_log", "categories", "refunds"]);
+    assert!(find_orphans(&create_mock_graph()).is_empty());
+}
+
+#[test]
+fn test_find_references() {
+    let table = |name: &str, keys: &[(&str, &str)]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|(col, dst)| (col.to_string(), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("users", &[("manager_id", "users")]),
+        table("posts", &[("author_id", "users"), ("editor_id", "users")]),
+        table("comments", &[("post_id", "posts")]),
+    ]);
+    let reference =
+        |table: &str, column: &str| (table.to_string(), column.to_string(), "id".to_string());
+
+    assert_eq!(
+        find_references(&g, find_node(&g, "users").unwrap()),
+        vec![
+            reference("posts", "author_id"),
+            reference("posts", "editor_id"),
+            reference("users", "manager_id"),
+        ]
+    );
+    assert!(find_references(&g, find_node(&g, "comments").unwrap()).is_empty());
+}
+
+#[test]
+fn test_find_neighbors() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("orders", &["customers"]),
+        table("wishlists", &["customers"]),
+        table("audit_log", &[]),
+    ]);
+    let pair = |neighbor: &str, column: &str, neighbor_column: &str| {
+        (
+            neighbor.to_string(),
+            column.to_string(),
+            neighbor_column.to_string(),
+        )
+    };
+
+    assert_eq!(
+        find_neighbors(&g, find_node(&g, "customers").unwrap()),
+        vec![
+            pair("orders", "id", "customers_id"),
+            pair("wishlists", "id", "customers_id"),
+        ]
+    );
+    assert_eq!(
+        find_neighbors(&g, find_node(&g, "orders").unwrap()),
+        vec![pair("customers", "customers_id", "id")]
+    );
+    assert!(find_neighbors(&g, find_node(&g, "audit_log").unwrap()).is_empty());
+}
+
+#[test]
+fn test_find_hubs() {
+    let table = |name: &str, keys: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: vec!["id".to_string()],
+        foreign_keys: keys
+            .iter()
+            .map(|dst| (format!("{}_id", dst), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let g = csvg::graph::create_graph(vec![
+        table("customers", &[]),
+        table("orders", &["customers"]),
+        table("items", &["orders"]),
+        table("reviews", &["customers"]),
+        table("wishlists", &["customers"]),
+        table("audit_log", &[]),
+    ]);
+
+    let hubs = find_hubs(&g);
+    let names: Vec<&str> = hubs.iter().map(|(name, _, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "customers",
+            "orders",
+            "items",
+            "reviews",
+            "wishlists",
+            "audit_log"
+        ]
+    );
+    assert_eq!(hubs[0], ("customers".to_string(), 3, 5.0));
+    assert_eq!(hubs[1], ("orders".to_string(), 2, 3.0));
+    assert_eq!(hubs[5], ("audit_log".to_string(), 0, 0.0));
+}
+
 #[test]
 fn test_find_join_columns() {
     let df1 = DataFrame {
@@ -68,6 +555,7 @@ fn test_find_join_columns() {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![],
+        ..Default::default()
     };
     let df2 = DataFrame {
         name: "table2".to_string(),
@@ -75,10 +563,10 @@ fn test_find_join_columns() {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![("id".to_string(), "table1".to_string(), "id".to_string())],
+        ..Default::default()
     };
-    let (left_col, right_col) = find_join_columns(&df1, &df2).unwrap();
-    assert_eq!(left_col, "id");
-    assert_eq!(right_col, "id");
+    let keys = find_join_columns(&df1, &df2).unwrap();
+    assert_eq!(keys, vec![("id".to_string(), "id".to_string())]);
 
     let df3 = DataFrame {
         name: "table3".to_string(),
@@ -86,6 +574,7 @@ fn test_find_join_columns() {
         header_indices: HashMap::new(),
         primary_key: Some("code".to_string()),
         foreign_keys: vec![],
+        ..Default::default()
     };
     assert!(find_join_columns(&df1, &df3).is_err());
 }
@@ -98,6 +587,7 @@ fn test_update_dataframe_after_join() {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![],
+        ..Default::default()
     };
     let right_df = DataFrame {
         name: "table2".to_string(),
@@ -105,9 +595,582 @@ fn test_update_dataframe_after_join() {
         header_indices: HashMap::new(),
         primary_key: Some("id".to_string()),
         foreign_keys: vec![("id".to_string(), "table1".to_string(), "id".to_string())],
+        ..Default::default()
     };
-    let joined_df = update_dataframe_after_join(&left_df, &right_df, "id", "id");
+    let joined_df =
+        update_dataframe_after_join(&left_df, &right_df, &[("id".to_string(), "id".to_string())]);
     assert_eq!(joined_df.headers, vec!["id", "name", "value"]);
     assert_eq!(joined_df.primary_key, Some("id".to_string()));
     assert!(joined_df.foreign_keys.is_empty());
 }
+
+#[test]
+fn test_assign_conditions() {
+    let g = create_mock_graph();
+    let path = vec![NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)];
+    let conditions = |list: &[&str]| list.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+    let assigned = assign_conditions(
+        &g,
+        &path,
+        &conditions(&["table3.description = 'x'", "table1.id > 1"]),
+    )
+    .unwrap();
+    assert_eq!(assigned.len(), 3);
+    assert_eq!(assigned[0][0].columns(), vec!["id"]);
+    assert!(assigned[1].is_empty());
+    assert_eq!(assigned[2][0].columns(), vec!["description"]);
+
+    assert!(assign_conditions(&g, &path, &conditions(&["id > 1"])).is_err());
+    assert!(assign_conditions(&g, &path, &conditions(&["table9.id > 1"])).is_err());
+    assert!(assign_conditions(&g, &path, &conditions(&["table1.id = table2.id"])).is_err());
+    assert!(assign_conditions(&g, &path[..1], &conditions(&["table2.id > 1"])).is_err());
+}
+
+#[test]
+fn test_find_explicit_path() {
+    let g = create_mock_graph();
+    let tables = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+    let path = find_explicit_path(&g, &tables(&["table3", "table2", "table1"])).unwrap();
+    assert_eq!(
+        path,
+        vec![NodeIndex::new(2), NodeIndex::new(1), NodeIndex::new(0)]
+    );
+    assert!(find_explicit_path(&g, &tables(&["table1", "table3"])).is_err());
+    assert!(find_explicit_path(&g, &tables(&["table1", "table2", "table1"])).is_err());
+    assert!(find_explicit_path(&g, &tables(&["table1"])).is_err());
+    assert!(find_explicit_path(&g, &tables(&["table1", "missing"])).is_err());
+}
+
+#[test]
+fn test_find_join_order() {
+    let g = create_mock_graph();
+    let tables = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        find_join_order(&g, &tables(&["table1", "table3"])).unwrap(),
+        vec![NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)],
+        "tables between the given ones are joined too"
+    );
+    assert_eq!(
+        find_join_order(&g, &tables(&["table2", "table3", "table1"])).unwrap(),
+        vec![NodeIndex::new(1), NodeIndex::new(0), NodeIndex::new(2)],
+        "every table follows one it has a foreign key with"
+    );
+    assert!(find_join_order(&g, &tables(&["table1", "missing"])).is_err());
+}
+
+// Helper function to create users <- orders <- items, where each references the one before
+fn create_orders_graph() -> (UnGraph<DataFrame, (String, String)>, Vec<NodeIndex>) {
+    let table = |name: &str, headers: &[&str], keys: &[(&str, &str)]| DataFrame {
+        name: name.to_string(),
+        headers: headers.iter().map(|h| h.to_string()).collect(),
+        foreign_keys: keys
+            .iter()
+            .map(|(column, dst)| (column.to_string(), dst.to_string(), "id".to_string()))
+            .collect(),
+        ..Default::default()
+    };
+    let mut g = UnGraph::new_undirected();
+    let users = g.add_node(table("users", &["id", "name"], &[]));
+    let orders = g.add_node(table(
+        "orders",
+        &["id", "user_id", "total"],
+        &[("user_id", "users")],
+    ));
+    let items = g.add_node(table(
+        "items",
+        &["id", "order_id", "sku"],
+        &[("order_id", "orders")],
+    ));
+    g.add_edge(orders, users, ("user_id".to_string(), "id".to_string()));
+    g.add_edge(items, orders, ("order_id".to_string(), "id".to_string()));
+    (g, vec![users, orders, items])
+}
+
+#[test]
+fn test_find_hop_keys() {
+    let (g, path) = create_orders_graph();
+    let users = path[0];
+
+    assert_eq!(
+        find_hop_keys(&g, &path, 1, &g[users]).unwrap(),
+        vec![("id".to_string(), "user_id".to_string())]
+    );
+    let joined = DataFrame {
+        headers: ["id", "name", "id_orders", "total"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect(),
+        ..g[users].clone()
+    };
+    assert_eq!(
+        find_hop_keys(&g, &path, 2, &joined).unwrap(),
+        vec![("id_orders".to_string(), "order_id".to_string())],
+        "the key of orders, not the id of users"
+    );
+}
+
+#[test]
+fn test_plan_join() {
+    let (mut g, path) = create_orders_graph();
+    for (&node, rows) in path.iter().zip([10, 40, 100]) {
+        g[node].row_count = Some(rows);
+    }
+
+    let steps = plan_join(&g, &path, &JoinOptions::default(), false).unwrap();
+    assert_eq!(steps.len(), 3);
+    assert!(steps[0].keys.is_empty());
+    assert_eq!(
+        steps[2].keys,
+        vec![("id_orders".to_string(), "order_id".to_string())]
+    );
+    assert_eq!(
+        steps[2].headers,
+        vec!["id", "name", "id_orders", "total", "id_items", "sku"]
+    );
+    let rows: Vec<Option<u64>> = steps.iter().map(|step| step.estimated_rows).collect();
+    assert_eq!(rows, vec![Some(10), Some(40), Some(100)]);
+
+    let options = JoinOptions {
+        select: vec!["name".to_string(), "sku".to_string()],
+        ..JoinOptions::default()
+    };
+    let steps = plan_join(&g, &path, &options, false).unwrap();
+    assert_eq!(steps[1].headers, vec!["name", "id_orders"]);
+    assert_eq!(steps[2].headers, vec!["name", "sku"]);
+}
+
+#[test]
+fn test_plan_join_prefix_columns() {
+    let (g, path) = create_orders_graph();
+
+    let steps = plan_join(&g, &path, &JoinOptions::default(), true).unwrap();
+    assert_eq!(
+        steps[2].keys,
+        vec![("orders.id".to_string(), "order_id".to_string())]
+    );
+    assert_eq!(
+        steps[2].headers,
+        vec![
+            "users.id",
+            "users.name",
+            "orders.id",
+            "orders.total",
+            "items.id",
+            "items.sku"
+        ]
+    );
+
+    let options = JoinOptions {
+        select: vec!["users.name".to_string(), "items.sku".to_string()],
+        ..JoinOptions::default()
+    };
+    let steps = plan_join(&g, &path, &options, true).unwrap();
+    assert_eq!(steps[1].headers, vec!["users.name", "orders.id"]);
+    assert_eq!(steps[2].headers, vec!["users.name", "items.sku"]);
+}
+
+#[test]
+fn test_compare_headers() {
+    let columns = |list: &[&str]| list.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    let schema = columns(&["id", "user_id", "total"]);
+
+    let (missing, extra) = compare_headers(&schema, &columns(&["TOTAL", "id", "user_id"]));
+    assert!(
+        missing.is_empty() && extra.is_empty(),
+        "order and case do not matter"
+    );
+    let (missing, extra) = compare_headers(&schema, &columns(&["id", "userid", "total", "note"]));
+    assert_eq!(missing, vec!["user_id"]);
+    assert_eq!(extra, vec!["userid", "note"]);
+}
+
+#[test]
+fn test_find_hop_keys_composite() {
+    let table = |name: &str, headers: &[&str]| DataFrame {
+        name: name.to_string(),
+        headers: headers.iter().map(|h| h.to_string()).collect(),
+        ..Default::default()
+    };
+    let mut g = UnGraph::new_undirected();
+    let users = g.add_node(table("users", &["id"]));
+    let orders = g.add_node(table(
+        "orders",
+        &["tenant_id", "id", "created_by", "approved_by"],
+    ));
+    let lines = g.add_node(DataFrame {
+        composite_foreign_keys: vec![vec!["tenant_id".to_string(), "order_id".to_string()]],
+        ..table("lines", &["tenant_id", "order_id", "sku"])
+    });
+    let customers = g.add_node(table("customers", &["id", "email"]));
+    let invoices = g.add_node(table("invoices", &["customer_id", "customer_email"]));
+    let edge = |column: &str, referenced: &str| (column.to_string(), referenced.to_string());
+    g.add_edge(orders, users, edge("created_by", "id"));
+    g.add_edge(orders, users, edge("approved_by", "id"));
+    g.add_edge(lines, orders, edge("tenant_id", "tenant_id"));
+    g.add_edge(lines, orders, edge("order_id", "id"));
+    g.add_edge(invoices, customers, edge("customer_id", "id"));
+    g.add_edge(invoices, customers, edge("customer_email", "email"));
+
+    let pairs = |list: &[(&str, &str)]| {
+        list.iter()
+            .map(|(left, right)| (left.to_string(), right.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        find_hop_keys(&g, &[orders, lines], 1, &g[orders]).unwrap(),
+        pairs(&[("tenant_id", "tenant_id"), ("id", "order_id")]),
+        "every column of a composite key"
+    );
+    assert_eq!(
+        find_hop_keys(&g, &[users, orders], 1, &g[users]).unwrap(),
+        pairs(&[("id", "created_by")]),
+        "only the first of two keys referencing the same column"
+    );
+    assert_eq!(
+        find_hop_keys(&g, &[customers, invoices], 1, &g[customers]).unwrap(),
+        pairs(&[("id", "customer_id")]),
+        "only the first of two keys referencing different columns"
+    );
+}
+
+#[test]
+fn test_remap_headers() {
+    let columns = |list: &[&str]| list.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+    let schema = columns(&["id", "user_id", "total"]);
+
+    assert_eq!(
+        remap_headers(&schema, &columns(&["TOTAL", "Id", "note", "user_id"])),
+        columns(&["total", "id", "note", "user_id"]),
+        "the file's order with the schema's spelling"
+    );
+}
+
+#[test]
+fn test_order_joins_by_size() {
+    let g = create_mock_graph();
+    let path = vec![NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)];
+
+    let sizes = [100, 50, 10];
+    assert_eq!(
+        order_joins_by_size(&g, &path, |n| sizes[n.index()]),
+        vec![NodeIndex::new(2), NodeIndex::new(1), NodeIndex::new(0)]
+    );
+    let sizes = [5, 50, 10];
+    assert_eq!(
+        order_joins_by_size(&g, &path, |n| sizes[n.index()]),
+        vec![NodeIndex::new(0), NodeIndex::new(1), NodeIndex::new(2)],
+        "a smaller table waits until a foreign key connects it"
+    );
+}
+
+#[test]
+fn test_join_parents() {
+    let (mut g, mut path) = create_orders_graph();
+    assert_eq!(join_parents(&g, &path), vec![0, 0, 1]);
+    assert!(!has_independent_branches(&join_parents(&g, &path)));
+
+    let addresses = g.add_node(DataFrame {
+        name: "addresses".to_string(),
+        headers: vec!["id".to_string(), "user_id".to_string()],
+        ..Default::default()
+    });
+    let visits = g.add_node(DataFrame {
+        name: "visits".to_string(),
+        headers: vec!["id".to_string(), "address_id".to_string()],
+        ..Default::default()
+    });
+    g.add_edge(
+        addresses,
+        path[0],
+        ("user_id".to_string(), "id".to_string()),
+    );
+    g.add_edge(
+        visits,
+        addresses,
+        ("address_id".to_string(), "id".to_string()),
+    );
+
+    // users, orders, addresses, items, visits: two chains of two tables below users.
+    path = vec![path[0], path[1], addresses, path[2], visits];
+    assert_eq!(join_parents(&g, &path), vec![0, 0, 0, 1, 2]);
+    assert!(has_independent_branches(&join_parents(&g, &path)));
+    assert!(!has_independent_branches(&[0, 0, 0, 1]));
+}
+
+/// Writes `a(id, x)`, `b(id, name)` with `b.id` referencing `a.id`, and `c(b_id, v)`
+/// with `c.b_id` referencing `b.id` to `dir`, returning their graph and the path a, b, c.
+fn create_chain_tables(dir: &Path) -> (UnGraph<DataFrame, (String, String)>, Vec<NodeIndex>) {
+    let mut g = UnGraph::new_undirected();
+    let mut path = Vec::new();
+    for (name, headers, key, rows) in [
+        ("a", ["id", "x"], None, "1,a1\n3,a3\n"),
+        ("b", ["id", "name"], Some(("id", "a")), "1,b1\n2,b2\n"),
+        ("c", ["b_id", "v"], Some(("b_id", "b")), "1,c1\n2,c2\n"),
+    ] {
+        std::fs::write(
+            dir.join(format!("{}.csv", name)),
+            format!("{}\n{}", headers.join(","), rows),
+        )
+        .unwrap();
+        let node = g.add_node(DataFrame {
+            name: name.to_string(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            foreign_keys: key
+                .iter()
+                .map(|(column, dst)| (column.to_string(), dst.to_string(), "id".to_string()))
+                .collect(),
+            ..Default::default()
+        });
+        if let Some((column, _)) = key {
+            g.add_edge(
+                node,
+                *path.last().unwrap(),
+                (column.to_string(), "id".to_string()),
+            );
+        }
+        path.push(node);
+    }
+    (g, path)
+}
+
+/// Writes users, orders, addresses, items and visits to `dir`, returning their graph
+/// and that path: orders and addresses reference users, items orders and visits
+/// addresses, so the path has two independent branches below users. Items and visits
+/// both have a `note`.
+fn create_branch_tables(dir: &Path) -> (UnGraph<DataFrame, (String, String)>, Vec<NodeIndex>) {
+    let mut g: UnGraph<DataFrame, (String, String)> = UnGraph::new_undirected();
+    let mut path: Vec<NodeIndex> = Vec::new();
+    for (name, headers, key, rows) in [
+        ("users", ["id", "name"], None, "1,ann\n2,bob\n"),
+        (
+            "orders",
+            ["user_id", "total"],
+            Some(("user_id", 0, "id")),
+            "1,10\n1,15\n2,20\n",
+        ),
+        (
+            "addresses",
+            ["user_id", "city"],
+            Some(("user_id", 0, "id")),
+            "1,Oslo\n2,Rome\n2,Pisa\n",
+        ),
+        (
+            "items",
+            ["order_total", "note"],
+            Some(("order_total", 1, "total")),
+            "10,fragile\n10,boxed\n20,gift\n",
+        ),
+        (
+            "visits",
+            ["address_city", "note"],
+            Some(("address_city", 2, "city")),
+            "Oslo,rainy\nRome,sunny\nPisa,windy\nPisa,calm\n",
+        ),
+    ] {
+        std::fs::write(
+            dir.join(format!("{}.csv", name)),
+            format!("{}\n{}", headers.join(","), rows),
+        )
+        .unwrap();
+        let node = g.add_node(DataFrame {
+            name: name.to_string(),
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            foreign_keys: key
+                .iter()
+                .map(|&(column, step, dst_column)| {
+                    (
+                        column.to_string(),
+                        g[path[step]].name.clone(),
+                        dst_column.to_string(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        });
+        if let Some((column, step, dst_column)) = key {
+            g.add_edge(
+                node,
+                path[step],
+                (column.to_string(), dst_column.to_string()),
+            );
+        }
+        path.push(node);
+    }
+    (g, path)
+}
+
+/// Parses the arguments of `csvg graph join`.
+fn join_args(args: &[&str]) -> GraphJoinArgs {
+    let cli = Cli::try_parse_from([&["csvg", "graph", "join"], args].concat()).unwrap();
+    match cli.command {
+        Commands::Graph(graph) => match graph.subcommand {
+            Some(GraphSubcommands::Join(join)) => join,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+/// A configuration reading tables from `dir` and writing joins to `output_file` there.
+fn join_config(dir: &Path, output_file: &str) -> Config {
+    Config {
+        source_path: dir.to_path_buf(),
+        output_file: dir.join(output_file).to_string_lossy().into_owned(),
+        ..Config::default()
+    }
+}
+
+#[test]
+fn test_join_tables_along_path_outer_joins() {
+    let dir = TempDir::new().unwrap();
+    let (g, path) = create_chain_tables(dir.path());
+    let config = join_config(dir.path(), "output.csv");
+    let join = |join_type: &str| {
+        let args = join_args(&["a", "c", "--type", join_type]);
+        join_tables_along_path(&g, &path, &config, None, &args, &JoinOptions::default()).unwrap();
+        std::fs::read_to_string(&config.output_file).unwrap()
+    };
+
+    assert_eq!(join("inner"), "id,x,name,v\n1,a1,b1,c1\n");
+    assert_eq!(join("left"), "id,x,name,v\n1,a1,b1,c1\n3,a3,,\n");
+    // Rows only on the right keep their keys, so later hops still match them.
+    assert_eq!(join("right"), "id,x,name,v\n1,a1,b1,c1\n2,,b2,c2\n");
+    assert_eq!(join("full"), "id,x,name,v\n1,a1,b1,c1\n3,a3,,\n2,,b2,c2\n");
+}
+
+#[test]
+fn test_join_tables_along_path_cache() {
+    let dir = TempDir::new().unwrap();
+    let cache = dir.path().join("cache");
+    let (g, path) = create_chain_tables(dir.path());
+    let config = join_config(dir.path(), "output.csv");
+    let args = join_args(&["a", "c"]);
+    let join = |cache: Option<&Path>| {
+        join_tables_along_path(&g, &path, &config, cache, &args, &JoinOptions::default()).unwrap();
+        std::fs::read_to_string(&config.output_file).unwrap()
+    };
+    let cached_files = || {
+        let mut files: Vec<_> = std::fs::read_dir(&cache)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "csv"))
+            .collect();
+        files.sort();
+        files
+    };
+
+    let output = join(Some(&cache));
+    assert_eq!(output, "id,x,name,v\n1,a1,b1,c1\n");
+    // The first table and each of the two hops.
+    assert_eq!(cached_files().len(), 3);
+
+    // A second run reuses the last hop's rows as cached.
+    let last_hop = cached_files()
+        .into_iter()
+        .find(|file| std::fs::read_to_string(file).unwrap() == output)
+        .unwrap();
+    std::fs::write(&last_hop, "id,x,name,v\n9,cached,b9,c9\n").unwrap();
+    assert_eq!(join(Some(&cache)), "id,x,name,v\n9,cached,b9,c9\n");
+
+    // Without the cache, every table is joined again.
+    assert_eq!(join(None), "id,x,name,v\n1,a1,b1,c1\n");
+
+    // Changing a table joins the hops after it again, and drops the hop it replaces.
+    std::fs::write(dir.path().join("c.csv"), "b_id,v\n1,c1\n1,c3\n2,c2\n").unwrap();
+    assert_eq!(join(Some(&cache)), "id,x,name,v\n1,a1,b1,c1\n1,a1,b1,c3\n");
+    assert_eq!(cached_files().len(), 3);
+    assert!(!last_hop.exists());
+
+    // Files of a join running at the same time are left alone.
+    let lock = std::fs::File::open(cache.join(".lock")).unwrap();
+    lock.lock_shared().unwrap();
+    let other = cache.join("0123456789abcdef.csv");
+    std::fs::write(&other, "id\n").unwrap();
+    join(Some(&cache));
+    assert!(other.exists());
+    lock.unlock().unwrap();
+    join(Some(&cache));
+    assert!(!other.exists());
+}
+
+#[test]
+fn test_join_tables_along_path_output() {
+    let dir = TempDir::new().unwrap();
+    let (g, path) = create_chain_tables(dir.path());
+    let config = join_config(dir.path(), "out/nested/result.csv");
+    let join = |args: &[&str]| {
+        join_tables_along_path(
+            &g,
+            &path,
+            &config,
+            None,
+            &join_args(args),
+            &JoinOptions::default(),
+        )
+        .unwrap()
+    };
+
+    join(&["a", "c", "--stdout"]);
+    assert!(!dir.path().join("out").exists());
+
+    join(&["a", "c"]);
+    assert_eq!(
+        std::fs::read_to_string(&config.output_file).unwrap(),
+        "id,x,name,v\n1,a1,b1,c1\n"
+    );
+}
+
+#[test]
+fn test_join_tables_along_path_branches() {
+    let dir = TempDir::new().unwrap();
+    let (g, path) = create_branch_tables(dir.path());
+    assert!(has_independent_branches(&join_parents(&g, &path)));
+    let config = join_config(dir.path(), "output.csv");
+    let join = |args: &[&str], cache_dir: Option<&Path>| {
+        let args = join_args(&[&["--path", "users,orders,addresses,items,visits"], args].concat());
+        let options = JoinOptions {
+            select: args.select.clone(),
+            ..JoinOptions::default()
+        };
+        join_tables_along_path(&g, &path, &config, cache_dir, &args, &options).unwrap();
+        std::fs::read_to_string(&config.output_file).unwrap()
+    };
+
+    // The branches are joined on their own, yet the output is that of joining the
+    // tables in path order, which the explain plan describes.
+    let output = join(&[], None);
+    let plan = plan_join(&g, &path, &JoinOptions::default(), false).unwrap();
+    assert_eq!(
+        output
+            .lines()
+            .next()
+            .unwrap()
+            .split(',')
+            .collect::<Vec<_>>(),
+        plan.last().unwrap().headers
+    );
+    assert_eq!(
+        output,
+        "id,name,total,city,note,note_visits\n\
+         1,ann,10,Oslo,fragile,rainy\n\
+         1,ann,10,Oslo,boxed,rainy\n\
+         2,bob,20,Rome,gift,sunny\n\
+         2,bob,20,Pisa,gift,windy\n\
+         2,bob,20,Pisa,gift,calm\n"
+    );
+    let cache = dir.path().join("cache");
+    assert_eq!(join(&[], Some(&cache)), output);
+    assert_eq!(join(&[], Some(&cache)), output);
+
+    assert_eq!(
+        join(&["--select", "name,note_visits"], None),
+        "name,note_visits\nann,rainy\nann,rainy\nbob,sunny\nbob,windy\nbob,calm\n"
+    );
+    assert_eq!(
+        join(&["--prefix-columns"], None).lines().next().unwrap(),
+        "users.id,users.name,orders.total,addresses.city,items.note,visits.note"
+    );
+}
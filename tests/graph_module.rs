@@ -1,9 +1,10 @@
 use petgraph::graph::UnGraph;
 
-use csvg::graph::{self, SerializableGraph};
+use csvg::config::GraphvizSettings;
+use csvg::graph::{self, GraphExport, SchemaDiff, SerializableGraph};
 use std::collections::HashSet;
 
-use csvg::csv::DataFrame;
+use csvg::csv::{ColumnType, DataFrame};
 
 // Helper function to create a sample DataFrame
 fn create_sample_dataframe(
@@ -24,6 +25,7 @@ fn create_sample_dataframe(
             .into_iter()
             .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
             .collect(),
+        ..Default::default()
     }
 }
 
@@ -141,7 +143,7 @@ fn test_write_dot_file() {
     ));
     graph.add_edge(node1, node2, ("user_id".to_string(), "id".to_string()));
 
-    let dot_content = graph::write_dot_file(&graph);
+    let dot_content = graph::write_dot_file(&graph, &GraphvizSettings::default());
 
     // Check for basic structure
     assert!(dot_content.starts_with("graph G {"));
@@ -149,11 +151,430 @@ fn test_write_dot_file() {
 
     // Check for node declarations
     assert!(dot_content
-        .contains("0 [label=<{<b><font point-size='16' color='red'>users</font></b>|id|name}>]"));
+        .contains("0 [label=<{<b><font point-size='16' color='red'>users</font></b>|id|name}>, "));
     assert!(dot_content.contains(
-        "1 [label=<{<b><font point-size='16' color='red'>posts</font></b>|id|title|user_id}>]"
+        "1 [label=<{<b><font point-size='16' color='red'>posts</font></b>|id|title|\
+         <i><font color='darkgreen'>user_id &#8594;</font></i>}>, "
     ));
 
+    // Check that tables are anchors in SVG output
+    assert!(dot_content.contains("id=\"users\", URL=\"#users\""));
+
     // Check for edge declaration
     assert!(dot_content.contains("0 -- 1 [label=\"(user_id, id)\"]"));
 }
+
+#[test]
+fn test_write_dot_file_column_types() {
+    let mut users = create_sample_dataframe("users", vec!["id", "name"], vec![]);
+    users.sql_types = vec!["INT".to_string(), "VARCHAR(255)".to_string()];
+    let graph = graph::create_graph(vec![users]);
+
+    let mut settings = GraphvizSettings::default();
+    assert!(graph::write_dot_file(&graph, &settings).contains("|id: INT|name: VARCHAR(255)}"));
+
+    settings.show_column_types = false;
+    assert!(graph::write_dot_file(&graph, &settings).contains("|id|name}"));
+}
+
+#[test]
+fn test_write_dot_file_style() {
+    let graph = graph::create_graph(vec![create_sample_dataframe("users", vec!["id"], vec![])]);
+    let mut settings = GraphvizSettings::default();
+    let dot_content = graph::write_dot_file(&graph, &settings);
+    assert!(dot_content.contains("  node [shape=record, fontname=\"Arial\"];\n"));
+    assert!(dot_content.contains("  rankdir=TB;\n"));
+
+    settings.style = serde_json::from_str(
+        r#"{"node_shape": "Mrecord", "font_name": "Inter", "title_color": "navy",
+            "rankdir": "LR", "nodesep": 0.25}"#,
+    )
+    .unwrap();
+    let dot_content = graph::write_dot_file(&graph, &settings);
+    assert!(dot_content.contains("  node [shape=Mrecord, fontname=\"Inter\"];\n"));
+    assert!(dot_content.contains("  edge [fontsize=12, fontname=\"Inter\", color=\"black\"];\n"));
+    assert!(dot_content.contains("  rankdir=LR;\n"));
+    assert!(dot_content.contains("  nodesep=0.25;\n"));
+    assert!(
+        dot_content.contains("  ranksep=0.5;\n"),
+        "unset keys keep their default"
+    );
+    assert!(dot_content.contains("<font point-size='16' color='navy'>users</font>"));
+}
+
+#[test]
+fn test_write_dot_file_key_columns() {
+    let mut users = create_sample_dataframe("users", vec!["id", "name"], vec![]);
+    users.primary_key = Some("id".to_string());
+    users.sql_types = vec!["INT".to_string(), "TEXT".to_string()];
+    let graph = graph::create_graph(vec![
+        users,
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+    ]);
+
+    let dot_content = graph::write_dot_file(&graph, &GraphvizSettings::default());
+    assert!(dot_content.contains("|<b><u>id: INT</u></b>|name: TEXT}"));
+    assert!(dot_content.contains("|id|<i><font color='darkgreen'>user_id &#8594;</font></i>}"));
+}
+
+#[test]
+fn test_write_dot_file_row_counts() {
+    let mut users = create_sample_dataframe("users", vec!["id"], vec![]);
+    users.row_count = Some(120);
+    let mut posts = create_sample_dataframe(
+        "posts",
+        vec!["id", "user_id"],
+        vec![("user_id", "users", "id")],
+    );
+    posts.row_count = Some(5000);
+    let graph = graph::create_graph(vec![users, posts]);
+
+    let dot_content = graph::write_dot_file(&graph, &GraphvizSettings::default());
+    assert!(dot_content.contains("users</font></b><br/><font point-size='10'>120 rows</font>|"));
+    assert!(dot_content.contains("1 -- 0 [label=\"(user_id, id)\\n5000 : 120 rows\"]"));
+}
+
+#[test]
+fn test_write_dot_file_schema_clusters() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe("billing.invoices", vec!["id"], vec![]),
+        create_sample_dataframe("audit_log", vec!["id"], vec![]),
+        create_sample_dataframe("billing.payments", vec!["id"], vec![]),
+    ]);
+
+    let dot_content = graph::write_dot_file(&graph, &GraphvizSettings::default());
+    let cluster = "  subgraph cluster_0 {\n    label=\"billing\";\n    style=rounded;\n    0 [";
+    assert!(dot_content.contains(cluster));
+    assert!(dot_content.contains("\n    2 ["));
+    assert!(dot_content.contains("\n  1 ["));
+    assert!(!dot_content.contains("cluster_1"));
+}
+
+#[test]
+fn test_graph_export() {
+    let mut users = create_sample_dataframe("users", vec!["id", "name"], vec![]);
+    users.primary_key = Some("id".to_string());
+    users.column_types = vec![ColumnType::Int, ColumnType::String];
+    users.sql_types = vec!["INT".to_string(), "TEXT".to_string()];
+    let graph = graph::create_graph(vec![
+        users,
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+    ]);
+
+    let json = serde_json::to_value(GraphExport::from(&graph)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "version": 1,
+            "tables": [
+                {
+                    "name": "users",
+                    "columns": [
+                        {"name": "id", "type": "int", "sql_type": "INT"},
+                        {"name": "name", "type": "string", "sql_type": "TEXT"}
+                    ],
+                    "primary_key": "id"
+                },
+                {
+                    "name": "posts",
+                    "columns": [
+                        {"name": "id", "type": null, "sql_type": null},
+                        {"name": "user_id", "type": null, "sql_type": null}
+                    ],
+                    "primary_key": null
+                }
+            ],
+            "foreign_keys": [
+                {
+                    "table": "posts",
+                    "column": "user_id",
+                    "references_table": "users",
+                    "references_column": "id"
+                }
+            ]
+        })
+    );
+}
+
+#[test]
+fn test_write_highlighted_dot_file() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "tags",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+    ]);
+    let path: Vec<_> = graph.node_indices().take(2).collect();
+
+    let dot_content =
+        graph::write_highlighted_dot_file(&graph, &GraphvizSettings::default(), &path);
+    let highlighted: Vec<&str> = dot_content
+        .lines()
+        .filter(|line| line.contains("color=\"blue\""))
+        .collect();
+    assert_eq!(highlighted.len(), 3);
+    assert!(highlighted[0].starts_with("  0 ["));
+    assert!(highlighted[1].starts_with("  1 ["));
+    assert!(highlighted[2].starts_with("  1 -- 0 "));
+
+    assert!(!graph::write_dot_file(&graph, &GraphvizSettings::default()).contains("color=\"blue\""));
+}
+
+#[test]
+fn test_write_layered_svg() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "replies",
+            vec!["id", "reply_to"],
+            vec![("reply_to", "replies", "id")],
+        ),
+    ]);
+    let path: Vec<_> = graph.node_indices().take(2).collect();
+
+    let svg = graph::write_layered_svg(&graph, &GraphvizSettings::default(), &path);
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains("user_id \u{2192}</text>"));
+    assert_eq!(
+        svg.matches("<line x1").count(),
+        3 + 1,
+        "a separator per table and a key"
+    );
+    assert_eq!(svg.matches("<path ").count(), 1, "a self-reference loops");
+    assert_eq!(svg.matches("stroke=\"blue\"").count(), 2 + 2 + 1);
+
+    // A referenced table is drawn above the tables referencing it.
+    let top = |name: &str| {
+        let group = svg.split(&format!("<g id=\"{}\">", name)).nth(1).unwrap();
+        let y = group.split("y=\"").nth(1).unwrap();
+        y[..y.find('"').unwrap()].parse::<f64>().unwrap()
+    };
+    assert!(top("users") < top("posts"));
+    assert_eq!(top("users"), top("replies"));
+}
+
+#[test]
+fn test_write_ascii() {
+    let mut graph = graph::create_graph(vec![
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "comments",
+            vec!["post_id", "user_id"],
+            vec![("post_id", "posts", "id"), ("user_id", "users", "id")],
+        ),
+        create_sample_dataframe("tags", vec!["id"], vec![]),
+    ]);
+    graph[petgraph::graph::NodeIndex::new(0)].row_count = Some(3);
+    let path: Vec<_> = graph.node_indices().take(2).collect();
+
+    assert_eq!(
+        graph::write_ascii(&graph, &path),
+        "comments\n\
+         ├── post_id → posts.id\n\
+         └── user_id → users.id\n\
+         posts  [path step 2]\n\
+         ├── id ← comments.post_id\n\
+         └── user_id → users.id\n\
+         tags\n\
+         users (3 rows)  [path step 1]\n\
+         ├── id ← comments.user_id\n\
+         └── id ← posts.user_id\n"
+    );
+}
+
+#[test]
+fn test_diff_schemas() {
+    let old = vec![
+        create_sample_dataframe("users", vec!["id", "fax"], vec![]),
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe("legacy", vec!["id"], vec![]),
+    ];
+    let new = vec![
+        create_sample_dataframe("users", vec!["id", "email"], vec![]),
+        create_sample_dataframe("posts", vec!["id", "user_id"], vec![]),
+        create_sample_dataframe(
+            "tags",
+            vec!["id", "post_id"],
+            vec![("post_id", "posts", "id")],
+        ),
+    ];
+    let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+    let key = |a: &str, b: &str, c: &str, d: &str| {
+        (a.to_string(), b.to_string(), c.to_string(), d.to_string())
+    };
+
+    let diff = graph::diff_schemas(&old, &new);
+    assert_eq!(
+        diff,
+        SchemaDiff {
+            added_tables: vec!["tags".to_string()],
+            removed_tables: vec!["legacy".to_string()],
+            added_columns: vec![pair("users", "email")],
+            removed_columns: vec![pair("users", "fax")],
+            added_foreign_keys: vec![key("tags", "post_id", "posts", "id")],
+            removed_foreign_keys: vec![key("posts", "user_id", "users", "id")],
+        }
+    );
+    assert!(graph::diff_schemas(&new, &new).is_empty());
+
+    let dot_content = graph::write_diff_dot_file(&old, &new, &diff, &GraphvizSettings::default());
+    assert!(
+        dot_content.contains("|<font color='green3'>email</font>|<font color='red'>fax</font>}")
+    );
+    assert!(dot_content.contains("tooltip=\"tags\", color=\"green3\""));
+    assert!(dot_content.contains("tooltip=\"legacy\", color=\"red\""));
+    assert!(dot_content.contains("[label=\"(post_id, id)\", color=\"green3\""));
+    assert!(dot_content.contains("[label=\"(user_id, id)\", color=\"red\""));
+}
+
+#[test]
+fn test_match_pattern() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "orders",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "invoices",
+            vec!["id", "order_id", "user_id"],
+            vec![("order_id", "orders", "id"), ("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "invoice_lines",
+            vec!["id", "invoice_id"],
+            vec![("invoice_id", "invoices", "id")],
+        ),
+    ]);
+    let chains = |pattern: &str| -> Vec<String> {
+        let steps = graph::parse_pattern(pattern).unwrap();
+        graph::match_pattern(&graph, &steps)
+            .into_iter()
+            .map(|chain| {
+                let names: Vec<&str> = chain.iter().map(|&n| graph[n].name.as_str()).collect();
+                names.join(" -> ")
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        chains("users -> * -> invoices"),
+        vec!["users -> orders -> invoices"]
+    );
+    assert_eq!(
+        chains("users -> ** -> invoices"),
+        vec!["users -> invoices", "users -> orders -> invoices"]
+    );
+    assert_eq!(chains("Invoice* -> users"), vec!["invoices -> users"]);
+    assert!(chains("users -> invoice_lines").is_empty());
+    assert!(graph::parse_pattern("** -> users").is_err());
+    assert!(graph::parse_pattern("users -> -> orders").is_err());
+}
+
+#[test]
+fn test_exclude_tables() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "audit_log",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "logs.events",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "user_id"],
+            vec![("user_id", "users", "id")],
+        ),
+    ]);
+
+    let patterns = vec!["AUDIT_*".to_string(), "events".to_string()];
+    let graph = graph::exclude_tables(graph, &patterns).unwrap();
+    let names: Vec<&str> = graph.node_weights().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["users", "posts"]);
+    assert_eq!(graph.edge_count(), 1);
+}
+
+#[test]
+fn test_minimum_spanning_tree() {
+    let graph = graph::create_graph(vec![
+        create_sample_dataframe("users", vec!["id"], vec![]),
+        create_sample_dataframe(
+            "posts",
+            vec!["id", "author_id"],
+            vec![("author_id", "users", "id")],
+        ),
+        create_sample_dataframe(
+            "comments",
+            vec!["id", "post_id", "user_id"],
+            vec![("post_id", "posts", "id"), ("user_id", "users", "id")],
+        ),
+    ]);
+    let mst = graph::minimum_spanning_tree(&graph);
+
+    assert_eq!(
+        graph::write_edge_list(&mst),
+        "posts.author_id -> users.id\ncomments.post_id -> posts.id\n"
+    );
+
+    let json = serde_json::to_value(GraphExport::from(&mst)).unwrap();
+    let tables: Vec<&str> = json["tables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|table| table["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tables, vec!["users", "posts", "comments"]);
+    assert_eq!(
+        json["foreign_keys"],
+        serde_json::json!([
+            {
+                "table": "posts",
+                "column": "author_id",
+                "references_table": "users",
+                "references_column": "id"
+            },
+            {
+                "table": "comments",
+                "column": "post_id",
+                "references_table": "posts",
+                "references_column": "id"
+            }
+        ])
+    );
+}
@@ -1,5 +1,7 @@
 mod config;
+mod csv_command;
 mod dataframe;
+mod expr;
 mod graph_command;
 mod graph_module;
 mod sql;
@@ -1,5 +1,9 @@
-use csvg::sql::parse_sql;
+use csvg::graph::{create_graph, find_table};
+use csvg::sql::{
+    parse_sql, parse_sql_schemas, quote_identifier, quote_qualified_name, tables_from_catalog,
+};
 use std::error::Error;
+use std::path::PathBuf;
 
 #[test]
 fn test_parse_sql_with_alter_table() -> Result<(), Box<dyn Error>> {
@@ -20,6 +24,7 @@ fn test_parse_sql_with_alter_table() -> Result<(), Box<dyn Error>> {
 
     let expected_headers = vec!["id".to_string(), "name".to_string()];
     assert_eq!(table.headers, expected_headers);
+    assert_eq!(table.sql_types, vec!["INT", "VARCHAR(255)"]);
 
     assert_eq!(table.primary_key, Some("id".to_string()));
     let expected_foreign_keys = vec![(
@@ -59,6 +64,28 @@ fn test_parse_sql_with_composite_primary_key() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn test_parse_sql_with_composite_foreign_key() -> Result<(), Box<dyn Error>> {
+    let sql = r#"
+        CREATE TABLE lines (
+            tenant_id INT,
+            order_id INT,
+            product_id INT,
+            FOREIGN KEY (tenant_id, order_id) REFERENCES orders (tenant_id, id),
+            FOREIGN KEY (product_id) REFERENCES products (id)
+        );
+    "#;
+
+    let tables = parse_sql(sql)?;
+
+    assert_eq!(tables[0].foreign_keys.len(), 3);
+    assert_eq!(
+        tables[0].composite_foreign_keys,
+        vec![vec!["tenant_id".to_string(), "order_id".to_string()]]
+    );
+    Ok(())
+}
+
 #[test]
 fn test_parse_sql_with_multiple_foreign_keys() -> Result<(), Box<dyn Error>> {
     let sql = r#"
@@ -198,3 +225,131 @@ fn test_parse_sql_with_multiple_comment_on_statements() -> Result<(), Box<dyn Er
 
     Ok(())
 }
+
+#[test]
+fn test_parse_sql_with_schemas() -> Result<(), Box<dyn Error>> {
+    let sql = r#"
+        CREATE TABLE billing.invoices (
+            id INT PRIMARY KEY,
+            customer_id INT,
+            FOREIGN KEY (customer_id) REFERENCES crm.customers(id)
+        );
+        CREATE TABLE crm.customers (id INT PRIMARY KEY);
+        CREATE TABLE payments (id INT PRIMARY KEY, invoice_id INT);
+        ALTER TABLE payments ADD CONSTRAINT fk_invoice
+            FOREIGN KEY (invoice_id) REFERENCES invoices(id);
+    "#;
+
+    let tables = parse_sql(sql)?;
+    let names: Vec<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["billing.invoices", "crm.customers", "payments"]);
+    assert_eq!(tables[0].foreign_keys[0].1, "crm.customers");
+
+    let g = create_graph(tables);
+    assert_eq!(g.edge_count(), 2);
+    assert_eq!(
+        find_table(g.node_weights(), "invoices"),
+        Some(0),
+        "an unqualified name finds the table in any schema"
+    );
+    assert_eq!(find_table(g.node_weights(), "crm.invoices"), None);
+    Ok(())
+}
+
+#[test]
+fn test_parse_sql_schemas() -> Result<(), Box<dyn Error>> {
+    let schemas = vec![
+        (
+            PathBuf::from("orders.sql"),
+            r#"
+            CREATE TABLE orders (id INT PRIMARY KEY, customer_id INT);
+            ALTER TABLE orders ADD CONSTRAINT fk_customer
+                FOREIGN KEY (customer_id) REFERENCES customers(id);
+            "#
+            .to_string(),
+        ),
+        (
+            PathBuf::from("customers.sql"),
+            "CREATE TABLE customers (id INT PRIMARY KEY)".to_string(),
+        ),
+    ];
+
+    let tables = parse_sql_schemas(&schemas)?;
+    let names: Vec<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["orders", "customers"]);
+    assert_eq!(
+        tables[0].foreign_keys,
+        vec![(
+            "customer_id".to_string(),
+            "customers".to_string(),
+            "id".to_string()
+        )],
+        "an ALTER TABLE resolves a table from a later file"
+    );
+    assert_eq!(create_graph(tables).edge_count(), 1);
+
+    let broken = vec![(PathBuf::from("broken.sql"), "CREATE TABLE (".to_string())];
+    let error = parse_sql_schemas(&broken).unwrap_err().to_string();
+    assert!(error.starts_with("broken.sql: "), "{}", error);
+    Ok(())
+}
+
+#[test]
+fn test_tables_from_catalog() {
+    let columns = vec![
+        ("Users".to_string(), "id".to_string()),
+        ("Users".to_string(), "company_id".to_string()),
+        ("companies".to_string(), "id".to_string()),
+        ("companies".to_string(), "name".to_string()),
+    ];
+    let primary_keys = vec![
+        ("Users".to_string(), "id".to_string()),
+        ("companies".to_string(), "id".to_string()),
+    ];
+    let foreign_key = |column: &str, ref_table: &str, ref_column: &str, constraint: &str| {
+        (
+            "Users".to_string(),
+            column.to_string(),
+            ref_table.to_string(),
+            ref_column.to_string(),
+            constraint.to_string(),
+        )
+    };
+    let foreign_keys = vec![
+        foreign_key("Company_Id", "Companies", "ID", "users_company_fk"),
+        foreign_key("company_id", "companies", "id", "users_member_fk"),
+        foreign_key("id", "companies", "name", "users_member_fk"),
+    ];
+
+    let tables = tables_from_catalog(&columns, &primary_keys, &foreign_keys);
+
+    assert_eq!(tables.len(), 2);
+    assert_eq!(tables[0].name, "users");
+    assert_eq!(tables[0].headers, vec!["id", "company_id"]);
+    assert_eq!(tables[0].primary_key, Some("id".to_string()));
+    assert_eq!(
+        tables[0].foreign_keys[0],
+        (
+            "company_id".to_string(),
+            "companies".to_string(),
+            "id".to_string()
+        )
+    );
+    assert_eq!(
+        tables[0].composite_foreign_keys,
+        vec![vec!["company_id".to_string(), "id".to_string()]]
+    );
+    assert_eq!(tables[1].name, "companies");
+    assert_eq!(tables[1].headers, vec!["id", "name"]);
+    assert!(tables[1].foreign_keys.is_empty());
+}
+
+#[test]
+fn test_quote_qualified_name() {
+    assert_eq!(quote_identifier("my \"table\""), "\"my \"\"table\"\"\"");
+    assert_eq!(quote_qualified_name("orders"), "\"orders\"");
+    assert_eq!(
+        quote_qualified_name("billing.invoices"),
+        "\"billing\".\"invoices\""
+    );
+}